@@ -0,0 +1,117 @@
+#ifndef KCC_H
+#define KCC_H
+
+/* Generated from src/ffi.rs with cbindgen, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of checking a single flag or rule
+ */
+typedef enum KccStatus {
+  KCC_STATUS_ENABLED = 0,
+  KCC_STATUS_MODULE = 1,
+  KCC_STATUS_MISSING = 2,
+  /**
+   * The flag doesn't exist in kernel config options
+   */
+  KCC_STATUS_INVALID = 3,
+  KCC_STATUS_RULE_PASS = 4,
+  KCC_STATUS_RULE_FAIL = 5,
+  /**
+   * Compiled in, but switched off at runtime
+   */
+  KCC_STATUS_ADMIN_DISABLED = 6,
+  /**
+   * Required to be disabled, and it is
+   */
+  KCC_STATUS_OFF = 7,
+  /**
+   * Required to be disabled, but enabled
+   */
+  KCC_STATUS_FORBIDDEN = 8,
+  /**
+   * In the config, but not set
+   */
+  KCC_STATUS_DISABLED = 9,
+  /**
+   * Set, but to a value that isn't accepted
+   */
+  KCC_STATUS_WRONG_VALUE = 10,
+  /**
+   * The arguments were invalid, see `kcc_last_error`
+   */
+  KCC_STATUS_ERROR = -1,
+} KccStatus;
+
+/**
+ * A parsed kernel config
+ */
+typedef struct KccConfig KccConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The message of the last error on this thread, or NULL. The string stays valid until the
+ * next failing call on the same thread and must not be freed.
+ */
+const char *kcc_last_error(void);
+
+/**
+ * Parse a config from a buffer: plain text, gzip, xz or zstd, or a kernel image with an
+ * embedded config. Returns NULL on failure.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes.
+ */
+struct KccConfig *kcc_config_parse(const uint8_t *data, size_t len);
+
+/**
+ * Release a config returned by `kcc_config_parse`; NULL is ignored
+ *
+ * # Safety
+ *
+ * `config` must be NULL or a config from `kcc_config_parse` that hasn't been freed.
+ */
+void kcc_config_free(struct KccConfig *config);
+
+/**
+ * Check a flag, with or without its `CONFIG_` prefix, or a rule expression
+ *
+ * # Safety
+ *
+ * `config` must be a live config from `kcc_config_parse` and `flag` a NUL-terminated string.
+ */
+enum KccStatus kcc_check_flag(const struct KccConfig *config, const char *flag);
+
+/**
+ * Check the config against the text of a flags file and return the report as JSON, the
+ * document `--format json` prints. Returns NULL when the flags don't parse.
+ *
+ * # Safety
+ *
+ * `config` must be a live config from `kcc_config_parse` and `flags` a NUL-terminated string.
+ */
+char *kcc_check_flags(const struct KccConfig *config, const char *flags);
+
+/**
+ * Release a string returned by kcc; NULL is ignored
+ *
+ * # Safety
+ *
+ * `text` must be NULL or a string returned by kcc that hasn't been freed.
+ */
+void kcc_string_free(char *text);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* KCC_H */
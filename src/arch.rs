@@ -0,0 +1,107 @@
+use crate::config::{ConfigValue, KernelConfig};
+
+/// Normalize an architecture name to the kernel's naming (e.g. aarch64 -> arm64)
+pub fn normalize(arch: &str) -> String {
+    match arch.to_lowercase().as_str() {
+        "amd64" | "x86-64" => "x86_64".to_string(),
+        "aarch64" => "arm64".to_string(),
+        "i386" | "i486" | "i586" | "i686" | "x86" => "i386".to_string(),
+        "ppc64" | "ppc64le" | "powerpc64le" => "powerpc64".to_string(),
+        "armhf" | "armv7" | "armv7l" => "arm".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// The architectures kcc knows, as `normalize` and `detect_config_arch` name them
+pub const ARCHES: &[&str] = &[
+    "x86_64",
+    "i386",
+    "arm64",
+    "arm",
+    "riscv64",
+    "riscv32",
+    "powerpc64",
+    "powerpc",
+    "s390x",
+    "loongarch64",
+    "mips",
+];
+
+/// Normalize an architecture name given by the user, refusing ones kcc doesn't know
+pub fn parse(arch: &str) -> anyhow::Result<String> {
+    if arch.trim().is_empty() {
+        return Err(anyhow::anyhow!("Missing architecture name"));
+    }
+    let normalized = normalize(arch.trim());
+    if ARCHES.contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(anyhow::anyhow!("Unknown architecture '{}', expected one of {}", arch.trim(), ARCHES.join(", ")))
+    }
+}
+
+/// The kernel's name for an architecture, as given to `make ARCH=`
+pub fn kernel_arch(arch: &str) -> Option<&'static str> {
+    Some(match normalize(arch).as_str() {
+        "x86_64" | "i386" => "x86",
+        "arm64" => "arm64",
+        "arm" => "arm",
+        "riscv64" | "riscv32" => "riscv",
+        "powerpc64" | "powerpc" => "powerpc",
+        "s390x" => "s390",
+        "loongarch64" => "loongarch",
+        "mips" => "mips",
+        _ => return None,
+    })
+}
+
+/// Architecture of the machine kcc is running on
+pub fn host_arch() -> String {
+    normalize(std::env::consts::ARCH)
+}
+
+/// The symbols `detect_config_arch` looks at
+pub const ARCH_SYMBOLS: &[&str] = &[
+    "CONFIG_X86_64",
+    "CONFIG_X86_32",
+    "CONFIG_ARM64",
+    "CONFIG_ARM",
+    "CONFIG_RISCV",
+    "CONFIG_64BIT",
+    "CONFIG_PPC64",
+    "CONFIG_PPC",
+    "CONFIG_S390",
+    "CONFIG_LOONGARCH",
+    "CONFIG_MIPS",
+];
+
+/// Architecture a kernel config was generated for, based on its arch symbols
+pub fn detect_config_arch(config: &KernelConfig) -> Option<String> {
+    let enabled = |symbol: &str| config.get(symbol) == Some(&ConfigValue::Bool(true));
+
+    let arch = if enabled("CONFIG_X86_64") {
+        "x86_64"
+    } else if enabled("CONFIG_X86_32") {
+        "i386"
+    } else if enabled("CONFIG_ARM64") {
+        "arm64"
+    } else if enabled("CONFIG_ARM") {
+        "arm"
+    } else if enabled("CONFIG_RISCV") {
+        if enabled("CONFIG_64BIT") { "riscv64" } else { "riscv32" }
+    } else if enabled("CONFIG_PPC64") {
+        "powerpc64"
+    } else if enabled("CONFIG_PPC") {
+        "powerpc"
+    } else if enabled("CONFIG_S390") {
+        "s390x"
+    } else if enabled("CONFIG_LOONGARCH") {
+        "loongarch64"
+    } else if enabled("CONFIG_MIPS") {
+        "mips"
+    } else {
+        return None;
+    };
+
+    Some(arch.to_string())
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::sysroot;
+
+/// A kernel installed through the system package manager
+pub struct InstalledKernel {
+    pub package: String,
+    pub version: String,
+    pub config: Option<PathBuf>,
+}
+
+/// Enumerate kernels installed as linux-image-* packages via dpkg
+pub fn dpkg_kernels() -> anyhow::Result<Vec<InstalledKernel>> {
+    let output = Command::new("dpkg-query")
+        .args(dpkg_admindir())
+        .args(["-W", "-f", "${Package}\\t${db:Status-Status}\\n", "linux-image-*"])
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run dpkg-query: {}", e))?;
+
+    // dpkg-query exits with 1 when the pattern matches nothing
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() && !stderr.contains("no packages found") {
+        return Err(anyhow::anyhow!(
+            "dpkg-query failed: {}",
+            stderr.trim()
+        ));
+    }
+
+    let mut kernels = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((package, status)) = line.split_once('\t') else {
+            continue;
+        };
+        if status != "installed" {
+            continue;
+        }
+
+        // Skip meta packages like linux-image-amd64 that don't carry a kernel
+        let version = package
+            .trim_start_matches("linux-image-")
+            .trim_start_matches("unsigned-");
+        if !version.starts_with(|c: char| c.is_ascii_digit()) {
+            continue;
+        }
+
+        kernels.push(InstalledKernel {
+            package: package.to_string(),
+            version: version.to_string(),
+            config: find_config(version, || dpkg_files(package)),
+        });
+    }
+
+    Ok(kernels)
+}
+
+/// Enumerate kernels installed as kernel-core (or legacy kernel) packages via rpm
+pub fn rpm_kernels() -> anyhow::Result<Vec<InstalledKernel>> {
+    let output = Command::new("rpm")
+        .args(rpm_root())
+        .args(["-q", "--qf", "%{NAME}-%{VERSION}-%{RELEASE}.%{ARCH}\\t%{VERSION}-%{RELEASE}.%{ARCH}\\n"])
+        .args(["kernel-core", "kernel"])
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run rpm: {}", e))?;
+
+    // rpm exits non-zero and prints "package ... is not installed" for each missing name
+    let mut kernels = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((package, version)) = line.split_once('\t') else {
+            continue;
+        };
+        // Both kernel and kernel-core can be installed for the same version
+        if kernels.iter().any(|k: &InstalledKernel| k.version == version) {
+            continue;
+        }
+
+        kernels.push(InstalledKernel {
+            package: package.to_string(),
+            version: version.to_string(),
+            config: find_config(version, || rpm_files(package)),
+        });
+    }
+
+    Ok(kernels)
+}
+
+/// Version of the running kernel, as reported by uname -r
+pub fn running_version() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+fn find_config(version: &str, package_files: impl FnOnce() -> Vec<String>) -> Option<PathBuf> {
+    let boot_config = sysroot::path(&format!("/boot/config-{}", version));
+    if boot_config.exists() {
+        return Some(boot_config);
+    }
+
+    // Fall back to whatever config file the package itself ships,
+    // e.g. /lib/modules/<version>/config on Fedora
+    package_files()
+        .into_iter()
+        .find(|file| file.starts_with("/boot/config-") || (file.contains("/modules/") && file.ends_with("/config")))
+        .map(|file| sysroot::path(&file))
+        .filter(|path| path.exists())
+}
+
+fn dpkg_files(package: &str) -> Vec<String> {
+    Command::new("dpkg")
+        .args(dpkg_admindir())
+        .args(["-L", package])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+fn rpm_files(package: &str) -> Vec<String> {
+    Command::new("rpm")
+        .args(rpm_root())
+        .args(["-ql", package])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// Point dpkg at the package database under --root
+fn dpkg_admindir() -> Option<String> {
+    sysroot::get().map(|_| format!("--admindir={}", sysroot::path("/var/lib/dpkg").display()))
+}
+
+/// Point rpm at the package database under --root
+fn rpm_root() -> Vec<String> {
+    sysroot::get().map(|root| vec!["--root".to_string(), root.display().to_string()]).unwrap_or_default()
+}
@@ -0,0 +1,320 @@
+//! Where requirements are checked: the kernel config, or the running system.
+//!
+//! Every requirement has a [`SourceKind`], and the [`Checker`](crate::Checker) hands
+//! it to the [`CheckSource`] of that kind. The config being checked is always
+//! available; runtime sources are registered with `Checker::source`, and requirements
+//! whose kind has no source, e.g. sysctls when checking a config file offline, are
+//! left out of the results. The profiles' runtime probes check their sysctls, boot
+//! parameters and modules through these sources too.
+//!
+//! ```no_run
+//! use kcc::backend::{Cmdline, Modules, SourceKind, Sysctl};
+//! use kcc::{Checker, FlagRequirement, KernelConfig};
+//!
+//! let config = KernelConfig::load("/proc/config.gz")?;
+//! let requirements = [
+//!     FlagRequirement::builder("CONFIG_BPF_SYSCALL").build()?,
+//!     FlagRequirement::builder_for(SourceKind::Sysctl, "kernel.unprivileged_bpf_disabled").accepts("1").accepts("2").build()?,
+//!     FlagRequirement::builder_for(SourceKind::Cmdline, "lockdown").accepts("integrity").build()?,
+//!     FlagRequirement::builder_for(SourceKind::Module, "overlay").build()?,
+//! ];
+//! let checker = Checker::new().source(Cmdline::running()?).source(Sysctl::new()).source(Modules::running());
+//! for (requirement, result) in checker.check(&config, &requirements) {
+//!     println!("{} {}: {}", requirement.kind, result.name, result.status.as_str());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::{self, FlagCheckResult, FlagStatus};
+use crate::config::KernelConfig;
+use crate::kconfig::KnownSymbols;
+use crate::requirement::FlagRequirement;
+use crate::{audit, probes};
+
+/// What a requirement is checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// A `CONFIG_` symbol or rule expression, checked against the kernel config
+    #[default]
+    Config,
+    /// A `name` or `name=value` parameter on the kernel command line
+    Cmdline,
+    /// A sysctl in dotted form, e.g. `kernel.kptr_restrict`
+    Sysctl,
+    /// A file under /sys, e.g. `/sys/kernel/mm/transparent_hugepage/enabled`
+    Sysfs,
+    /// A kernel module, loaded or built in, or installed when the source lists installed modules
+    Module,
+}
+
+impl SourceKind {
+    pub fn is_config(&self) -> bool {
+        *self == SourceKind::Config
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            SourceKind::Config => "config",
+            SourceKind::Cmdline => "cmdline",
+            SourceKind::Sysctl => "sysctl",
+            SourceKind::Sysfs => "sysfs",
+            SourceKind::Module => "module",
+        })
+    }
+}
+
+/// Something requirements of one kind are checked against.
+///
+/// Without accepted values a requirement passes when the setting is on: a config
+/// symbol built in or a module, a command line parameter or module present, a
+/// sysctl or sysfs attribute that exists and isn't `0`. With accepted values it
+/// passes when the current value is one of them. Settings that don't exist at all,
+/// like a sysctl the kernel doesn't have, are reported as invalid.
+pub trait CheckSource: Send + Sync {
+    /// The kind of requirements this source answers
+    fn kind(&self) -> SourceKind;
+
+    /// Check a requirement of this source's kind
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult;
+}
+
+/// The config being checked, and the symbols flags are validated against
+pub(crate) struct ConfigCheck<'a> {
+    pub config: &'a KernelConfig,
+    pub validation: Option<&'a dyn KnownSymbols>,
+}
+
+impl CheckSource for ConfigCheck<'_> {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Config
+    }
+
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult {
+        let mut result = check::check_config(self.config, None, &requirement.name);
+        // Flags that must be disabled aren't validated, as in `check_config`
+        let forbids = requirement.accepted.iter().any(|value| value == "n");
+        if !requirement.is_rule() && !forbids && self.validation.is_some_and(|symbols| !symbols.knows(&result.name)) {
+            result.status = FlagStatus::Invalid;
+        } else if !requirement.accepted.is_empty() {
+            result.status = check::accepted_status(self.config, &result.name, &requirement.accepted);
+        }
+        result
+    }
+}
+
+impl CheckSource for KernelConfig {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Config
+    }
+
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult {
+        ConfigCheck { config: self, validation: None }.check(requirement)
+    }
+}
+
+/// Compare the current value of a runtime setting with what a requirement accepts
+fn check_value(requirement: &FlagRequirement, value: Option<&str>) -> FlagCheckResult {
+    let status = match value.map(str::trim) {
+        None => FlagStatus::Invalid,
+        Some(value) if requirement.accepted.is_empty() && value != "0" => FlagStatus::EnabledInKernel,
+        Some(value) if requirement.accepted.iter().any(|accepted| accepted == value) => FlagStatus::EnabledInKernel,
+        Some(_) => FlagStatus::Missing,
+    };
+    FlagCheckResult { name: requirement.name.clone(), status }
+}
+
+/// Parameters on a kernel command line
+pub struct Cmdline {
+    cmdline: String,
+}
+
+impl Cmdline {
+    /// The running kernel's command line, from /proc/cmdline
+    pub fn running() -> anyhow::Result<Self> {
+        let cmdline = fs::read_to_string("/proc/cmdline")
+            .map_err(|e| anyhow::anyhow!("Failed to read /proc/cmdline: {}", e))?;
+        Ok(Cmdline::new(&cmdline))
+    }
+
+    /// A command line given as text, e.g. from a boot loader entry
+    pub fn new(cmdline: &str) -> Self {
+        Cmdline { cmdline: cmdline.trim().to_string() }
+    }
+
+    /// The value of a parameter, empty for a bare `name`; the last occurrence wins, as in the kernel
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.cmdline.split_whitespace().rev().find_map(|param| match param.split_once('=') {
+            Some((key, value)) if key == name => Some(value),
+            None if param == name => Some(""),
+            _ => None,
+        })
+    }
+}
+
+impl CheckSource for Cmdline {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Cmdline
+    }
+
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult {
+        let status = match self.get(&requirement.name) {
+            Some(_) if requirement.accepted.is_empty() => FlagStatus::EnabledInKernel,
+            Some(value) if requirement.accepted.iter().any(|accepted| accepted == value) => FlagStatus::EnabledInKernel,
+            // A parameter that isn't given is missing rather than invalid: the kernel may still know it
+            _ => FlagStatus::Missing,
+        };
+        FlagCheckResult { name: requirement.name.clone(), status }
+    }
+}
+
+/// Sysctls, read from /proc/sys
+pub struct Sysctl {
+    root: PathBuf,
+}
+
+impl Default for Sysctl {
+    fn default() -> Self {
+        Sysctl::under("/proc/sys")
+    }
+}
+
+impl Sysctl {
+    /// The running kernel's sysctls
+    pub fn new() -> Self {
+        Sysctl::default()
+    }
+
+    /// Sysctls under another directory laid out like /proc/sys
+    pub fn under(root: impl Into<PathBuf>) -> Self {
+        Sysctl { root: root.into() }
+    }
+
+    /// The value of a sysctl in dotted form, or `None` when the kernel doesn't have it
+    pub fn get(&self, name: &str) -> Option<String> {
+        fs::read_to_string(self.root.join(name.replace('.', "/"))).ok()
+    }
+}
+
+impl CheckSource for Sysctl {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Sysctl
+    }
+
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult {
+        check_value(requirement, self.get(&requirement.name).as_deref())
+    }
+}
+
+/// Attributes under /sys
+pub struct Sysfs {
+    root: PathBuf,
+}
+
+impl Default for Sysfs {
+    fn default() -> Self {
+        Sysfs::under("/")
+    }
+}
+
+impl Sysfs {
+    /// The running system's /sys
+    pub fn new() -> Self {
+        Sysfs::default()
+    }
+
+    /// Attributes of another root, e.g. a copy of a machine's /sys taken for support
+    pub fn under(root: impl Into<PathBuf>) -> Self {
+        Sysfs { root: root.into() }
+    }
+
+    /// The value of an attribute given by its absolute path; for attributes listing the
+    /// choices with the current one in brackets, like `always [madvise] never`, the current one
+    pub fn get(&self, path: &str) -> Option<String> {
+        let value = fs::read_to_string(self.root.join(path.trim_start_matches('/'))).ok()?;
+        let selected = value.split_whitespace().find_map(|choice| choice.strip_prefix('[')?.strip_suffix(']'));
+        Some(selected.map_or(value.trim().to_string(), str::to_string))
+    }
+}
+
+impl CheckSource for Sysfs {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Sysfs
+    }
+
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult {
+        check_value(requirement, self.get(&requirement.name).as_deref())
+    }
+}
+
+/// Kernel modules: the loaded ones, as `lsmod` lists them, the built-in ones, and
+/// optionally the ones installed for the kernel, which load on demand
+pub struct Modules {
+    loaded: HashSet<String>,
+    builtin: HashSet<String>,
+    installed: HashSet<String>,
+}
+
+impl Modules {
+    /// The running kernel's modules, from /proc/modules and its modules.builtin
+    pub fn running() -> Self {
+        // Without module support there's no /proc/modules, and nothing is loaded
+        let loaded = fs::read_to_string("/proc/modules").unwrap_or_default();
+        let builtin = audit::running_version()
+            .and_then(|release| fs::read_to_string(Path::new("/lib/modules").join(release).join("modules.builtin")).ok())
+            .unwrap_or_default();
+        Modules::new(&loaded, &builtin)
+    }
+
+    /// Modules from the text of /proc/modules (or `lsmod` output) and of modules.builtin
+    pub fn new(loaded: &str, builtin: &str) -> Self {
+        Modules {
+            loaded: loaded
+                .lines()
+                .filter_map(|line| line.split_whitespace().next())
+                .filter(|name| *name != "Module")
+                .map(probes::module_name)
+                .collect(),
+            builtin: builtin.lines().filter(|line| !line.trim().is_empty()).map(probes::module_name).collect(),
+            installed: HashSet::new(),
+        }
+    }
+
+    /// Also count the modules listed in the text of a modules.dep as present
+    pub fn installed(mut self, modules_dep: &str) -> Self {
+        self.installed = modules_dep
+            .lines()
+            .filter_map(|line| line.split(':').next())
+            .filter(|path| !path.trim().is_empty())
+            .map(probes::module_name)
+            .collect();
+        self
+    }
+}
+
+impl CheckSource for Modules {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Module
+    }
+
+    fn check(&self, requirement: &FlagRequirement) -> FlagCheckResult {
+        let name = probes::module_name(&requirement.name);
+        let status = if self.builtin.contains(&name) {
+            FlagStatus::EnabledInKernel
+        } else if self.loaded.contains(&name) || self.installed.contains(&name) {
+            FlagStatus::EnabledAsModule
+        } else {
+            FlagStatus::Missing
+        };
+        FlagCheckResult { name: requirement.name.clone(), status }
+    }
+}
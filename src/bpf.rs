@@ -0,0 +1,536 @@
+//! Derive kernel requirements from an eBPF ELF object.
+//!
+//! Program types come from libbpf's section naming conventions, map types from
+//! legacy `maps` definitions or BTF-defined `.maps`, and helper calls from the
+//! program instructions. Helper IDs are assigned sequentially as helpers are
+//! added to the kernel, so the highest ID used gives a lower bound for the
+//! kernel version.
+
+use std::fs;
+
+use crate::derive::Requirements;
+
+/// Inspect an eBPF object file and derive the kernel config it needs
+pub fn derive(path: &str) -> anyhow::Result<Requirements> {
+    let data = fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+    let elf = Elf::parse(&data)?;
+
+    if elf.machine != EM_BPF {
+        return Err(anyhow::anyhow!("{} is not an eBPF object (ELF machine {})", path, elf.machine));
+    }
+
+    let mut requirements = Requirements::default();
+    requirements.require("CONFIG_BPF", "eBPF support");
+    requirements.require("CONFIG_BPF_SYSCALL", "bpf() syscall");
+    requirements.require_version("3.19", "bpf() syscall");
+
+    for section in &elf.sections {
+        if section.flags & SHF_EXECINSTR != 0 && section.size > 0 {
+            program_requirements(&section.name, &mut requirements);
+            instruction_requirements(&elf, section, &mut requirements);
+        }
+
+        match section.name.as_str() {
+            ".data" | ".rodata" | ".bss" if section.size > 0 => {
+                requirements.require_version("5.2", "global variables");
+            }
+            name if name == "maps" || name.starts_with("maps/") => {
+                for map_type in legacy_map_types(&elf, section) {
+                    map_requirements(map_type, &mut requirements);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(btf) = elf.section(".BTF").map(|s| elf.section_data(s)) {
+        for map_type in btf_map_types(btf, elf.little_endian)? {
+            map_requirements(map_type, &mut requirements);
+        }
+    }
+
+    // CO-RE relocations need the kernel's own BTF at /sys/kernel/btf/vmlinux
+    if let Some(btf_ext) = elf.section(".BTF.ext").map(|s| elf.section_data(s)) {
+        if has_core_relocations(btf_ext, elf.little_endian) {
+            requirements.require("CONFIG_DEBUG_INFO_BTF", "CO-RE relocations");
+            requirements.require_version("5.4", "CO-RE relocations against kernel BTF");
+        }
+    }
+
+    Ok(requirements)
+}
+
+fn program_requirements(section: &str, req: &mut Requirements) {
+    let kind = section.split('/').next().unwrap_or(section);
+
+    match kind {
+        "kprobe" | "kretprobe" | "ksyscall" | "kretsyscall" => {
+            req.require("CONFIG_KPROBES", "kprobe programs");
+            req.require("CONFIG_KPROBE_EVENTS", "kprobe programs");
+            req.require("CONFIG_BPF_EVENTS", "kprobe programs");
+            req.require_version("4.1", "kprobe programs");
+        }
+        "kprobe.multi" | "kretprobe.multi" => {
+            req.require("CONFIG_FPROBE", "multi-kprobe programs");
+            req.require("CONFIG_BPF_EVENTS", "multi-kprobe programs");
+            req.require_version("5.18", "multi-kprobe programs");
+        }
+        "uprobe" | "uretprobe" | "usdt" => {
+            req.require("CONFIG_UPROBES", "uprobe programs");
+            req.require("CONFIG_UPROBE_EVENTS", "uprobe programs");
+            req.require("CONFIG_BPF_EVENTS", "uprobe programs");
+            req.require_version("4.3", "uprobe programs");
+        }
+        "tracepoint" | "tp" => {
+            req.require("CONFIG_TRACEPOINTS", "tracepoint programs");
+            req.require("CONFIG_BPF_EVENTS", "tracepoint programs");
+            req.require_version("4.7", "tracepoint programs");
+        }
+        "raw_tracepoint" | "raw_tp" => {
+            req.require("CONFIG_BPF_EVENTS", "raw tracepoint programs");
+            req.require_version("4.17", "raw tracepoint programs");
+        }
+        "tp_btf" => {
+            req.require("CONFIG_DEBUG_INFO_BTF", "BTF tracepoint programs");
+            req.require("CONFIG_BPF_EVENTS", "BTF tracepoint programs");
+            req.require_version("5.5", "BTF tracepoint programs");
+        }
+        "fentry" | "fexit" | "fmod_ret" | "freplace" => {
+            req.require("CONFIG_DEBUG_INFO_BTF", "fentry/fexit programs");
+            req.require("CONFIG_BPF_JIT", "fentry/fexit programs");
+            req.require("CONFIG_FUNCTION_TRACER", "fentry/fexit programs");
+            req.require_version(if kind == "fmod_ret" { "5.7" } else { "5.5" }, "fentry/fexit programs");
+        }
+        "lsm" | "lsm.s" => {
+            req.require("CONFIG_BPF_LSM", "LSM programs");
+            req.require("CONFIG_DEBUG_INFO_BTF", "LSM programs");
+            req.require_version("5.7", "LSM programs");
+        }
+        "perf_event" => {
+            req.require("CONFIG_PERF_EVENTS", "perf event programs");
+            req.require("CONFIG_BPF_EVENTS", "perf event programs");
+            req.require_version("4.9", "perf event programs");
+        }
+        "xdp" | "xdp.frags" => {
+            req.require_version("4.8", "XDP programs");
+        }
+        "tc" | "classifier" => {
+            req.require("CONFIG_NET_CLS_BPF", "tc classifier programs");
+            req.require("CONFIG_NET_CLS_ACT", "tc classifier programs");
+            req.require_version("4.1", "tc classifier programs");
+        }
+        "tcx" | "netkit" => {
+            req.require("CONFIG_NET_XGRESS", "tcx programs");
+            req.require_version("6.6", "tcx programs");
+        }
+        "action" => {
+            req.require("CONFIG_NET_ACT_BPF", "tc action programs");
+            req.require_version("4.1", "tc action programs");
+        }
+        "socket" => {}
+        "sockops" | "cgroup_skb" | "cgroup" => {
+            req.require("CONFIG_CGROUP_BPF", "cgroup programs");
+            req.require_version(if kind == "sockops" { "4.13" } else { "4.10" }, "cgroup programs");
+        }
+        "sk_skb" | "sk_msg" => {
+            req.require("CONFIG_BPF_STREAM_PARSER", "sockmap programs");
+            req.require_version(if kind == "sk_msg" { "4.17" } else { "4.14" }, "sockmap programs");
+        }
+        "lwt_in" | "lwt_out" | "lwt_xmit" | "lwt_seg6local" => {
+            req.require("CONFIG_LWTUNNEL_BPF", "lightweight tunnel programs");
+            req.require_version("4.10", "lightweight tunnel programs");
+        }
+        "lirc_mode2" => {
+            req.require("CONFIG_BPF_LIRC_MODE2", "IR decoder programs");
+            req.require_version("4.18", "IR decoder programs");
+        }
+        "sk_reuseport" => req.require_version("4.19", "SO_REUSEPORT programs"),
+        "flow_dissector" => req.require_version("4.20", "flow dissector programs"),
+        "struct_ops" | "struct_ops.s" => {
+            req.require("CONFIG_DEBUG_INFO_BTF", "struct_ops programs");
+            req.require("CONFIG_BPF_JIT", "struct_ops programs");
+            req.require_version("5.6", "struct_ops programs");
+        }
+        "iter" | "iter.s" => {
+            req.require("CONFIG_DEBUG_INFO_BTF", "iterator programs");
+            req.require_version("5.8", "iterator programs");
+        }
+        "sk_lookup" => req.require_version("5.9", "socket lookup programs"),
+        "syscall" => req.require_version("5.14", "syscall programs"),
+        "netfilter" => {
+            req.require("CONFIG_NETFILTER_BPF_LINK", "netfilter programs");
+            req.require_version("6.4", "netfilter programs");
+        }
+        _ => {}
+    }
+}
+
+fn instruction_requirements(elf: &Elf, section: &Section, req: &mut Requirements) {
+    let code = elf.section_data(section);
+
+    for insn in code.chunks_exact(8) {
+        // BPF_JMP | BPF_CALL
+        if insn[0] != 0x85 {
+            continue;
+        }
+        let src_reg = if elf.little_endian { insn[1] >> 4 } else { insn[1] & 0x0f };
+        let imm = elf.u32(&insn[4..8]) as i32;
+
+        match src_reg {
+            0 => helper_requirements(imm, req),
+            1 => req.require_version("4.16", "BPF-to-BPF function calls"),
+            2 => {
+                req.require("CONFIG_DEBUG_INFO_BTF", "kfunc calls");
+                req.require("CONFIG_BPF_JIT", "kfunc calls");
+                req.require_version("5.13", "kfunc calls");
+            }
+            _ => {}
+        }
+    }
+}
+
+/// First helper ID added in each kernel release
+const HELPER_VERSIONS: &[(i32, &str)] = &[
+    (4, "4.1"), (12, "4.2"), (17, "4.3"), (23, "4.4"), (26, "4.5"), (27, "4.6"),
+    (31, "4.8"), (37, "4.9"), (42, "4.10"), (45, "4.11"), (46, "4.12"), (48, "4.13"),
+    (51, "4.14"), (54, "4.15"), (58, "4.16"), (60, "4.17"), (65, "4.18"), (81, "4.19"),
+    (84, "4.20"), (91, "5.0"), (93, "5.1"), (99, "5.2"), (109, "5.3"), (111, "5.5"),
+    (119, "5.6"), (122, "5.7"), (125, "5.8"), (136, "5.9"), (142, "5.10"), (156, "5.11"),
+    (163, "5.12"), (164, "5.13"), (166, "5.14"), (169, "5.15"), (176, "5.16"), (180, "5.17"),
+    (186, "5.18"),
+];
+
+fn helper_requirements(id: i32, req: &mut Requirements) {
+    if let Some((_, version)) = HELPER_VERSIONS.iter().rev().find(|(first, _)| id >= *first) {
+        req.require_version(version, &format!("BPF helper #{}", id));
+    }
+
+    match id {
+        58 => req.require("CONFIG_BPF_KPROBE_OVERRIDE", "bpf_override_return helper"),
+        66 => req.require("CONFIG_XFRM", "bpf_skb_get_xfrm_state helper"),
+        73..=76 => req.require("CONFIG_IPV6_SEG6_BPF", "SRv6 helpers"),
+        77 | 78 | 92 => req.require("CONFIG_BPF_LIRC_MODE2", "rc helpers"),
+        161 => req.require("CONFIG_IMA", "bpf_ima_inode_hash helper"),
+        _ => {}
+    }
+}
+
+fn map_requirements(map_type: u32, req: &mut Requirements) {
+    let (name, version) = match map_type {
+        1 | 2 => return,
+        3 => ("prog array", "4.2"),
+        4 => ("perf event array", "4.3"),
+        5 | 6 => ("per-CPU hash/array", "4.6"),
+        7 => ("stack trace", "4.6"),
+        8 => ("cgroup array", "4.8"),
+        9 | 10 => ("LRU hash", "4.10"),
+        11 => ("LPM trie", "4.11"),
+        12 | 13 => ("map-in-map", "4.12"),
+        14 => ("devmap", "4.14"),
+        15 => ("sockmap", "4.14"),
+        16 => ("cpumap", "4.15"),
+        17 => ("xskmap", "4.18"),
+        18 => ("sockhash", "4.18"),
+        19 => ("cgroup storage", "4.19"),
+        20 => ("reuseport sockarray", "4.19"),
+        21 => ("per-CPU cgroup storage", "4.20"),
+        22 | 23 => ("queue/stack", "4.20"),
+        24 => ("socket storage", "5.2"),
+        25 => ("devmap hash", "5.4"),
+        26 => ("struct_ops", "5.6"),
+        27 => ("ring buffer", "5.8"),
+        28 => ("inode storage", "5.10"),
+        29 => ("task storage", "5.11"),
+        30 => ("bloom filter", "5.16"),
+        31 => ("user ring buffer", "6.1"),
+        32 => ("cgroup local storage", "6.2"),
+        33 => ("arena", "6.9"),
+        _ => return,
+    };
+    let reason = format!("{} maps", name);
+    req.require_version(version, &reason);
+
+    match map_type {
+        8 | 19 | 21 | 32 => req.require("CONFIG_CGROUP_BPF", &reason),
+        15 | 18 => req.require("CONFIG_BPF_STREAM_PARSER", &reason),
+        17 => req.require("CONFIG_XDP_SOCKETS", &reason),
+        28 => req.require("CONFIG_BPF_LSM", &reason),
+        26 => req.require("CONFIG_DEBUG_INFO_BTF", &reason),
+        _ => {}
+    }
+}
+
+/// Map types from legacy `struct bpf_map_def` definitions
+fn legacy_map_types(elf: &Elf, section: &Section) -> Vec<u32> {
+    let data = elf.section_data(section);
+    elf.symbols
+        .iter()
+        .filter(|sym| sym.section == section.index)
+        .filter_map(|sym| data.get(sym.value as usize..sym.value as usize + 4))
+        .map(|bytes| elf.u32(bytes))
+        .collect()
+}
+
+const BTF_KIND_INT: u32 = 1;
+const BTF_KIND_PTR: u32 = 2;
+const BTF_KIND_ARRAY: u32 = 3;
+const BTF_KIND_STRUCT: u32 = 4;
+const BTF_KIND_UNION: u32 = 5;
+const BTF_KIND_ENUM: u32 = 6;
+const BTF_KIND_FUNC_PROTO: u32 = 13;
+const BTF_KIND_VAR: u32 = 14;
+const BTF_KIND_DATASEC: u32 = 15;
+const BTF_KIND_DECL_TAG: u32 = 17;
+const BTF_KIND_ENUM64: u32 = 19;
+
+struct BtfType {
+    name: String,
+    kind: u32,
+    /// Size or referenced type, depending on the kind
+    size_or_type: u32,
+    /// ARRAY: element count; STRUCT: (member name, type); DATASEC: variable types
+    nelems: u32,
+    members: Vec<(String, u32)>,
+}
+
+/// Map types from BTF-defined maps: each variable in the `.maps` DATASEC is a struct
+/// whose `type` member is declared as `int (*type)[BPF_MAP_TYPE_...]`
+fn btf_map_types(btf: &[u8], little_endian: bool) -> anyhow::Result<Vec<u32>> {
+    let types = parse_btf(btf, little_endian)?;
+    let get = |id: u32| types.get(id.checked_sub(1)? as usize);
+
+    let mut map_types = Vec::new();
+    for datasec in types.iter().filter(|t| t.kind == BTF_KIND_DATASEC && t.name == ".maps") {
+        for (_, var_id) in &datasec.members {
+            let Some(var) = get(*var_id).filter(|t| t.kind == BTF_KIND_VAR) else {
+                continue;
+            };
+            let Some(def) = get(var.size_or_type).filter(|t| t.kind == BTF_KIND_STRUCT) else {
+                continue;
+            };
+            let map_type = def
+                .members
+                .iter()
+                .find(|(name, _)| name == "type")
+                .and_then(|(_, ptr)| get(*ptr).filter(|t| t.kind == BTF_KIND_PTR))
+                .and_then(|ptr| get(ptr.size_or_type).filter(|t| t.kind == BTF_KIND_ARRAY))
+                .map(|array| array.nelems);
+            map_types.extend(map_type);
+        }
+    }
+
+    Ok(map_types)
+}
+
+fn parse_btf(btf: &[u8], little_endian: bool) -> anyhow::Result<Vec<BtfType>> {
+    let read = |offset: usize| -> anyhow::Result<u32> {
+        let bytes: [u8; 4] = btf
+            .get(offset..offset + 4)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| anyhow::anyhow!("Truncated BTF data"))?;
+        Ok(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    };
+
+    let hdr_len = read(4)? as usize;
+    let type_start = hdr_len + read(8)? as usize;
+    let type_end = type_start + read(12)? as usize;
+    let str_start = hdr_len + read(16)? as usize;
+    let name = |offset: u32| -> String {
+        btf.get(str_start + offset as usize..)
+            .and_then(|s| s.split(|&b| b == 0).next())
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .unwrap_or_default()
+    };
+
+    let mut types = Vec::new();
+    let mut pos = type_start;
+    while pos < type_end {
+        let name_off = read(pos)?;
+        let info = read(pos + 4)?;
+        let size_or_type = read(pos + 8)?;
+        let kind = (info >> 24) & 0x1f;
+        let vlen = (info & 0xffff) as usize;
+        pos += 12;
+
+        let mut btf_type = BtfType {
+            name: name(name_off),
+            kind,
+            size_or_type,
+            nelems: 0,
+            members: Vec::new(),
+        };
+
+        match kind {
+            BTF_KIND_INT | BTF_KIND_VAR | BTF_KIND_DECL_TAG => pos += 4,
+            BTF_KIND_ARRAY => {
+                btf_type.nelems = read(pos + 8)?;
+                pos += 12;
+            }
+            BTF_KIND_STRUCT | BTF_KIND_UNION => {
+                for i in 0..vlen {
+                    let member = pos + i * 12;
+                    btf_type.members.push((name(read(member)?), read(member + 4)?));
+                }
+                pos += vlen * 12;
+            }
+            BTF_KIND_ENUM | BTF_KIND_FUNC_PROTO => pos += vlen * 8,
+            BTF_KIND_DATASEC => {
+                for i in 0..vlen {
+                    btf_type.members.push((String::new(), read(pos + i * 12)?));
+                }
+                pos += vlen * 12;
+            }
+            BTF_KIND_ENUM64 => pos += vlen * 12,
+            _ => {}
+        }
+
+        types.push(btf_type);
+    }
+
+    Ok(types)
+}
+
+/// Returns true if .BTF.ext carries CO-RE relocation records
+fn has_core_relocations(btf_ext: &[u8], little_endian: bool) -> bool {
+    let read = |offset: usize| -> Option<u32> {
+        let bytes: [u8; 4] = btf_ext.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    };
+
+    // The core_relo_off/core_relo_len fields only exist in headers of at least 32 bytes
+    match read(4) {
+        Some(hdr_len) if hdr_len >= 32 => read(28).is_some_and(|len| len > 0),
+        _ => false,
+    }
+}
+
+const EM_BPF: u16 = 247;
+const SHF_EXECINSTR: u64 = 0x4;
+const SHT_SYMTAB: u32 = 2;
+const SHT_NOBITS: u32 = 8;
+
+struct Section {
+    index: u16,
+    name: String,
+    kind: u32,
+    flags: u64,
+    offset: u64,
+    size: u64,
+}
+
+struct ElfSymbol {
+    section: u16,
+    value: u64,
+}
+
+/// Just enough of a 64-bit ELF reader to inspect eBPF objects
+struct Elf<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+    machine: u16,
+    sections: Vec<Section>,
+    symbols: Vec<ElfSymbol>,
+}
+
+impl<'a> Elf<'a> {
+    fn parse(data: &'a [u8]) -> anyhow::Result<Self> {
+        if data.len() < 64 || &data[..4] != b"\x7fELF" {
+            return Err(anyhow::anyhow!("Not an ELF file"));
+        }
+        if data[4] != 2 {
+            return Err(anyhow::anyhow!("Only 64-bit ELF objects are supported"));
+        }
+
+        let mut elf = Elf {
+            data,
+            little_endian: data[5] == 1,
+            machine: 0,
+            sections: Vec::new(),
+            symbols: Vec::new(),
+        };
+        elf.machine = elf.u16(elf.bytes(18, 2)?);
+
+        let shoff = elf.u64(elf.bytes(40, 8)?) as usize;
+        let shentsize = elf.u16(elf.bytes(58, 2)?) as usize;
+        let shnum = elf.u16(elf.bytes(60, 2)?) as usize;
+        let shstrndx = elf.u16(elf.bytes(62, 2)?) as usize;
+
+        let mut name_offsets = Vec::new();
+        for index in 0..shnum {
+            let header = elf.bytes(shoff + index * shentsize, 64)?;
+            name_offsets.push(elf.u32(&header[0..4]));
+            elf.sections.push(Section {
+                index: index as u16,
+                name: String::new(),
+                kind: elf.u32(&header[4..8]),
+                flags: elf.u64(&header[8..16]),
+                offset: elf.u64(&header[24..32]),
+                size: elf.u64(&header[32..40]),
+            });
+        }
+
+        if let Some(strtab) = elf.sections.get(shstrndx) {
+            let strtab = elf.section_data(strtab);
+            let names: Vec<String> = name_offsets.iter().map(|&off| c_string(strtab, off as usize)).collect();
+            for (section, name) in elf.sections.iter_mut().zip(names) {
+                section.name = name;
+            }
+        }
+
+        if let Some(symtab) = elf.sections.iter().find(|s| s.kind == SHT_SYMTAB) {
+            let symbols = elf
+                .section_data(symtab)
+                .chunks_exact(24)
+                .map(|sym| ElfSymbol {
+                    section: elf.u16(&sym[6..8]),
+                    value: elf.u64(&sym[8..16]),
+                })
+                .collect();
+            elf.symbols = symbols;
+        }
+
+        Ok(elf)
+    }
+
+    fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    fn section_data(&self, section: &Section) -> &'a [u8] {
+        // SHT_NOBITS sections like .bss have no file contents
+        if section.kind == SHT_NOBITS {
+            return &[];
+        }
+        self.data
+            .get(section.offset as usize..(section.offset + section.size) as usize)
+            .unwrap_or(&[])
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> anyhow::Result<&'a [u8]> {
+        self.data
+            .get(offset..offset + len)
+            .ok_or_else(|| anyhow::anyhow!("Truncated ELF file"))
+    }
+
+    fn u16(&self, b: &[u8]) -> u16 {
+        let b = [b[0], b[1]];
+        if self.little_endian { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) }
+    }
+
+    fn u32(&self, b: &[u8]) -> u32 {
+        let b = [b[0], b[1], b[2], b[3]];
+        if self.little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) }
+    }
+
+    fn u64(&self, b: &[u8]) -> u64 {
+        let b = [b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]];
+        if self.little_endian { u64::from_le_bytes(b) } else { u64::from_be_bytes(b) }
+    }
+}
+
+fn c_string(data: &[u8], offset: usize) -> String {
+    data.get(offset..)
+        .and_then(|s| s.split(|&b| b == 0).next())
+        .map(|s| String::from_utf8_lossy(s).to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A kernel config generated by an embedded build system, together with the
+/// config fragments that were applied to produce it
+pub struct BuildTree {
+    pub system: &'static str,
+    pub config: PathBuf,
+    pub fragments: Vec<PathBuf>,
+}
+
+/// Where a flag is set within a build tree's fragments
+pub struct Attribution {
+    pub fragment: PathBuf,
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// Locate the kernel .config and applied fragments inside a Yocto build directory
+pub fn locate_yocto(builddir: &str) -> anyhow::Result<BuildTree> {
+    let work = Path::new(builddir).join("tmp").join("work");
+    if !work.is_dir() {
+        return Err(anyhow::anyhow!("Not a Yocto build directory (no tmp/work): {}", builddir));
+    }
+
+    // tmp/work/<arch>/<recipe>/<version>/<build dir>/.config
+    let mut configs = Vec::new();
+    find_files(&work, 5, &mut |path| {
+        path.file_name().is_some_and(|n| n == ".config") && is_kernel_build_config(path)
+    }, &mut configs);
+
+    let config = newest(configs)
+        .ok_or_else(|| anyhow::anyhow!("No generated kernel .config found under {}", work.display()))?;
+
+    // Fragments from SRC_URI are unpacked next to the build directory in WORKDIR
+    let mut fragments = Vec::new();
+    if let Some(workdir) = config.parent().and_then(Path::parent) {
+        find_files(workdir, 2, &mut |path| {
+            path.extension().is_some_and(|e| e == "cfg")
+        }, &mut fragments);
+    }
+    fragments.sort();
+
+    Ok(BuildTree { system: "Yocto", config, fragments })
+}
+
+/// Locate the kernel .config and applied fragments inside a Buildroot output directory
+pub fn locate_buildroot(builddir: &str) -> anyhow::Result<BuildTree> {
+    let builddir = Path::new(builddir);
+    let br_config = fs::read_to_string(builddir.join(".config"))
+        .map_err(|e| anyhow::anyhow!("Not a Buildroot output directory (no .config): {}", e))?;
+
+    let mut configs = Vec::new();
+    if let Ok(entries) = fs::read_dir(builddir.join("build")) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Skip linux-headers, linux-firmware, linux-tools and friends
+            let version = name.strip_prefix("linux-").unwrap_or_default();
+            if version.starts_with(|c: char| c.is_ascii_digit()) || name == "linux-custom" {
+                let config = entry.path().join(".config");
+                if config.exists() {
+                    configs.push(config);
+                }
+            }
+        }
+    }
+
+    let config = newest(configs).ok_or_else(|| {
+        anyhow::anyhow!("No generated kernel .config found under {}", builddir.join("build").display())
+    })?;
+
+    let mut fragments = Vec::new();
+    for key in ["BR2_LINUX_KERNEL_CUSTOM_CONFIG_FILE", "BR2_LINUX_KERNEL_CONFIG_FRAGMENT_FILES"] {
+        for path in buildroot_setting(&br_config, key).split_whitespace() {
+            let path = if Path::new(path).is_absolute() {
+                PathBuf::from(path)
+            } else {
+                builddir.join(path)
+            };
+            if path.exists() {
+                fragments.push(path);
+            } else {
+                eprintln!("⚠️  {} entry not found: {}", key, path.display());
+            }
+        }
+    }
+
+    Ok(BuildTree { system: "Buildroot", config, fragments })
+}
+
+impl BuildTree {
+    /// Find the fragment line that sets (or unsets) a flag, i.e. the place a fix belongs
+    pub fn attribute(&self, flag: &str) -> Option<Attribution> {
+        let assignment = format!("{}=", flag);
+        let not_set = format!("# {} is not set", flag);
+
+        // Later fragments override earlier ones, so the last mention wins
+        self.fragments.iter().rev().find_map(|fragment| {
+            let content = fs::read_to_string(fragment).ok()?;
+            content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.starts_with(&assignment) || line.trim() == not_set)
+                .last()
+                .map(|(i, line)| Attribution {
+                    fragment: fragment.clone(),
+                    line_number: i + 1,
+                    line: line.to_string(),
+                })
+        })
+    }
+}
+
+fn buildroot_setting(br_config: &str, key: &str) -> String {
+    let prefix = format!("{}=", key);
+    br_config
+        .lines()
+        .find_map(|line| line.strip_prefix(&prefix))
+        .map(|value| value.trim_matches('"').to_string())
+        .unwrap_or_default()
+}
+
+fn is_kernel_build_config(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .map(|content| content.lines().take(5).any(|line| line.ends_with("Kernel Configuration")))
+        .unwrap_or(false)
+}
+
+fn find_files(dir: &Path, depth: usize, matches: &mut dyn FnMut(&Path) -> bool, found: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if depth > 0 {
+                find_files(&path, depth - 1, matches, found);
+            }
+        } else if matches(&path) {
+            found.push(path);
+        }
+    }
+}
+
+fn newest(paths: Vec<PathBuf>) -> Option<PathBuf> {
+    paths
+        .into_iter()
+        .max_by_key(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+}
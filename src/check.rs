@@ -0,0 +1,605 @@
+//! Checking flags and rules against a kernel config, and the flags-file parser.
+
+use std::cmp::Ordering;
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigValue, KernelConfig, Tristate};
+use crate::requirement::Severity;
+use crate::{arch, expr};
+
+/// Outcome of checking a single flag or rule; serialized as in reports, e.g. `enabled` or `rule-fail`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlagStatus {
+    #[serde(rename = "enabled")]
+    EnabledInKernel,
+    #[serde(rename = "module")]
+    EnabledAsModule,
+    #[serde(rename = "missing")]
+    Missing, // Not in the config at all, often a wrong name or a kernel without the option
+    #[serde(rename = "disabled")]
+    Disabled, // In the config, but `is not set`
+    #[serde(rename = "wrong-value")]
+    WrongValue, // Set, but to a value that isn't accepted, e.g. `m` where `y` is required
+    #[serde(rename = "invalid")]
+    Invalid, // Flag doesn't exist in kernel config options
+    #[serde(rename = "rule-pass")]
+    RulePassed,
+    #[serde(rename = "rule-fail")]
+    RuleFailed,
+    #[serde(rename = "admin-disabled")]
+    AdminDisabled, // Compiled in, but switched off at runtime (e.g. kernel.io_uring_disabled)
+    #[serde(rename = "off")]
+    Off, // Required to be disabled, and it is
+    #[serde(rename = "forbidden")]
+    Forbidden, // Required to be disabled, but enabled
+}
+
+impl FlagStatus {
+    /// The status as written in reports, e.g. `enabled` or `rule-fail`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlagStatus::EnabledInKernel => "enabled",
+            FlagStatus::EnabledAsModule => "module",
+            FlagStatus::Missing => "missing",
+            FlagStatus::Disabled => "disabled",
+            FlagStatus::WrongValue => "wrong-value",
+            FlagStatus::Invalid => "invalid",
+            FlagStatus::RulePassed => "rule-pass",
+            FlagStatus::RuleFailed => "rule-fail",
+            FlagStatus::AdminDisabled => "admin-disabled",
+            FlagStatus::Off => "off",
+            FlagStatus::Forbidden => "forbidden",
+        }
+    }
+}
+
+/// A checked flag, normalized to its `CONFIG_` name, or a rule as written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlagCheckResult {
+    pub name: String,
+    pub status: FlagStatus,
+}
+
+impl FlagCheckResult {
+    /// The result as printed by the CLI, with emoji and colors
+    pub fn format_output(&self) -> String {
+        if self.status == FlagStatus::EnabledInKernel {
+            format!("✅ {}", self.name.green())
+        } else if self.status == FlagStatus::EnabledAsModule {
+            format!("✅ {} (as module)", self.name.green())
+        } else if self.status == FlagStatus::Missing {
+            format!("❌ {}", self.name.red())
+        } else if self.status == FlagStatus::Disabled {
+            format!("❌ {} (not set)", self.name.red())
+        } else if self.status == FlagStatus::WrongValue {
+            format!("❌ {} (set to another value)", self.name.red())
+        } else if self.status == FlagStatus::RulePassed {
+            format!("✅ {}", self.name.green())
+        } else if self.status == FlagStatus::RuleFailed {
+            format!("❌ {} (rule not satisfied)", self.name.red())
+        } else if self.status == FlagStatus::AdminDisabled {
+            format!("⛔ {} (compiled in but administratively disabled)", self.name.red())
+        } else if self.status == FlagStatus::Off {
+            format!("✅ {} (disabled)", self.name.green())
+        } else if self.status == FlagStatus::Forbidden {
+            format!("🚫 {} (enabled but must be disabled)", self.name.red())
+        } else {
+            format!("⚠️  {} (invalid flag)", self.name.yellow())
+        }
+    }
+}
+
+/// Parse a flags file, naming it in errors
+pub fn parse_flags_file(path: &str, content: &str) -> anyhow::Result<Vec<String>> {
+    parse_flags(content).map_err(|e| anyhow::anyhow!("{}: {}", path, e))
+}
+
+/// Parse the entries of a flags file: `FLAG`, `FLAG=value`, `!FLAG`, rule expressions and
+/// `any-of`/`all-of` groups, which become rules, each optionally followed by `depends on CONDITION`,
+/// qualified with `@ arch,...` and marked `!recommended` or `!optional` at the end; `[Section]`
+/// headers only group the entries below them, and `include` directives must have been expanded
+/// with [`include::expand`](crate::include::expand)
+pub fn parse_flags(content: &str) -> anyhow::Result<Vec<String>> {
+    let mut flags = Vec::new();
+    let lines = flag_lines(content).map_err(|(line_number, e)| anyhow::anyhow!("line {}: {}", line_number, e))?;
+    
+    for (line_number, trimmed) in &lines {
+        let trimmed = trimmed.as_str();
+        if trimmed.is_empty() || trimmed.starts_with('#') || section_header(trimmed).is_some() {
+            continue;
+        }
+        if include_directive(trimmed).is_some() {
+            return Err(anyhow::anyhow!("line {}: includes are only followed in flags files read from disk", line_number));
+        }
+
+        // `ENTRY !recommended` and `ENTRY !optional` don't fail the check by default
+        let (trimmed, severity) = match severity_marker(trimmed) {
+            Some(("", _)) => return Err(anyhow::anyhow!("line {}: nothing before the severity to mark", line_number)),
+            Some((entry, severity)) => (entry, severity),
+            None => (trimmed, Severity::Required),
+        };
+        
+        // `ENTRY @ x86_64,arm64` only applies on those architectures
+        let (entry, arches) = match arch_qualifier(trimmed) {
+            Some(("", _)) => return Err(anyhow::anyhow!("line {}: nothing before '@' to qualify", line_number)),
+            Some((entry, arches)) => (entry, parse_arches(&arches).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?),
+            None => (trimmed, Vec::new()),
+        };
+
+        // `ENTRY depends on CONDITION` is only checked when the condition holds
+        let (entry, condition) = match entry.split_once(DEPENDS_ON) {
+            Some(("", _)) => return Err(anyhow::anyhow!("line {}: nothing before 'depends on'", line_number)),
+            Some((entry, condition)) => {
+                expr::parse(condition.trim()).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+                (entry.trim(), Some(condition.trim()))
+            }
+            None => (entry, None),
+        };
+
+        // Handle rule expressions, "FLAG", "!FLAG" and "FLAG=value" formats
+        let flag = if negated_flag(entry).is_some() {
+            entry.to_string()
+        } else if let Some((flag_name, value)) = value_assertion(entry) {
+            // Keep the value, the config has to match it exactly
+            format!("{}={}", flag_name, value)
+        } else if expr::is_expression(entry) {
+            expr::parse(entry).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+            entry.to_string()
+        } else if let Some((flag_name, value)) = entry.split_once('=') {
+            format!("{}={}", flag_name.trim(), value.trim())
+        } else {
+            // It's just a flag name
+            entry.to_string()
+        };
+        let flag = match condition {
+            Some(condition) => format!("{}{}{}", flag, DEPENDS_ON, condition),
+            None => flag,
+        };
+        let flag = if arches.is_empty() { flag } else { format!("{} @ {}", flag, arches.join(",")) };
+        flags.push(if severity.is_required() { flag } else { format!("{} !{}", flag, severity.as_str()) });
+    }
+    
+    Ok(flags)
+}
+
+/// The trimmed lines of a flags file with their 1-based numbers, each `any-of { ... }` or
+/// `all-of { ... }` group joined into the rule it stands for, numbered by its first line:
+///
+/// ```text
+/// any-of {
+///     CONFIG_IP_NF_IPTABLES
+///     CONFIG_NFT_COMPAT=m|y
+/// }
+/// ```
+///
+/// becomes `CONFIG_IP_NF_IPTABLES || (CONFIG_NFT_COMPAT=m || CONFIG_NFT_COMPAT=y)`. Groups
+/// nest; comments and blank lines inside them are dropped. An outermost group closed with
+/// `} @ arch` keeps the qualifier. Errors carry the line number.
+pub(crate) fn flag_lines(content: &str) -> Result<Vec<(usize, String)>, (usize, String)> {
+    let mut lines = Vec::new();
+    // Open groups: first line, kind and the members so far
+    let mut groups: Vec<(usize, &str, Vec<String>)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let (number, trimmed) = (index + 1, line.trim());
+        if let Some(kind) = trimmed.strip_suffix('{').map(str::trim).filter(|kind| ["any-of", "all-of"].contains(kind)) {
+            groups.push((number, kind, Vec::new()));
+            continue;
+        }
+        // A closing brace, with the architecture qualifier and severity that may follow it
+        let closing = trimmed
+            .strip_prefix('}')
+            .map(str::trim)
+            .filter(|rest| rest.is_empty() || rest.starts_with('@') || Severity::from_marker(rest).is_some());
+        let closed = match (groups.last_mut(), closing) {
+            (None, Some(_)) => return Err((number, "'}' without an any-of or all-of group".to_string())),
+            (None, None) => {
+                lines.push((number, trimmed.to_string()));
+                continue;
+            }
+            (Some(_), Some(qualifier)) => groups.pop().map(|group| (group, qualifier)),
+            (Some(_), None) if section_header(trimmed).is_some() => {
+                return Err((number, format!("{}: a section can't start inside a group", trimmed)))
+            }
+            (Some(_), None) if include_directive(trimmed).is_some() => {
+                return Err((number, format!("{}: files can't be included inside a group", trimmed)))
+            }
+            (Some((_, _, members)), None) => {
+                if !trimmed.is_empty() && !trimmed.starts_with('#') {
+                    members.push(group_member(trimmed).map_err(|e| (number, e))?);
+                }
+                continue;
+            }
+        };
+
+        let Some(((start, kind, members), qualifier)) = closed else { continue };
+        if members.is_empty() {
+            return Err((start, format!("empty {} group", kind)));
+        }
+        let rule = members.join(if kind == "any-of" { " || " } else { " && " });
+        match groups.last_mut() {
+            Some(_) if !qualifier.is_empty() => {
+                return Err((number, "only outermost groups can be qualified with an architecture or severity".to_string()))
+            }
+            Some((_, _, outer)) => outer.push(if members.len() > 1 { format!("({})", rule) } else { rule }),
+            None if qualifier.is_empty() => lines.push((start, rule)),
+            None => lines.push((start, format!("{} {}", rule, qualifier))),
+        }
+    }
+
+    match groups.first() {
+        Some((start, kind, _)) => Err((*start, format!("{} group is never closed", kind))),
+        None => Ok(lines),
+    }
+}
+
+/// A flags file entry inside a group, as a term of the group's rule
+fn group_member(entry: &str) -> Result<String, String> {
+    if arch_qualifier(entry).is_some() {
+        return Err(format!("{}: qualify the whole group with '}} @ arch' instead of its entries", entry));
+    }
+    if severity_marker(entry).is_some() {
+        return Err(format!("{}: mark the whole group with '}} !severity' instead of its entries", entry));
+    }
+    if entry.contains(DEPENDS_ON) {
+        return Err(format!("{}: entries inside a group can't have dependencies", entry));
+    }
+    if let Some(flag) = negated_flag(entry) {
+        return Ok(format!("!{}", flag));
+    }
+    if comparison(entry).is_some() {
+        return Ok(entry.to_string());
+    }
+    if is_rule(entry) {
+        expr::parse(entry).map_err(|e| e.to_string())?;
+        return Ok(format!("({})", entry));
+    }
+    let Some((flag, value)) = entry.split_once('=') else {
+        return Ok(entry.to_string());
+    };
+    let tests: Vec<String> = accepted_values(value.trim()).iter().map(|value| format!("{}={}", flag.trim(), value)).collect();
+    match tests.as_slice() {
+        [] => Err(format!("{} has no value", entry)),
+        [test] => Ok(test.clone()),
+        _ => Ok(format!("({})", tests.join(" || "))),
+    }
+}
+
+/// Check a flag or rule against the text of a config; with `validation_content`, flags
+/// that don't exist there are reported as invalid instead of missing
+pub fn check_flag(config_content: &str, validation_content: Option<&str>, flag: &str) -> FlagCheckResult {
+    let validation = validation_content.map(|content| KernelConfig::parse("", content));
+    check_config(&KernelConfig::parse("", config_content), validation.as_ref(), flag)
+}
+
+/// Check a flag or rule against a parsed config; with `validation`, flags that don't exist
+/// there are reported as invalid instead of missing
+pub fn check_config(config: &KernelConfig, validation: Option<&KernelConfig>, flag: &str) -> FlagCheckResult {
+    // Architecture qualifiers and dependencies decide whether a requirement is checked at all, see `Checker::check_one`
+    let flag = unqualified(flag);
+
+    // Rule expressions were validated when the flags were read
+    if setting(flag).is_none() && is_rule(flag) {
+        let satisfied = expr::parse(flag)
+            .map(|rule| expr::evaluate(&rule, &expr::Context::new(config)))
+            .unwrap_or(false);
+        return FlagCheckResult {
+            name: flag.to_string(),
+            status: if satisfied { FlagStatus::RulePassed } else { FlagStatus::RuleFailed },
+        };
+    }
+
+    // `FLAG=value` only passes with exactly that value
+    let (flag, value) = match setting(flag) {
+        Some((flag, value)) => (flag, Some(value)),
+        None => (flag.trim(), None),
+    };
+
+    // Remove CONFIG_ prefix if it already exists in the input
+    let name = format!("CONFIG_{}", flag.strip_prefix("CONFIG_").unwrap_or(flag));
+
+    // Check if the flag actually exists in kernel config options, `is not set` included; a flag
+    // that must be disabled is disabled wherever it doesn't exist, so it isn't validated
+    let forbids = value.is_some_and(|value| accepted_values(value).contains(&"n"));
+    if !forbids && validation.is_some_and(|v| v.get(&name).is_none()) {
+        return FlagCheckResult { name, status: FlagStatus::Invalid };
+    }
+
+    let status = match value {
+        Some(value) => accepted_status(config, &name, &accepted_values(value)),
+        None => match config.get(&name).and_then(ConfigValue::tristate) {
+            Some(Tristate::Yes) => FlagStatus::EnabledInKernel,
+            Some(Tristate::Module) => FlagStatus::EnabledAsModule,
+            Some(Tristate::No) => FlagStatus::Disabled,
+            None => FlagStatus::Missing,
+        },
+    };
+    FlagCheckResult { name, status }
+}
+
+/// The files named by an `include base.flags` or `include conf.d/*.flags` directive
+pub(crate) fn include_directive(line: &str) -> Option<&str> {
+    let files = line.strip_prefix("include")?;
+    files.starts_with(char::is_whitespace).then(|| files.trim()).filter(|files| !files.is_empty())
+}
+
+/// The name of a `[Networking]` section header, grouping the entries below it up to the next one;
+/// empty for `[]`, which ends the section so the entries below it are in none
+pub(crate) fn section_header(line: &str) -> Option<&str> {
+    Some(line.strip_prefix('[')?.strip_suffix(']')?.trim())
+}
+
+/// The entry and severity of an entry marked like `CONFIG_BPF_LSM !recommended`, the last word
+/// of the line
+pub(crate) fn severity_marker(entry: &str) -> Option<(&str, Severity)> {
+    let (entry, marker) = entry.trim().rsplit_once(char::is_whitespace)?;
+    Some((entry.trim(), Severity::from_marker(marker)?))
+}
+
+/// An entry without its severity marker
+pub(crate) fn unmarked(entry: &str) -> &str {
+    severity_marker(entry).map_or(entry, |(entry, _)| entry)
+}
+
+/// The entry and architectures of an entry qualified like `CONFIG_X86_INTEL_TSX @ x86_64,i386`,
+/// ignoring any severity marker; an `@` inside a quoted value isn't a qualifier
+pub(crate) fn arch_qualifier(entry: &str) -> Option<(&str, Vec<&str>)> {
+    let entry = unmarked(entry);
+    let at = entry.rfind('@')?;
+    if entry[..at].matches('"').count() % 2 == 1 {
+        return None;
+    }
+    Some((entry[..at].trim(), entry[at + 1..].split(',').map(str::trim).collect()))
+}
+
+/// The architectures of a qualifier, normalized and without duplicates
+pub(crate) fn parse_arches(arches: &[&str]) -> anyhow::Result<Vec<String>> {
+    let mut parsed: Vec<String> = Vec::new();
+    for name in arches {
+        let name = arch::parse(name)?;
+        if !parsed.contains(&name) {
+            parsed.push(name);
+        }
+    }
+    Ok(parsed)
+}
+
+/// Separates an entry from the condition it depends on
+const DEPENDS_ON: &str = " depends on ";
+
+/// The entry and condition of an entry like `CONFIG_BPF_JIT depends on CONFIG_BPF_SYSCALL`,
+/// ignoring any architecture qualifier and severity marker
+pub(crate) fn dependency(entry: &str) -> Option<(&str, &str)> {
+    let entry = arch_qualifier(entry).map_or(unmarked(entry), |(entry, _)| entry);
+    entry.split_once(DEPENDS_ON).map(|(entry, condition)| (entry.trim(), condition.trim()))
+}
+
+/// An entry without its severity marker, architecture qualifier and dependency
+pub(crate) fn unqualified(entry: &str) -> &str {
+    let entry = arch_qualifier(entry).map_or(unmarked(entry).trim(), |(entry, _)| entry);
+    entry.split_once(DEPENDS_ON).map_or(entry, |(entry, _)| entry.trim())
+}
+
+/// The symbol of a `!FLAG` entry, which requires the flag to be disabled like `FLAG=n`
+pub(crate) fn negated_flag(entry: &str) -> Option<&str> {
+    let flag = entry.trim().strip_prefix('!')?.trim();
+    (!flag.is_empty() && flag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')).then_some(flag)
+}
+
+/// The flag and value of a `FLAG=value` or `!FLAG` entry, or the flag and bound of a numeric
+/// comparison like `FLAG>=64`; `None` for plain flags and rules
+pub(crate) fn setting(entry: &str) -> Option<(&str, &str)> {
+    if let Some(flag) = negated_flag(entry) {
+        return Some((flag, "n"));
+    }
+    if let Some(comparison) = comparison(entry) {
+        return Some(comparison);
+    }
+    if let Some(assertion) = value_assertion(entry) {
+        return Some(assertion);
+    }
+    if expr::is_expression(entry) {
+        return None;
+    }
+    entry.split_once('=').map(|(flag, value)| (flag.trim(), value.trim()))
+}
+
+/// Whether an entry is a rule expression rather than a flag, a `FLAG="..."` value being a flag
+/// whatever its quoted string holds
+pub fn is_rule(entry: &str) -> bool {
+    value_assertion(entry).is_none() && expr::is_expression(entry)
+}
+
+/// The flag and value of a `FLAG=value` or `FLAG="(none)"` entry: a symbol, then a value without
+/// `&&`, `||` or a leading `(` outside its quotes
+fn value_assertion(entry: &str) -> Option<(&str, &str)> {
+    let (flag, value) = entry.split_once('=')?;
+    let (flag, value) = (flag.trim(), value.trim());
+    let is_symbol = !flag.is_empty() && flag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let unquoted = unquoted(value);
+    let is_rule = value.starts_with('(') || unquoted.contains("&&") || unquoted.contains("||");
+    (is_symbol && !is_rule).then_some((flag, value))
+}
+
+/// The text outside the double-quoted strings of a value, escaped quotes staying inside them
+fn unquoted(value: &str) -> String {
+    let (mut out, mut quoted, mut escaped) = (String::new(), false, false);
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if !quoted => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// The flag and bound of an entry that compares a single flag with a number, e.g. `CONFIG_NR_CPUS >= 64`
+fn comparison(entry: &str) -> Option<(&str, &str)> {
+    let start = entry.find(['<', '>'])?;
+    let (flag, bound) = (entry[..start].trim(), entry[start..].trim());
+    let is_symbol = !flag.is_empty() && flag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    (is_symbol && flag != "KERNEL" && flag != "ARCH" && numeric_bound(bound).is_some()).then_some((flag, bound))
+}
+
+/// The operator and number of a bound like `>=64` or `< 0x1000`
+fn numeric_bound(bound: &str) -> Option<(Ordering, bool, i64)> {
+    // The ordering the value must have relative to the number, and whether equal is fine too
+    let (ordering, or_equal, number) = if let Some(number) = bound.strip_prefix(">=") {
+        (Ordering::Greater, true, number)
+    } else if let Some(number) = bound.strip_prefix("<=") {
+        (Ordering::Less, true, number)
+    } else if let Some(number) = bound.strip_prefix('>') {
+        (Ordering::Greater, false, number)
+    } else if let Some(number) = bound.strip_prefix('<') {
+        (Ordering::Less, false, number)
+    } else {
+        return None;
+    };
+    Some((ordering, or_equal, expr::parse_number(number.trim())?))
+}
+
+/// Whether a config value is an accepted value or within an accepted bound; numbers
+/// are compared by value, so `0x40` accepts `64`
+fn accepts(accepted: &str, value: &ConfigValue) -> bool {
+    if let Some((ordering, or_equal, bound)) = numeric_bound(accepted) {
+        return value.number().is_some_and(|number| {
+            let actual = number.cmp(&bound);
+            actual == ordering || (or_equal && actual == Ordering::Equal)
+        });
+    }
+    let expected = ConfigValue::parse(accepted);
+    match (expected.number(), value.number()) {
+        (Some(expected), Some(actual)) => expected == actual,
+        _ => expected == *value,
+    }
+}
+
+/// A value that satisfies the accepted values, to fix a flag with: `y` when building
+/// the flag in will do, otherwise the first value or the nearest number within the bound
+pub(crate) fn satisfying_value(accepted: &[String]) -> Option<String> {
+    if accepted.iter().any(|value| value == "y") {
+        return Some("y".to_string());
+    }
+    let first = accepted.first()?;
+    Some(match numeric_bound(first) {
+        Some((Ordering::Greater, false, bound)) => (bound + 1).to_string(),
+        Some((Ordering::Less, false, bound)) => (bound - 1).to_string(),
+        Some((_, _, bound)) => bound.to_string(),
+        None => first.clone(),
+    })
+}
+
+/// The values a `FLAG=value` entry accepts: `m|y` accepts a module or built in, while
+/// a quoted string is taken whole
+pub(crate) fn accepted_values(value: &str) -> Vec<&str> {
+    if value.starts_with('"') {
+        vec![value]
+    } else {
+        value.split('|').map(str::trim).filter(|v| !v.is_empty()).collect()
+    }
+}
+
+/// Status of a flag that must have one of the accepted values or be within one of the bounds,
+/// compared as typed values so that `"-custom"` matches the quoted string and `1000` the integer; accepting `n`
+/// makes a flag that is enabled anyway forbidden rather than missing, and a flag set to a value that
+/// isn't accepted has the wrong value
+pub(crate) fn accepted_status<S: AsRef<str>>(config: &KernelConfig, name: &str, accepted: &[S]) -> FlagStatus {
+    let unset = config.get(name).and_then(ConfigValue::tristate) == Some(Tristate::No);
+    let value = config.get(name).filter(|value| **value != ConfigValue::NotSet);
+    let matches = value.is_some_and(|value| accepted.iter().any(|a| accepts(a.as_ref(), value)));
+    let forbids = accepted.iter().any(|a| a.as_ref() == "n");
+    match value {
+        None | Some(ConfigValue::Bool(false)) if forbids => FlagStatus::Off,
+        Some(ConfigValue::Tristate(_)) if matches => FlagStatus::EnabledAsModule,
+        Some(_) if matches => FlagStatus::EnabledInKernel,
+        _ if forbids => FlagStatus::Forbidden,
+        _ if unset => FlagStatus::Disabled,
+        Some(_) => FlagStatus::WrongValue,
+        None => FlagStatus::Missing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+CONFIG_BPF=y
+CONFIG_NFT_COMPAT=m
+# CONFIG_KEXEC is not set
+CONFIG_NR_CPUS=64
+CONFIG_LOCALVERSION=\"-custom\"
+";
+
+    fn check(config: &str, entry: &str) -> FlagStatus {
+        check_flag(config, None, entry).status
+    }
+
+    #[test]
+    fn values_must_match_exactly() {
+        assert_eq!(parse_flags("CONFIG_NR_CPUS = 64\n").unwrap(), ["CONFIG_NR_CPUS=64"]);
+        assert_eq!(setting("CONFIG_NR_CPUS=64"), Some(("CONFIG_NR_CPUS", "64")));
+        assert_eq!(check(CONFIG, "CONFIG_NR_CPUS=64"), FlagStatus::EnabledInKernel);
+        assert_eq!(check(CONFIG, "CONFIG_NR_CPUS=0x40"), FlagStatus::EnabledInKernel);
+        assert_eq!(check(CONFIG, "CONFIG_NR_CPUS=128"), FlagStatus::WrongValue);
+        assert_eq!(check(CONFIG, r#"CONFIG_LOCALVERSION="-custom""#), FlagStatus::EnabledInKernel);
+    }
+
+    #[test]
+    fn module_and_builtin_values() {
+        assert_eq!(parse_flags("CONFIG_NFT_COMPAT=m|y\n").unwrap(), ["CONFIG_NFT_COMPAT=m|y"]);
+        assert_eq!(accepted_values("m|y"), ["m", "y"]);
+        assert_eq!(check(CONFIG, "CONFIG_NFT_COMPAT=m"), FlagStatus::EnabledAsModule);
+        assert_eq!(check(CONFIG, "CONFIG_NFT_COMPAT=y"), FlagStatus::WrongValue);
+        assert_eq!(check(CONFIG, "CONFIG_BPF=m|y"), FlagStatus::EnabledInKernel);
+        assert_eq!(check(CONFIG, "CONFIG_KEXEC=y"), FlagStatus::Disabled);
+        assert_eq!(check(CONFIG, "CONFIG_USER_NS=m|y"), FlagStatus::Missing);
+    }
+
+    #[test]
+    fn negated_flags_must_be_disabled() {
+        assert_eq!(parse_flags("!CONFIG_KEXEC\n").unwrap(), ["!CONFIG_KEXEC"]);
+        assert_eq!(setting("!CONFIG_KEXEC"), Some(("CONFIG_KEXEC", "n")));
+        assert_eq!(check(CONFIG, "!CONFIG_KEXEC"), FlagStatus::Off);
+        assert_eq!(check(CONFIG, "!CONFIG_USER_NS"), FlagStatus::Off);
+        assert_eq!(check(CONFIG, "!CONFIG_BPF"), FlagStatus::Forbidden);
+    }
+
+    #[test]
+    fn bounds_compare_numbers() {
+        assert_eq!(parse_flags("CONFIG_NR_CPUS>=64\n").unwrap(), ["CONFIG_NR_CPUS>=64"]);
+        assert_eq!(setting("CONFIG_NR_CPUS<=0x80"), Some(("CONFIG_NR_CPUS", "<=0x80")));
+        assert_eq!(check(CONFIG, "CONFIG_NR_CPUS>=64"), FlagStatus::EnabledInKernel);
+        assert_eq!(check(CONFIG, "CONFIG_NR_CPUS<=0x80"), FlagStatus::EnabledInKernel);
+        assert_eq!(check(CONFIG, "CONFIG_NR_CPUS<=32"), FlagStatus::WrongValue);
+    }
+
+    #[test]
+    fn groups_become_rules() {
+        let any_of = "any-of {\n    CONFIG_IP_NF_IPTABLES\n    CONFIG_NFT_COMPAT=m|y\n} @ x86_64\n";
+        let [rule] = parse_flags(any_of).unwrap().try_into().unwrap();
+        assert_eq!(rule, "CONFIG_IP_NF_IPTABLES || (CONFIG_NFT_COMPAT=m || CONFIG_NFT_COMPAT=y) @ x86_64");
+        assert_eq!(check(CONFIG, unqualified(&rule)), FlagStatus::RulePassed);
+
+        let all_of = "all-of {\n    CONFIG_BPF\n    !CONFIG_NFT_COMPAT\n}\n";
+        assert_eq!(parse_flags(all_of).unwrap(), ["CONFIG_BPF && !CONFIG_NFT_COMPAT"]);
+        assert_eq!(check(CONFIG, "CONFIG_BPF && !CONFIG_NFT_COMPAT"), FlagStatus::RuleFailed);
+        assert!(parse_flags("all-of {\n}\n").is_err());
+    }
+
+    #[test]
+    fn a_quoted_value_with_parentheses_is_a_value() {
+        let entry = r#"CONFIG_DEFAULT_HOSTNAME="(none)""#;
+        assert_eq!(parse_flags(entry).unwrap(), [entry]);
+        assert!(!is_rule(entry));
+        assert_eq!(setting(entry), Some(("CONFIG_DEFAULT_HOSTNAME", r#""(none)""#)));
+        assert_eq!(check("CONFIG_DEFAULT_HOSTNAME=\"(none)\"\n", entry), FlagStatus::EnabledInKernel);
+        assert_eq!(check("CONFIG_DEFAULT_HOSTNAME=\"box\"\n", entry), FlagStatus::WrongValue);
+        assert!(is_rule(r#"CONFIG_A="x" || CONFIG_B"#));
+    }
+}
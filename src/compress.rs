@@ -0,0 +1,129 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use flate2::read::GzDecoder;
+use ruzstd::decoding::StreamingDecoder;
+
+use crate::ikconfig;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decode a kernel config, decompressing it when the magic bytes or the
+/// file name say it is compressed, or extracting it from a kernel image
+pub fn decode_config(data: &[u8], name: &str) -> anyhow::Result<String> {
+    if ikconfig::is_kernel_image(data) {
+        return ikconfig::extract(data).ok_or_else(|| {
+            anyhow::anyhow!("{} is a kernel image without an embedded config (CONFIG_IKCONFIG is not set)", name)
+        });
+    }
+
+    let content = if data.starts_with(GZIP_MAGIC) || name.ends_with(".gz") {
+        gunzip(data)
+    } else if data.starts_with(XZ_MAGIC) || name.ends_with(".xz") {
+        unxz(data)
+    } else if data.starts_with(ZSTD_MAGIC) || name.ends_with(".zst") {
+        unzstd(data)
+    } else {
+        String::from_utf8(data.to_vec()).map_err(|e| anyhow::anyhow!("Failed to read config file {}: {}", name, e))
+    };
+
+    // A compressed kernel image such as Image.gz doesn't decode as text
+    content.or_else(|e| ikconfig::extract(data).ok_or(e))
+}
+
+/// Decode a config from `reader` into `out` as it arrives, decompressing it when the
+/// magic bytes or the name say it is compressed; kernel images need random access and
+/// can't be decoded this way
+pub fn decode_stream(reader: impl Read, out: &mut impl Write, name: &str) -> anyhow::Result<()> {
+    let mut reader = BufReader::with_capacity(64 * 1024, reader);
+    let head = reader.fill_buf().map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", name, e))?;
+    if ikconfig::is_kernel_image(head) {
+        return Err(anyhow::anyhow!("{} is a kernel image, which can't be read as a stream", name));
+    }
+    let (gzip, xz, zstd) = (
+        head.starts_with(GZIP_MAGIC) || name.ends_with(".gz"),
+        head.starts_with(XZ_MAGIC) || name.ends_with(".xz"),
+        head.starts_with(ZSTD_MAGIC) || name.ends_with(".zst"),
+    );
+
+    let copied = if gzip {
+        io::copy(&mut flate2::bufread::GzDecoder::new(reader), out)
+    } else if xz {
+        lzma_rs::xz_decompress(&mut reader, out).map(|_| 0).map_err(|e| io::Error::other(e.to_string()))
+    } else if zstd {
+        StreamingDecoder::new(reader)
+            .map_err(|e| io::Error::other(e.to_string()))
+            .and_then(|mut decoder| io::copy(&mut decoder, out))
+    } else {
+        io::copy(&mut reader, out)
+    };
+    copied.map_err(|e| anyhow::anyhow!("Failed to read config {}: {}", name, e))?;
+    Ok(())
+}
+
+/// Decompress a gzip-compressed kernel config
+pub fn gunzip(data: &[u8]) -> anyhow::Result<String> {
+    let mut content = String::new();
+    GzDecoder::new(data)
+        .read_to_string(&mut content)
+        .map_err(|e| anyhow::anyhow!("Failed to decompress gzip config: {}", e))?;
+
+    Ok(content)
+}
+
+/// Decompress an xz-compressed kernel config
+pub fn unxz(data: &[u8]) -> anyhow::Result<String> {
+    let mut content = Vec::new();
+    lzma_rs::xz_decompress(&mut &data[..], &mut content)
+        .map_err(|e| anyhow::anyhow!("Failed to decompress xz config: {}", e))?;
+
+    String::from_utf8(content).map_err(|e| anyhow::anyhow!("Failed to decompress xz config: {}", e))
+}
+
+/// Decompress a zstd-compressed kernel config
+pub fn unzstd(data: &[u8]) -> anyhow::Result<String> {
+    let mut decoder = StreamingDecoder::new(data).map_err(|e| anyhow::anyhow!("Failed to decompress zstd config: {}", e))?;
+    let mut content = String::new();
+    decoder
+        .read_to_string(&mut content)
+        .map_err(|e| anyhow::anyhow!("Failed to decompress zstd config: {}", e))?;
+
+    Ok(content)
+}
+
+/// Decompress a gzip, xz or zstd stream recognized by its magic bytes, ignoring
+/// anything after the end of the stream
+pub fn decompress(data: &[u8]) -> Option<Vec<u8>> {
+    let mut content = Vec::new();
+    if data.starts_with(GZIP_MAGIC) {
+        GzDecoder::new(data).read_to_end(&mut content).ok()?;
+    } else if data.starts_with(XZ_MAGIC) {
+        // lzma-rs refuses data after the stream, but only once it has written everything before it
+        if lzma_rs::xz_decompress(&mut &data[..], &mut content).is_err() && content.is_empty() {
+            return None;
+        }
+    } else if data.starts_with(ZSTD_MAGIC) {
+        StreamingDecoder::new(data).ok()?.read_to_end(&mut content).ok()?;
+    } else {
+        return None;
+    }
+    Some(content)
+}
+
+/// Offsets of what look like gzip, xz and zstd streams inside `data`, at most
+/// `limit` of each kind
+pub fn find_streams(data: &[u8], limit: usize) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    // gzip with the deflate method byte, so random 1f 8b pairs are skipped
+    for magic in [&[0x1f, 0x8b, 0x08][..], XZ_MAGIC, ZSTD_MAGIC] {
+        offsets.extend(
+            data.windows(magic.len())
+                .enumerate()
+                .filter(|(_, window)| *window == magic)
+                .map(|(offset, _)| offset)
+                .take(limit),
+        );
+    }
+    offsets
+}
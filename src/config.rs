@@ -0,0 +1,228 @@
+//! Kernel configs: loading them from files and looking up their values.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::{self, FlagCheckResult};
+use crate::compress;
+
+/// The running kernel's config, when built with `CONFIG_IKCONFIG_PROC`
+pub const PROC_CONFIG: &str = "/proc/config.gz";
+
+/// State of a tristate symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tristate {
+    No,
+    Module,
+    Yes,
+}
+
+/// The value of a config symbol.
+///
+/// A config file doesn't record the symbol types, so the type is inferred from the value:
+/// `y` and `n` are booleans (tristates set to `y` look the same), `m` is a tristate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ConfigValue {
+    Bool(bool),
+    Tristate(Tristate),
+    Int(i64),
+    Hex(u64),
+    /// A quoted string, unescaped, or an unquoted value that is none of the above
+    String(String),
+    /// `# CONFIG_FOO is not set`
+    NotSet,
+}
+
+impl ConfigValue {
+    /// Parse the right-hand side of `CONFIG_FOO=<value>`
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "y" => return ConfigValue::Bool(true),
+            "n" => return ConfigValue::Bool(false),
+            "m" => return ConfigValue::Tristate(Tristate::Module),
+            _ => {}
+        }
+        if let Some(quoted) = value.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+            return ConfigValue::String(unescape(quoted));
+        }
+        if let Some(hex) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+            if let Ok(hex) = u64::from_str_radix(hex, 16) {
+                return ConfigValue::Hex(hex);
+            }
+        }
+        match value.parse() {
+            Ok(int) => ConfigValue::Int(int),
+            Err(_) => ConfigValue::String(value.to_string()),
+        }
+    }
+
+    /// The value as a tristate: `y` is yes, `m` module, and `n` or not set no; `None` for other types
+    pub fn tristate(&self) -> Option<Tristate> {
+        match self {
+            ConfigValue::Bool(true) => Some(Tristate::Yes),
+            ConfigValue::Bool(false) | ConfigValue::NotSet => Some(Tristate::No),
+            ConfigValue::Tristate(state) => Some(*state),
+            _ => None,
+        }
+    }
+
+    /// Whether the symbol is built in or a module
+    pub fn is_enabled(&self) -> bool {
+        matches!(self.tristate(), Some(Tristate::Yes | Tristate::Module))
+    }
+
+    /// The value as a number, for int and hex symbols
+    pub fn number(&self) -> Option<i64> {
+        match self {
+            ConfigValue::Int(int) => Some(*int),
+            ConfigValue::Hex(hex) => i64::try_from(*hex).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConfigValue {
+    /// The value as written in a config file
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigValue::Bool(true) | ConfigValue::Tristate(Tristate::Yes) => write!(f, "y"),
+            ConfigValue::Bool(false) | ConfigValue::Tristate(Tristate::No) => write!(f, "n"),
+            ConfigValue::Tristate(Tristate::Module) => write!(f, "m"),
+            ConfigValue::Int(int) => write!(f, "{}", int),
+            ConfigValue::Hex(hex) => write!(f, "0x{:x}", hex),
+            ConfigValue::String(string) => write!(f, "\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\"")),
+            ConfigValue::NotSet => write!(f, "is not set"),
+        }
+    }
+}
+
+/// A kernel config parsed into its symbols, and where it came from
+pub struct KernelConfig {
+    source: String,
+    content: String,
+    /// Symbols in the order they appear, with the 1-based line that sets them
+    entries: Vec<(String, ConfigValue, usize)>,
+    index: HashMap<String, usize>,
+}
+
+impl KernelConfig {
+    /// Parse the text of a config; when a symbol is assigned twice, as in merged fragments, the last one wins
+    pub fn parse(source: &str, content: impl Into<String>) -> Self {
+        let content = content.into();
+        let mut entries: Vec<(String, ConfigValue, usize)> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            let entry = if let Some(name) = line.strip_prefix("# ").and_then(|rest| rest.strip_suffix(" is not set")) {
+                (name, ConfigValue::NotSet)
+            } else if let Some((name, value)) = line.split_once('=').filter(|_| !line.starts_with('#')) {
+                (name, ConfigValue::parse(value))
+            } else {
+                continue;
+            };
+            if !entry.0.starts_with("CONFIG_") {
+                continue;
+            }
+            match index.get(entry.0) {
+                Some(&position) => (entries[position].1, entries[position].2) = (entry.1, number + 1),
+                None => {
+                    index.insert(entry.0.to_string(), entries.len());
+                    entries.push((entry.0.to_string(), entry.1, number + 1));
+                }
+            }
+        }
+        KernelConfig { source: source.to_string(), content, entries, index }
+    }
+
+    /// A config from raw bytes: plain text, gzip, xz or zstd, or a kernel image with an embedded config
+    pub fn from_bytes(source: &str, data: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self::parse(source, compress::decode_config(data, source)?))
+    }
+
+    /// Read a local config file
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        Ok(Self::parse(path, read_kernel_config(path)?))
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The text of the config, after decompression
+    pub fn text(&self) -> &str {
+        &self.content
+    }
+
+    /// The value of a symbol, with or without its `CONFIG_` prefix
+    pub fn get(&self, name: &str) -> Option<&ConfigValue> {
+        self.position(name).map(|position| &self.entries[position].1)
+    }
+
+    /// The 1-based line that sets or unsets a symbol, the last one when it is assigned twice
+    pub fn line(&self, name: &str) -> Option<usize> {
+        self.position(name).map(|position| self.entries[position].2)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        match name.strip_prefix("CONFIG_") {
+            Some(_) => self.index.get(name).copied(),
+            None => self.index.get(&format!("CONFIG_{}", name)).copied(),
+        }
+    }
+
+    /// Whether the config assigns or explicitly unsets the symbol
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// The symbols and their values, in the order they appear in the config
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConfigValue)> {
+        self.entries.iter().map(|(name, value, _)| (name.as_str(), value))
+    }
+
+    /// Number of symbols, including the ones that are not set
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Check a flag or rule against this config
+    pub fn check(&self, flag: &str) -> FlagCheckResult {
+        check::check_config(self, None, flag)
+    }
+}
+
+/// Undo the escaping of `\` and `"` inside a quoted string value
+fn unescape(quoted: &str) -> String {
+    let mut out = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Read a local config, decompressing it or extracting it from a kernel image as needed
+pub fn read_kernel_config(path: &str) -> anyhow::Result<String> {
+    let path = Path::new(path);
+
+    if !path.exists() {
+        return Err(anyhow::anyhow!("Config file not found: {}", path.display()));
+    }
+
+    // Compressed configs and kernel images are recognized by their magic bytes as well as the extension
+    let data = fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
+    compress::decode_config(&data, &path.display().to_string())
+}
@@ -0,0 +1,160 @@
+//! Check the kernel as seen from inside a docker or podman container.
+//!
+//! Containers share the host kernel, but what they can use of it depends on
+//! the runtime: which cgroup hierarchy is mounted, whether they run in a user
+//! namespace and which seccomp filter applies. The container's view is read
+//! through `<engine> exec` and the host's `/proc/<pid>` of its init process.
+
+use std::fs;
+use std::process::Command;
+
+/// Container engines tried, in order
+const ENGINES: &[&str] = &["docker", "podman"];
+
+/// A running container and the engine that manages it
+pub struct Container {
+    pub engine: &'static str,
+    pub name: String,
+    /// Host PID of the container's init process
+    pub pid: u32,
+}
+
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A fact about the container's runtime environment
+pub struct RuntimeCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// Find the engine running `name` and the host PID of its init process
+pub fn resolve(name: &str) -> anyhow::Result<Container> {
+    let mut errors = Vec::new();
+    for &engine in ENGINES {
+        let output = match Command::new(engine).args(["inspect", "--format", "{{.State.Pid}}", name]).output() {
+            Ok(output) => output,
+            Err(e) => {
+                errors.push(format!("{}: {}", engine, e));
+                continue;
+            }
+        };
+        if !output.status.success() {
+            errors.push(format!("{}: {}", engine, String::from_utf8_lossy(&output.stderr).trim()));
+            continue;
+        }
+
+        let pid: u32 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Failed to read the PID of container {}: {}", name, e))?;
+        if pid == 0 {
+            return Err(anyhow::anyhow!("Container {} is not running", name));
+        }
+        return Ok(Container { engine, name: name.to_string(), pid });
+    }
+    Err(anyhow::anyhow!("Failed to find container {}: {}", name, errors.join("; ")))
+}
+
+impl Container {
+    /// Read a file inside the container
+    pub fn read(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+        let output = Command::new(self.engine)
+            .args(["exec", &self.name, "cat", path])
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run {} exec: {}", self.engine, e))?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to read {} in container {}: {}",
+                path,
+                self.name,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(output.stdout)
+    }
+
+    /// The cgroup hierarchy, user namespace and seccomp mode of the container
+    pub fn runtime_checks(&self) -> Vec<RuntimeCheck> {
+        vec![self.cgroup_mount(), self.user_namespace(), self.seccomp()]
+    }
+
+    fn proc_file(&self, name: &str) -> Option<String> {
+        fs::read_to_string(format!("/proc/{}/{}", self.pid, name)).ok()
+    }
+
+    fn cgroup_mount(&self) -> RuntimeCheck {
+        let name = "cgroup filesystem mounted at /sys/fs/cgroup";
+        let Some(mountinfo) = self.proc_file("mountinfo") else {
+            return RuntimeCheck { name, status: CheckStatus::Warn, detail: format!("can't read /proc/{}/mountinfo", self.pid) };
+        };
+
+        // mountinfo: ID parent major:minor root mount-point options ... - fstype source super-options
+        let mounts: Vec<(&str, &str)> = mountinfo
+            .lines()
+            .filter_map(|line| {
+                let mount_point = line.split_whitespace().nth(4)?;
+                let fs_type = line.split_once(" - ")?.1.split_whitespace().next()?;
+                Some((mount_point, fs_type))
+            })
+            .filter(|(mount_point, _)| mount_point.starts_with("/sys/fs/cgroup"))
+            .collect();
+
+        if mounts.iter().any(|(mount_point, fs_type)| *mount_point == "/sys/fs/cgroup" && *fs_type == "cgroup2") {
+            RuntimeCheck { name, status: CheckStatus::Pass, detail: "cgroup v2 (unified hierarchy)".to_string() }
+        } else if mounts.iter().any(|(_, fs_type)| *fs_type == "cgroup") {
+            let controllers = mounts.iter().filter(|(_, fs_type)| *fs_type == "cgroup").count();
+            RuntimeCheck {
+                name,
+                status: CheckStatus::Warn,
+                detail: format!("legacy cgroup v1 hierarchy with {} controller mount(s)", controllers),
+            }
+        } else {
+            RuntimeCheck { name, status: CheckStatus::Fail, detail: "no cgroup filesystem is mounted".to_string() }
+        }
+    }
+
+    fn user_namespace(&self) -> RuntimeCheck {
+        let name = "user namespace";
+        let Some(uid_map) = self.proc_file("uid_map") else {
+            return RuntimeCheck { name, status: CheckStatus::Warn, detail: format!("can't read /proc/{}/uid_map", self.pid) };
+        };
+
+        // The initial namespace maps every ID onto itself
+        let mapping: Vec<&str> = uid_map.split_whitespace().collect();
+        match mapping.as_slice() {
+            ["0", "0", "4294967295"] => RuntimeCheck {
+                name,
+                status: CheckStatus::Warn,
+                detail: "not used, root in the container is root on the host".to_string(),
+            },
+            [inside, outside, count, ..] => RuntimeCheck {
+                name,
+                status: CheckStatus::Pass,
+                detail: format!("uid {} in the container is uid {} on the host ({} IDs mapped)", inside, outside, count),
+            },
+            _ => RuntimeCheck { name, status: CheckStatus::Warn, detail: "empty uid_map".to_string() },
+        }
+    }
+
+    fn seccomp(&self) -> RuntimeCheck {
+        let name = "seccomp filter";
+        let mode = self
+            .proc_file("status")
+            .and_then(|status| status.lines().find_map(|line| line.strip_prefix("Seccomp:").map(|m| m.trim().to_string())));
+        match mode.as_deref() {
+            Some("2") => RuntimeCheck { name, status: CheckStatus::Pass, detail: "filter mode".to_string() },
+            Some("1") => RuntimeCheck { name, status: CheckStatus::Pass, detail: "strict mode".to_string() },
+            Some(_) => RuntimeCheck {
+                name,
+                status: CheckStatus::Warn,
+                detail: "disabled, the container can make every syscall (--privileged or seccomp=unconfined?)".to_string(),
+            },
+            None => RuntimeCheck { name, status: CheckStatus::Warn, detail: format!("can't read /proc/{}/status", self.pid) },
+        }
+    }
+}
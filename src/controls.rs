@@ -0,0 +1,77 @@
+//! Compliance control IDs attached to requirements.
+//!
+//! A comment starting with a bracketed ID, e.g. `# [CIS 1.1.1.1] Ensure mounting
+//! of cramfs filesystems is disabled`, tags the entries below it up to the next
+//! blank line, ID comment or section header. Results for tagged entries carry the IDs so findings
+//! can be traced back to the benchmark.
+
+use std::collections::BTreeMap;
+
+use crate::check;
+
+/// Control IDs by checked entry, keyed like check results (`CONFIG_FOO` or the rule)
+#[derive(Default)]
+pub struct ControlIds(BTreeMap<String, Vec<String>>);
+
+impl ControlIds {
+    /// Record the control IDs tagged in a flags file
+    pub fn add_file(&mut self, content: &str) {
+        // Files with broken groups are reported when their flags are read
+        let Ok(lines) = check::flag_lines(content) else {
+            return;
+        };
+        let mut current: Option<String> = None;
+        for (_, trimmed) in &lines {
+            let trimmed = trimmed.as_str();
+            if trimmed.is_empty() || check::section_header(trimmed).is_some() {
+                current = None;
+            } else if let Some(comment) = trimmed.strip_prefix('#') {
+                if let Some(id) = parse_tag(comment) {
+                    current = Some(id);
+                }
+            } else if let Some(id) = &current {
+                let ids = self.0.entry(result_name(trimmed)).or_default();
+                if !ids.contains(id) {
+                    ids.push(id.clone());
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> &[String] {
+        self.0.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// " [CIS 1.1.1.1]" for display after a result, empty when untagged
+    pub fn suffix(&self, name: &str) -> String {
+        suffix(self.get(name))
+    }
+}
+
+/// Format IDs for display after a result, empty when there are none
+pub fn suffix<S: AsRef<str>>(ids: &[S]) -> String {
+    if ids.is_empty() {
+        return String::new();
+    }
+    let ids: Vec<&str> = ids.iter().map(AsRef::as_ref).collect();
+    format!(" [{}]", ids.join(", "))
+}
+
+/// The ID of a `[ID] description` comment
+fn parse_tag(comment: &str) -> Option<String> {
+    let rest = comment.trim_start().strip_prefix('[')?;
+    let (id, _) = rest.split_once(']')?;
+    let id = id.trim();
+    (!id.is_empty()).then(|| id.to_string())
+}
+
+/// The name a flags file entry is reported under
+pub fn result_name(entry: &str) -> String {
+    let entry = check::unqualified(entry);
+    let name = match check::setting(entry) {
+        Some((name, _)) => name,
+        None if check::is_rule(entry) => return entry.to_string(),
+        None => entry,
+    };
+    format!("CONFIG_{}", name.strip_prefix("CONFIG_").unwrap_or(name))
+}
@@ -0,0 +1,44 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use crate::expr;
+
+/// Kernel config options and minimum kernel version something depends on
+#[derive(Default)]
+pub struct Requirements {
+    /// Flag name mapped to the reasons it is needed
+    pub flags: BTreeMap<String, Vec<String>>,
+    /// Minimum kernel version and the feature that sets it
+    pub min_version: Option<(String, String)>,
+}
+
+impl Requirements {
+    pub fn require(&mut self, flag: &str, reason: &str) {
+        let reasons = self.flags.entry(flag.to_string()).or_default();
+        if !reasons.iter().any(|r| r == reason) {
+            reasons.push(reason.to_string());
+        }
+    }
+
+    pub fn require_version(&mut self, version: &str, reason: &str) {
+        let newer = match &self.min_version {
+            Some((current, _)) => expr::compare_versions(version, current) == Ordering::Greater,
+            None => true,
+        };
+        if newer {
+            self.min_version = Some((version.to_string(), reason.to_string()));
+        }
+    }
+
+    /// Render the requirements as a flags file that kcc can check directly
+    pub fn to_flags_file(&self, source: &str) -> String {
+        let mut out = format!("# Kernel requirements derived by kcc from {}\n", source);
+        for (flag, reasons) in &self.flags {
+            out.push_str(&format!("\n# {}\n{}\n", reasons.join(", "), flag));
+        }
+        if let Some((version, reason)) = &self.min_version {
+            out.push_str(&format!("\n# Minimum kernel version: {}\nKERNEL>={}\n", reason, version));
+        }
+        out
+    }
+}
@@ -0,0 +1,113 @@
+//! Compare two kernel configs option by option, e.g. the config of a kernel and of its
+//! upgrade, and report what silently changed between them.
+
+use colored::*;
+use serde::Serialize;
+
+use crate::{ConfigValue, KernelConfig};
+
+/// How an option differs between two configs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Only the new config has the option
+    Added,
+    /// Only the old config has the option
+    Removed,
+    /// Switched between built in, module and not set
+    Tristate,
+    /// A number or string that changed
+    Value,
+}
+
+/// An option that differs between two configs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    /// The option, with its `CONFIG_` prefix
+    pub name: String,
+    pub kind: ChangeKind,
+    /// The value in the old config as written there, `n` when it is not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<String>,
+}
+
+/// The options that differ between two configs, grouped by kind and sorted by name. An option
+/// that is not set counts as `n`, so `# CONFIG_X is not set` and `CONFIG_X=n` are the same.
+pub fn diff(old: &KernelConfig, new: &KernelConfig) -> Vec<Change> {
+    let written = |value: &ConfigValue| match value.tristate() {
+        Some(state) => ConfigValue::Tristate(state).to_string(),
+        None => value.to_string(),
+    };
+    let mut changes = Vec::new();
+    for (name, value) in old.iter() {
+        let change = match new.get(name) {
+            None => Change { name: name.to_string(), kind: ChangeKind::Removed, old: Some(written(value)), new: None },
+            Some(other) if written(value) != written(other) => {
+                let both_tristate = value.tristate().is_some() && other.tristate().is_some();
+                let kind = if both_tristate { ChangeKind::Tristate } else { ChangeKind::Value };
+                Change { name: name.to_string(), kind, old: Some(written(value)), new: Some(written(other)) }
+            }
+            Some(_) => continue,
+        };
+        changes.push(change);
+    }
+    for (name, value) in new.iter().filter(|(name, _)| !old.contains(name)) {
+        changes.push(Change { name: name.to_string(), kind: ChangeKind::Added, old: None, new: Some(written(value)) });
+    }
+    changes.sort_by(|a, b| (a.kind, &a.name).cmp(&(b.kind, &b.name)));
+    changes
+}
+
+/// Whether an option starts with one of the prefixes, given with or without `CONFIG_`; any option
+/// does when there are none
+pub fn matches_prefix(name: &str, prefixes: &[String]) -> bool {
+    let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+    prefixes.is_empty() || prefixes.iter().any(|prefix| name.starts_with(prefix.strip_prefix("CONFIG_").unwrap_or(prefix)))
+}
+
+/// Compare two configs and print the options that differ, keeping those that start with one of
+/// the prefixes; returns the exit code, 1 when they differ like diff(1)
+pub fn run(old: &KernelConfig, new: &KernelConfig, prefixes: &[String], json: bool) -> i32 {
+    let changes: Vec<Change> = diff(old, new).into_iter().filter(|change| matches_prefix(&change.name, prefixes)).collect();
+    let code = if changes.is_empty() { 0 } else { 1 };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes).expect("config diff serializes"));
+        return code;
+    }
+
+    println!("🔍 Comparing {} → {}", old.source(), new.source());
+    println!();
+    if changes.is_empty() {
+        println!("{}", "✅ The configs don't differ".green().bold());
+    } else {
+        print_changes(&changes, "");
+    }
+    code
+}
+
+/// Print changes one per line, each after the indent, followed by a summary of their kinds
+pub fn print_changes(changes: &[Change], indent: &str) {
+    for change in changes {
+        let old_value = change.old.as_deref().unwrap_or_default();
+        let new_value = change.new.as_deref().unwrap_or_default();
+        match change.kind {
+            ChangeKind::Added => println!("{}{} {}={}", indent, "➕".green(), change.name.green(), new_value),
+            ChangeKind::Removed => println!("{}{} {}={}", indent, "➖".red(), change.name.red(), old_value),
+            ChangeKind::Tristate => println!("{}🔄 {}: {} → {}", indent, change.name.yellow(), old_value, new_value),
+            ChangeKind::Value => println!("{}✏️  {}: {} → {}", indent, change.name.cyan(), old_value, new_value),
+        }
+    }
+
+    let count = |kind: ChangeKind| changes.iter().filter(|change| change.kind == kind).count();
+    println!();
+    println!(
+        "📊 {} added, {} removed, {} switched between y/m/n, {} changed value",
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Tristate),
+        count(ChangeKind::Value)
+    );
+}
@@ -0,0 +1,194 @@
+//! Pre-flight checks for building an out-of-tree module with DKMS.
+//!
+//! A DKMS build needs more than a suitable config: the headers for the exact
+//! kernel release, a Module.symvers when the kernel uses symbol versions, and
+//! whatever the module's dkms.conf restricts the build to.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use colored::*;
+
+use crate::{expr, profiles, sysroot, ConfigValue, FlagStatus, KernelConfig};
+
+/// Where the build tree (headers) of a kernel release lives
+pub fn headers_dir(release: &str) -> PathBuf {
+    sysroot::path(&format!("/lib/modules/{}/build", release))
+}
+
+/// The release a headers tree was prepared for
+pub fn headers_release(build: &Path) -> Option<String> {
+    fs::read_to_string(build.join("include/config/kernel.release"))
+        .ok()
+        .map(|release| release.trim().to_string())
+}
+
+/// Check that `module` can be built for `release`, printing each finding; returns the exit code
+pub fn check(module: &str, release: &str, config: Option<&str>) -> anyhow::Result<i32> {
+    println!("🧱 DKMS build prerequisites for {} on kernel {}", module, release);
+    println!();
+
+    let mut problems = Vec::new();
+
+    let source = find_source(module);
+    match &source {
+        Some(dir) => println!("✅ Module source: {}", dir.display()),
+        None => println!(
+            "{}",
+            format!("⚠️  No /usr/src/{}-<version>/dkms.conf found, is the module's dkms package installed?", module).yellow()
+        ),
+    }
+
+    let build = headers_dir(release);
+    if build.join("Makefile").exists() {
+        println!("✅ Kernel headers: {}", build.display());
+        match headers_release(&build) {
+            Some(found) if found == release => {}
+            Some(found) => {
+                println!("{}", format!("❌ Headers in {} were prepared for {}", build.display(), found).red());
+                problems.push(format!("headers are for {}, not {}", found, release));
+            }
+            None => println!("💡 Can't tell which release the headers were prepared for"),
+        }
+    } else {
+        println!("{}", format!("❌ Kernel headers: {} is missing", build.display()).red());
+        problems.push(format!("install the headers package for {}", release));
+    }
+
+    // The config the headers were generated from is the one the module is built against
+    let config_path = match config {
+        Some(path) => Some(path.to_string()),
+        None => [build.join(".config"), sysroot::path(&format!("/boot/config-{}", release))]
+            .into_iter()
+            .find(|path| path.exists())
+            .map(|path| path.display().to_string()),
+    };
+    let Some(config_path) = config_path else {
+        println!("{}", format!("❌ No config found for {}, pass one with -c", release).red());
+        problems.push("no kernel config to check".to_string());
+        return Ok(summary(module, release, &problems));
+    };
+    let kernel_config = crate::KernelConfig::load(&config_path)?;
+    println!("📋 Config: {}", config_path);
+    println!();
+
+    for flag in crate::parse_flags(profiles::find("dkms")?.flags)? {
+        let result = kernel_config.check(&flag);
+        println!("{}", result.format_output());
+        if matches!(
+            result.status,
+            FlagStatus::Missing | FlagStatus::Disabled | FlagStatus::WrongValue | FlagStatus::Invalid | FlagStatus::RuleFailed | FlagStatus::Forbidden
+        ) {
+            problems.push(result.name);
+        }
+    }
+
+    // With symbol versions, modpost needs the CRCs of every exported symbol
+    let modversions = kernel_config.get("CONFIG_MODVERSIONS").is_some_and(ConfigValue::is_enabled);
+    if modversions {
+        if build.join("Module.symvers").metadata().is_ok_and(|m| m.len() > 0) {
+            println!("✅ Module.symvers present for MODVERSIONS");
+        } else {
+            println!("{}", "❌ CONFIG_MODVERSIONS is set but the headers have no Module.symvers".red());
+            problems.push("Module.symvers missing".to_string());
+        }
+    }
+
+    // A mismatch makes every load fail with "disagrees about version of symbol"
+    // The running kernel has nothing to do with a kernel installed under --root
+    let running = crate::audit::running_version().filter(|_| sysroot::get().is_none());
+    if running.as_deref() == Some(release) {
+        if let Ok(running_config) = crate::KernelConfig::load(crate::PROC_CONFIG) {
+            let running_modversions = running_config.get("CONFIG_MODVERSIONS").is_some_and(ConfigValue::is_enabled);
+            if running_modversions == modversions {
+                println!("✅ MODVERSIONS matches the running kernel");
+            } else {
+                println!(
+                    "{}",
+                    format!(
+                        "❌ MODVERSIONS is {} in {} but {} in the running kernel, built modules will be rejected",
+                        if modversions { "set" } else { "unset" },
+                        config_path,
+                        if running_modversions { "set" } else { "unset" }
+                    )
+                    .red()
+                );
+                problems.push("MODVERSIONS differs from the running kernel".to_string());
+            }
+        }
+    }
+
+    if let Some(dir) = &source {
+        problems.extend(check_build_exclusive(&dir.join("dkms.conf"), &kernel_config));
+    }
+
+    Ok(summary(module, release, &problems))
+}
+
+fn summary(module: &str, release: &str, problems: &[String]) -> i32 {
+    println!();
+    if problems.is_empty() {
+        println!("{}", format!("✅ {} should build for {}", module, release).green().bold());
+        0
+    } else {
+        println!("{}", format!("❌ {} will not build for {}:", module, release).red().bold());
+        for problem in problems {
+            println!("   - {}", problem);
+        }
+        1
+    }
+}
+
+/// The newest /usr/src/<module>-<version> with a dkms.conf, or the exact one for `module/version`
+fn find_source(module: &str) -> Option<PathBuf> {
+    if let Some((name, version)) = module.split_once('/') {
+        let dir = sysroot::path(&format!("/usr/src/{}-{}", name, version));
+        return dir.join("dkms.conf").exists().then_some(dir);
+    }
+
+    let prefix = format!("{}-", module);
+    fs::read_dir(sysroot::path("/usr/src"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let version = name.strip_prefix(&prefix)?.to_string();
+            entry.path().join("dkms.conf").exists().then(|| (version, entry.path()))
+        })
+        .max_by(|(a, _), (b, _)| expr::compare_versions(a, b))
+        .map(|(_, path)| path)
+}
+
+/// Evaluate the config options dkms.conf restricts the build to
+fn check_build_exclusive(dkms_conf: &Path, config: &KernelConfig) -> Vec<String> {
+    let Ok(content) = fs::read_to_string(dkms_conf) else {
+        return Vec::new();
+    };
+
+    let mut problems = Vec::new();
+    for line in content.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let value = value.trim().trim_matches('"').trim_matches('\'');
+        match key.trim() {
+            "BUILD_EXCLUSIVE_CONFIG" => {
+                let ctx = expr::Context::new(config);
+                for option in value.split_whitespace() {
+                    let satisfied = expr::parse(option).is_ok_and(|rule| expr::evaluate(&rule, &ctx));
+                    if satisfied {
+                        println!("✅ dkms.conf requires {}", option);
+                    } else {
+                        println!("{}", format!("❌ dkms.conf requires {}", option).red());
+                        problems.push(format!("dkms.conf BUILD_EXCLUSIVE_CONFIG {}", option));
+                    }
+                }
+            }
+            "BUILD_EXCLUSIVE_KERNEL" | "BUILD_EXCLUSIVE_ARCH" => {
+                println!("💡 dkms.conf also restricts the build with {}={}", key.trim(), value);
+            }
+            _ => {}
+        }
+    }
+    problems
+}
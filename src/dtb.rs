@@ -0,0 +1,216 @@
+//! Map the devices in a flattened device tree blob (.dtb) to the drivers a
+//! kernel needs to support the board.
+
+use std::fs;
+
+/// Driver options for well-known `compatible` strings; a trailing `*` matches a
+/// whole SoC family
+const DRIVERS: &[(&str, &[&str])] = &[
+    // SoC platforms (root node compatibles)
+    ("brcm,bcm2835", &["ARCH_BCM2835"]),
+    ("brcm,bcm2836", &["ARCH_BCM2835"]),
+    ("brcm,bcm2837", &["ARCH_BCM2835"]),
+    ("brcm,bcm2711", &["ARCH_BCM2835"]),
+    ("brcm,bcm2712", &["ARCH_BCM2835"]),
+    ("allwinner,sun*", &["ARCH_SUNXI"]),
+    ("rockchip,rk*", &["ARCH_ROCKCHIP"]),
+    ("amlogic,meson*", &["ARCH_MESON"]),
+    ("fsl,imx8*", &["ARCH_MXC"]),
+    ("ti,am33xx", &["SOC_AM33XX"]),
+    ("starfive,jh71*", &["ARCH_STARFIVE"]),
+    // Raspberry Pi peripherals
+    ("raspberrypi,bcm2835-firmware", &["RASPBERRYPI_FIRMWARE"]),
+    ("raspberrypi,bcm2835-power", &["RASPBERRYPI_POWER"]),
+    ("raspberrypi,firmware-clocks", &["CLK_RASPBERRYPI"]),
+    ("brcm,bcm2835-mbox", &["BCM2835_MBOX"]),
+    ("brcm,bcm2835-gpio", &["PINCTRL_BCM2835"]),
+    ("brcm,bcm2711-gpio", &["PINCTRL_BCM2835"]),
+    ("brcm,bcm2835-i2c", &["I2C_BCM2835"]),
+    ("brcm,bcm2835-spi", &["SPI_BCM2835"]),
+    ("brcm,bcm2835-aux-spi", &["SPI_BCM2835AUX"]),
+    ("brcm,bcm2835-aux-uart", &["SERIAL_8250_BCM2835AUX"]),
+    ("brcm,bcm2835-pwm", &["PWM_BCM2835"]),
+    ("brcm,bcm2835-sdhost", &["MMC_BCM2835"]),
+    ("brcm,bcm2835-sdhci", &["MMC_SDHCI_IPROC"]),
+    ("brcm,bcm2711-emmc2", &["MMC_SDHCI_IPROC"]),
+    ("brcm,bcm2835-vc4", &["DRM_VC4"]),
+    ("brcm,bcm2711-vc5", &["DRM_VC4"]),
+    ("brcm,bcm2711-hdmi*", &["DRM_VC4", "DRM_VC4_HDMI_CEC"]),
+    ("brcm,bcm2835-hdmi*", &["DRM_VC4"]),
+    ("brcm,bcm2835-i2s", &["SND_BCM2835_SOC_I2S"]),
+    ("brcm,bcm2711-pcie", &["PCIE_BRCMSTB"]),
+    ("brcm,bcm2711-thermal", &["BCM2711_THERMAL"]),
+    ("brcm,bcm2835-thermal", &["BCM2835_THERMAL"]),
+    ("brcm,genet-v5", &["BCMGENET"]),
+    ("brcm,bcm2711-genet-v5", &["BCMGENET"]),
+    ("brcm,bcm2835-rng", &["HW_RANDOM_BCM2835"]),
+    ("brcm,bcm2835-wdt", &["BCM2835_WDT"]),
+    ("brcm,bcm2835-dma", &["DMA_BCM2835"]),
+    ("brcm,bcm2835-system-timer", &["BCM2835_TIMER"]),
+    // Common IP blocks and generic bindings
+    ("arm,pl011", &["SERIAL_AMBA_PL011"]),
+    ("arm,gic-400", &["ARM_GIC"]),
+    ("arm,cortex-a15-gic", &["ARM_GIC"]),
+    ("arm,gic-v3", &["ARM_GIC_V3"]),
+    ("arm,armv7-timer", &["ARM_ARCH_TIMER"]),
+    ("arm,armv8-timer", &["ARM_ARCH_TIMER"]),
+    ("arm,psci", &["ARM_PSCI_FW"]),
+    ("arm,scmi", &["ARM_SCMI_PROTOCOL"]),
+    ("snps,dwc2", &["USB_DWC2"]),
+    ("snps,dwc3", &["USB_DWC3"]),
+    ("generic-xhci", &["USB_XHCI_PLATFORM"]),
+    ("generic-ehci", &["USB_EHCI_HCD_PLATFORM"]),
+    ("generic-ohci", &["USB_OHCI_HCD_PLATFORM"]),
+    ("snps,designware-i2c", &["I2C_DESIGNWARE_PLATFORM"]),
+    ("snps,dw-apb-uart", &["SERIAL_8250_DW"]),
+    ("snps,dw-apb-gpio", &["GPIO_DWAPB"]),
+    ("snps,dwmac", &["STMMAC_PLATFORM"]),
+    ("ns16550a", &["SERIAL_8250", "SERIAL_OF_PLATFORM"]),
+    ("gpio-leds", &["LEDS_GPIO"]),
+    ("gpio-keys", &["KEYBOARD_GPIO"]),
+    ("pwm-fan", &["SENSORS_PWM_FAN"]),
+    ("gpio-fan", &["SENSORS_GPIO_FAN"]),
+    ("mmc-pwrseq-simple", &["PWRSEQ_SIMPLE"]),
+    ("regulator-fixed", &["REGULATOR_FIXED_VOLTAGE"]),
+    ("regulator-gpio", &["REGULATOR_GPIO"]),
+    ("simple-audio-card", &["SND_SIMPLE_CARD"]),
+    ("w1-gpio", &["W1_MASTER_GPIO"]),
+    ("spidev", &["SPI_SPIDEV"]),
+    ("linux,spdif-dit", &["SND_SOC_SPDIF"]),
+    ("pci-host-ecam-generic", &["PCI_HOST_GENERIC"]),
+    ("virtio,mmio", &["VIRTIO_MMIO"]),
+];
+
+/// Devices described by a device tree and the driver options they need
+pub struct DeviceTree {
+    pub model: Option<String>,
+    /// Config symbols, without the CONFIG_ prefix, in first-seen order
+    pub flags: Vec<String>,
+    /// Compatible strings of enabled nodes that no driver mapping covers
+    pub unmapped: Vec<String>,
+}
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+#[derive(Default)]
+struct Node {
+    compatible: Vec<String>,
+    disabled: bool,
+}
+
+/// Read a .dtb and work out the drivers its enabled devices need
+pub fn load(path: &str) -> anyhow::Result<DeviceTree> {
+    let data = fs::read(path).map_err(|e| anyhow::anyhow!("Failed to read device tree {}: {}", path, e))?;
+    let u32_at = |offset: usize| -> anyhow::Result<u32> {
+        data.get(offset..offset + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| anyhow::anyhow!("Truncated device tree blob: {}", path))
+    };
+    let string_at = |offset: usize| -> String {
+        data.get(offset..)
+            .and_then(|s| s.split(|&b| b == 0).next())
+            .map(|s| String::from_utf8_lossy(s).to_string())
+            .unwrap_or_default()
+    };
+
+    if u32_at(0)? != FDT_MAGIC {
+        return Err(anyhow::anyhow!("{} is not a flattened device tree blob", path));
+    }
+    let struct_offset = u32_at(8)? as usize;
+    let strings_offset = u32_at(12)? as usize;
+
+    let mut tree = DeviceTree { model: None, flags: Vec::new(), unmapped: Vec::new() };
+    let mut stack: Vec<Node> = Vec::new();
+    let mut pos = struct_offset;
+
+    loop {
+        let token = u32_at(pos)?;
+        pos += 4;
+        match token {
+            FDT_BEGIN_NODE => {
+                let name_len = data[pos..].iter().position(|&b| b == 0).unwrap_or(0);
+                pos = align4(pos + name_len + 1);
+                // Children of a disabled node are unusable too
+                let disabled = stack.last().is_some_and(|parent| parent.disabled);
+                stack.push(Node { disabled, ..Default::default() });
+            }
+            FDT_END_NODE => {
+                let node = stack.pop().ok_or_else(|| anyhow::anyhow!("Malformed device tree blob: {}", path))?;
+                if !node.disabled {
+                    tree.add_node(&node.compatible);
+                }
+            }
+            FDT_PROP => {
+                let len = u32_at(pos)? as usize;
+                let name = string_at(strings_offset + u32_at(pos + 4)? as usize);
+                let value = data
+                    .get(pos + 8..pos + 8 + len)
+                    .ok_or_else(|| anyhow::anyhow!("Truncated device tree blob: {}", path))?;
+                pos = align4(pos + 8 + len);
+
+                let strings = || -> Vec<String> {
+                    value
+                        .split(|&b| b == 0)
+                        .filter(|s| !s.is_empty())
+                        .map(|s| String::from_utf8_lossy(s).to_string())
+                        .collect()
+                };
+                let Some(node) = stack.last_mut() else {
+                    continue;
+                };
+                match name.as_str() {
+                    "compatible" => node.compatible = strings(),
+                    "status" => node.disabled |= strings().first().is_some_and(|s| s != "okay" && s != "ok"),
+                    "model" if stack.len() == 1 => tree.model = strings().into_iter().next(),
+                    _ => {}
+                }
+            }
+            FDT_NOP => {}
+            FDT_END => break,
+            other => return Err(anyhow::anyhow!("Malformed device tree blob {}: unknown token {}", path, other)),
+        }
+    }
+
+    Ok(tree)
+}
+
+impl DeviceTree {
+    fn add_node(&mut self, compatible: &[String]) {
+        // The first compatible string with a known driver wins, as in the kernel
+        let drivers = compatible.iter().find_map(|c| {
+            DRIVERS
+                .iter()
+                .find(|(pattern, _)| match pattern.strip_suffix('*') {
+                    Some(prefix) => c.starts_with(prefix),
+                    None => c == pattern,
+                })
+                .map(|(_, flags)| *flags)
+        });
+
+        match drivers {
+            Some(flags) => {
+                for flag in flags {
+                    if !self.flags.iter().any(|f| f == flag) {
+                        self.flags.push(flag.to_string());
+                    }
+                }
+            }
+            None => {
+                if let Some(first) = compatible.first() {
+                    if !self.unmapped.contains(first) {
+                        self.unmapped.push(first.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn align4(pos: usize) -> usize {
+    (pos + 3) & !3
+}
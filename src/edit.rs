@@ -0,0 +1,102 @@
+//! Editing config files the way the kernel's `scripts/config` helper does.
+//!
+//! An option that the config already assigns or unsets is changed in place, every
+//! occurrence of it; one the config doesn't mention is appended at the end. Names
+//! are given with or without the `CONFIG_` prefix, and upper-cased unless asked
+//! to keep their case, as `scripts/config --keep-case` does.
+//!
+//! ```no_run
+//! use kcc::edit::{self, Edit};
+//!
+//! let config = std::fs::read_to_string(".config")?;
+//! let config = edit::apply(&config, "BPF_SYSCALL", &Edit::Enable);
+//! let config = edit::apply(&config, "LOCALVERSION", &Edit::SetStr("-kcc".to_string()));
+//! assert_eq!(edit::state(&config, "CONFIG_BPF_SYSCALL"), "y");
+//! std::fs::write(".config", config)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+/// A change to one option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Edit {
+    /// `CONFIG_X=y`
+    Enable,
+    /// `CONFIG_X=m`
+    Module,
+    /// `# CONFIG_X is not set`
+    Disable,
+    /// `CONFIG_X=<value>`, written as given
+    SetVal(String),
+    /// `CONFIG_X="<value>"`, with quotes in the value escaped
+    SetStr(String),
+    /// Remove the option from the config
+    Undefine,
+}
+
+/// An option name as written in the config: `CONFIG_` prefixed and upper-cased, unless `keep_case`
+pub fn option_name(name: &str, keep_case: bool) -> String {
+    let name = name.trim();
+    let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+    if keep_case {
+        format!("CONFIG_{}", name)
+    } else {
+        format!("CONFIG_{}", name.to_uppercase())
+    }
+}
+
+/// Whether a config line assigns or unsets the option
+fn mentions(line: &str, name: &str) -> bool {
+    let line = line.trim();
+    line.strip_prefix(name).is_some_and(|rest| rest.starts_with('='))
+        || line.strip_prefix("# ").and_then(|rest| rest.strip_prefix(name)) == Some(" is not set")
+}
+
+/// Apply an edit to the text of a config. `name` is used as written, with the `CONFIG_` prefix
+/// added when it's missing; `option_name` upper-cases it first.
+pub fn apply(config: &str, name: &str, edit: &Edit) -> String {
+    let name = option_name(name, true);
+    let line = match edit {
+        Edit::Enable => Some(format!("{}=y", name)),
+        Edit::Module => Some(format!("{}=m", name)),
+        Edit::Disable => Some(format!("# {} is not set", name)),
+        Edit::SetVal(value) => Some(format!("{}={}", name, value)),
+        Edit::SetStr(value) => Some(format!("{}=\"{}\"", name, value.replace('"', "\\\""))),
+        Edit::Undefine => None,
+    };
+
+    let mut found = false;
+    let mut lines = Vec::new();
+    for existing in config.lines() {
+        if !mentions(existing, &name) {
+            lines.push(existing.to_string());
+            continue;
+        }
+        found = true;
+        lines.extend(line.clone());
+    }
+    if !found {
+        lines.extend(line);
+    }
+
+    let mut edited = lines.join("\n");
+    if !edited.is_empty() {
+        edited.push('\n');
+    }
+    edited
+}
+
+/// The state of an option as `scripts/config --state` prints it: its value, with the quotes of
+/// strings removed, `n` when it is unset and `undef` when the config doesn't mention it
+pub fn state(config: &str, name: &str) -> String {
+    let name = option_name(name, true);
+    let Some(line) = config.lines().rfind(|line| mentions(line, &name)) else {
+        return "undef".to_string();
+    };
+    match line.trim().split_once('=') {
+        Some((_, value)) if !line.trim().starts_with('#') => match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(string) => string.replace("\\\"", "\""),
+            None => value.to_string(),
+        },
+        _ => "n".to_string(),
+    }
+}
@@ -0,0 +1,197 @@
+use crate::probes;
+
+/// Render missing flags, plain or `FLAG=value`, as a NixOS `boot.kernelPatches` entry
+pub fn nixos(missing_flags: &[String]) -> String {
+    let mut out = String::new();
+    out.push_str("boot.kernelPatches = [\n");
+    out.push_str("  {\n");
+    out.push_str("    name = \"kcc-required-options\";\n");
+    out.push_str("    patch = null;\n");
+    out.push_str("    extraStructuredConfig = with lib.kernel; {\n");
+    for flag in missing_flags {
+        let (flag, value) = setting(flag);
+        let symbol = flag.strip_prefix("CONFIG_").unwrap_or(flag);
+        let value = match value {
+            "y" => "yes".to_string(),
+            "m" => "module".to_string(),
+            "n" => "no".to_string(),
+            value => format!("freeform {:?}", value.trim_matches('"')),
+        };
+        // Nix identifiers can't start with a digit (e.g. 9P_FS)
+        if symbol.starts_with(|c: char| c.is_ascii_digit()) {
+            out.push_str(&format!("      \"{}\" = {};\n", symbol, value));
+        } else {
+            out.push_str(&format!("      {} = {};\n", symbol, value));
+        }
+    }
+    out.push_str("    };\n");
+    out.push_str("  }\n");
+    out.push_str("];\n");
+    out
+}
+
+/// Render missing flags as a kernel config fragment for Gentoo's /etc/kernel/config.d
+pub fn gentoo(missing_flags: &[String]) -> String {
+    let mut out = String::new();
+    out.push_str("# Kernel options required by kcc, merged by installkernel/gentoo-kernel\n");
+    for flag in missing_flags {
+        let (flag, value) = setting(flag);
+        out.push_str(&format!("{}={}\n", flag, value));
+    }
+    out
+}
+
+/// Split a missing flag into its name and the value to set, `y` unless it was asserted as `FLAG=value`
+fn setting(flag: &str) -> (&str, &str) {
+    flag.split_once('=').unwrap_or((flag, "y"))
+}
+
+/// Everything a failed check found that a remediation plan can address
+#[derive(Default)]
+pub struct FixPlan {
+    /// The checked config, merged with the fragment when it is a local file
+    pub config: String,
+    pub missing_flags: Vec<String>,
+    pub modules: Vec<String>,
+    pub sysctls: Vec<(String, String)>,
+    pub boot_params: Vec<String>,
+    /// Failures that need a person to look at them, with the reason
+    pub manual: Vec<String>,
+}
+
+impl FixPlan {
+    /// Record the fix for a failed probe or disabled feature
+    pub fn add(&mut self, fix: probes::Fix) {
+        match fix {
+            probes::Fix::Sysctl(name, value) => {
+                if !self.sysctls.iter().any(|(n, _)| n == name) {
+                    self.sysctls.push((name.to_string(), value.to_string()));
+                }
+            }
+            probes::Fix::BootParam(param) => {
+                if !self.boot_params.iter().any(|p| p == param) {
+                    self.boot_params.push(param.to_string());
+                }
+            }
+            probes::Fix::Module(name) => {
+                if !self.modules.iter().any(|m| m == name) {
+                    self.modules.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    /// Record a failure that has no automatic fix
+    pub fn note(&mut self, item: String) {
+        if !self.manual.contains(&item) {
+            self.manual.push(item);
+        }
+    }
+}
+
+/// Render an ordered, commented shell script that applies the remediation plan
+pub fn fixplan(plan: &FixPlan) -> String {
+    let rebuild = !plan.missing_flags.is_empty();
+    let reboot = rebuild || !plan.boot_params.is_empty();
+
+    let mut out = String::new();
+    out.push_str("#!/bin/sh\n");
+    out.push_str(&format!("# Remediation plan generated by kcc for {}\n", plan.config));
+    out.push_str("#\n");
+    out.push_str(&format!("# Kernel rebuild required: {}\n", if rebuild { "yes" } else { "no" }));
+    out.push_str(&format!("# Reboot required: {}\n", if reboot { "yes" } else { "no" }));
+    out.push_str("#\n");
+    out.push_str("# Review every step before running this script as root.\n");
+    out.push_str("set -e\n");
+
+    let mut step = 0;
+    let mut heading = |out: &mut String, title: &str| {
+        step += 1;
+        out.push_str(&format!("\n# {}. {}\n", step, title));
+    };
+
+    if rebuild {
+        heading(&mut out, "Merge the missing options into the kernel config");
+        out.push_str("KERNEL_SRC=${KERNEL_SRC:-/usr/src/linux}\n");
+        out.push_str("FRAGMENT=$(mktemp)\n");
+        out.push_str("cat > \"$FRAGMENT\" <<'EOF'\n");
+        for flag in &plan.missing_flags {
+            let (flag, value) = setting(flag);
+            out.push_str(&format!("{}={}\n", flag, value));
+        }
+        out.push_str("EOF\n");
+        if crate::remote::is_remote(&plan.config) {
+            out.push_str(&format!("# Start from a copy of {} in $KERNEL_SRC/.config\n", plan.config));
+        } else if plan.config.ends_with(".gz") {
+            out.push_str(&format!("zcat {} > \"$KERNEL_SRC/.config\"\n", shell_quote(&plan.config)));
+        } else if plan.config.ends_with(".xz") {
+            out.push_str(&format!("xzcat {} > \"$KERNEL_SRC/.config\"\n", shell_quote(&plan.config)));
+        } else if plan.config.ends_with(".zst") {
+            out.push_str(&format!("zstdcat {} > \"$KERNEL_SRC/.config\"\n", shell_quote(&plan.config)));
+        } else {
+            out.push_str(&format!("cp {} \"$KERNEL_SRC/.config\"\n", shell_quote(&plan.config)));
+        }
+        out.push_str("cd \"$KERNEL_SRC\"\n");
+        out.push_str("scripts/kconfig/merge_config.sh -m .config \"$FRAGMENT\"\n");
+
+        heading(&mut out, "Resolve dependencies of the new options");
+        out.push_str("# olddefconfig silently drops options whose dependencies aren't met, rerun kcc afterwards\n");
+        out.push_str("make olddefconfig\n");
+
+        heading(&mut out, "Rebuild and install the kernel");
+        out.push_str("# Uncomment, or use your distribution's kernel packaging instead\n");
+        out.push_str("# make -j\"$(nproc)\"\n");
+        out.push_str("# make modules_install install\n");
+    }
+
+    if !plan.modules.is_empty() {
+        heading(&mut out, "Load modules, and load them on every boot");
+        for module in &plan.modules {
+            out.push_str(&format!("modprobe {}\n", module));
+        }
+        for module in &plan.modules {
+            out.push_str(&format!("echo {} >> /etc/modules-load.d/kcc.conf\n", module));
+        }
+    }
+
+    if !plan.sysctls.is_empty() {
+        heading(&mut out, "Set sysctls now and persist them");
+        for (name, value) in &plan.sysctls {
+            out.push_str(&format!("sysctl -w {}\n", shell_quote(&format!("{}={}", name, value))));
+        }
+        for (name, value) in &plan.sysctls {
+            out.push_str(&format!("echo {} >> /etc/sysctl.d/90-kcc.conf\n", shell_quote(&format!("{} = {}", name, value))));
+        }
+    }
+
+    if !plan.boot_params.is_empty() {
+        heading(&mut out, "Add boot parameters (effective after the next reboot)");
+        let params = plan.boot_params.join(" ");
+        out.push_str("if command -v grubby >/dev/null; then\n");
+        out.push_str(&format!("    grubby --update-kernel=ALL --args={}\n", shell_quote(&params)));
+        out.push_str("else\n");
+        out.push_str(&format!("    echo 'Add to GRUB_CMDLINE_LINUX in /etc/default/grub and run update-grub: {}'\n", params.replace('\'', "")));
+        out.push_str("fi\n");
+    }
+
+    if !plan.manual.is_empty() {
+        heading(&mut out, "Needs a manual fix");
+        for item in &plan.manual {
+            out.push_str(&format!("#   - {}\n", item));
+        }
+    }
+
+    if reboot {
+        heading(&mut out, "Reboot, then check again with kcc");
+    }
+    out
+}
+
+/// Quote a value for the shell, leaving plain words alone
+fn shell_quote(value: &str) -> String {
+    if value.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=,:".contains(c)) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,317 @@
+//! Boolean rule expressions for requirements that a plain flag list can't express,
+//! e.g. `CONFIG_SECCOMP=y && (CONFIG_SECCOMP_FILTER=y || KERNEL>=5.10)`.
+//!
+//! Each test is a config symbol (the `CONFIG_` prefix is optional), `KERNEL`
+//! (the version from the config header) or `ARCH`, optionally followed by a
+//! comparison: `=`/`==`, `!=`, `<`, `<=`, `>`, `>=`. A bare symbol is true when
+//! it is built in or a module. Tests combine with `!`, `&&`, `||` and parentheses.
+
+use std::cmp::Ordering;
+
+use crate::arch;
+use crate::config::{ConfigValue, KernelConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Test { symbol: String, op: Option<(Op, String)> },
+}
+
+/// Values an expression is evaluated against
+pub struct Context<'a> {
+    pub config: &'a KernelConfig,
+    pub arch: Option<String>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(config: &'a KernelConfig) -> Self {
+        Context { config, arch: arch::detect_config_arch(config) }
+    }
+}
+
+/// Returns true if a flags-file line is a rule expression rather than a plain flag
+pub fn is_expression(line: &str) -> bool {
+    ["&&", "||", "(", "!", "<", ">"].iter().any(|op| line.contains(op))
+}
+
+/// Parse an expression
+pub fn parse(input: &str) -> anyhow::Result<Expr> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_or()?;
+
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(anyhow::anyhow!("Unexpected {:?} in expression: {}", token, input));
+    }
+
+    Ok(expr)
+}
+
+/// Evaluate an expression against a kernel config
+pub fn evaluate(expr: &Expr, ctx: &Context) -> bool {
+    match expr {
+        Expr::And(a, b) => evaluate(a, ctx) && evaluate(b, ctx),
+        Expr::Or(a, b) => evaluate(a, ctx) || evaluate(b, ctx),
+        Expr::Not(e) => !evaluate(e, ctx),
+        Expr::Test { symbol, op } => evaluate_test(symbol, op.as_ref(), ctx),
+    }
+}
+
+/// The config symbols an expression tests, with their `CONFIG_` prefix
+pub fn symbols(expr: &Expr) -> Vec<String> {
+    match expr {
+        Expr::And(a, b) | Expr::Or(a, b) => [symbols(a), symbols(b)].concat(),
+        Expr::Not(e) => symbols(e),
+        Expr::Test { symbol, .. } if symbol == "KERNEL" || symbol == "ARCH" => Vec::new(),
+        Expr::Test { symbol, .. } => vec![format!("CONFIG_{}", symbol.strip_prefix("CONFIG_").unwrap_or(symbol))],
+    }
+}
+
+/// Kernel version from a config header like `# Linux/x86 6.8.0 Kernel Configuration`
+pub fn kernel_version(config: &str) -> Option<String> {
+    config.lines().take(10).find_map(|line| {
+        let rest = line.strip_prefix("# Linux")?;
+        let rest = rest.strip_suffix(" Kernel Configuration")?;
+        rest.split_whitespace().last().map(String::from)
+    })
+}
+
+fn evaluate_test(symbol: &str, op: Option<&(Op, String)>, ctx: &Context) -> bool {
+    match symbol {
+        "KERNEL" => {
+            let (Some(version), Some((op, expected))) = (kernel_version(ctx.config.text()), op) else {
+                return false;
+            };
+            compare(compare_versions(&version, expected), *op)
+        }
+        "ARCH" => {
+            let (Some(actual), Some((op, expected))) = (&ctx.arch, op) else {
+                return false;
+            };
+            compare(actual.cmp(&arch::normalize(expected)), *op)
+        }
+        _ => {
+            let actual = ctx.config.get(symbol).filter(|value| **value != ConfigValue::NotSet);
+
+            match op {
+                None => actual.is_some_and(ConfigValue::is_enabled),
+                Some((op @ (Op::Eq | Op::Ne), expected)) => {
+                    let equal = match actual {
+                        // An absent option behaves like n, as in Kconfig
+                        None => expected == "n",
+                        Some(ConfigValue::String(actual)) => actual == expected.trim_matches('"'),
+                        Some(actual) => match (actual.number(), parse_number(expected)) {
+                            (Some(actual), Some(expected)) => actual == expected,
+                            _ => actual.to_string() == expected.trim_matches('"'),
+                        },
+                    };
+                    equal == (*op == Op::Eq)
+                }
+                Some((op, expected)) => match (actual.and_then(number), parse_number(expected)) {
+                    (Some(actual), Some(expected)) => compare(actual.cmp(&expected), *op),
+                    _ => false,
+                },
+            }
+        }
+    }
+}
+
+fn compare(ordering: Ordering, op: Op) -> bool {
+    match op {
+        Op::Eq => ordering == Ordering::Equal,
+        Op::Ne => ordering != Ordering::Equal,
+        Op::Lt => ordering == Ordering::Less,
+        Op::Le => ordering != Ordering::Greater,
+        Op::Gt => ordering == Ordering::Greater,
+        Op::Ge => ordering != Ordering::Less,
+    }
+}
+
+/// Compare dotted versions numerically, ignoring any suffix like "-rc1" or "+"
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| -> Vec<u64> {
+        v.split(['.', '-', '+'])
+            .map_while(|part| part.parse().ok())
+            .collect()
+    };
+    let (a, b) = (parts(a), parts(b));
+
+    for i in 0..a.len().max(b.len()) {
+        let ordering = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// A config value as a number, including strings that hold one
+fn number(value: &ConfigValue) -> Option<i64> {
+    match value {
+        ConfigValue::String(string) => parse_number(string),
+        value => value.number(),
+    }
+}
+
+/// Parse a decimal or 0x-prefixed hex config value
+pub fn parse_number(value: &str) -> Option<i64> {
+    let value = value.trim_matches('"');
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(hex) => i64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Value(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let (token, len) = match (c, next) {
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('!', _) => (Token::Not, 1),
+            ('=', _) => (Token::Op(Op::Eq), 1),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            _ => {
+                // Whatever follows a comparison operator is a value, anything else a symbol
+                let after_op = matches!(tokens.last(), Some(Token::Op(_)));
+                let start = i;
+                if after_op && c == '"' {
+                    i += 1;
+                    while i < chars.len() && chars[i] != '"' {
+                        i += 1;
+                    }
+                    if i == chars.len() {
+                        return Err(anyhow::anyhow!("Unterminated string in expression: {}", input));
+                    }
+                    i += 1;
+                } else {
+                    while i < chars.len() && (chars[i].is_alphanumeric() || "_.-+".contains(chars[i])) {
+                        i += 1;
+                    }
+                }
+                if i == start {
+                    return Err(anyhow::anyhow!("Unexpected '{}' in expression: {}", c, input));
+                }
+
+                let word: String = chars[start..i].iter().collect();
+                tokens.push(if after_op { Token::Value(word) } else { Token::Ident(word) });
+                continue;
+            }
+        };
+
+        tokens.push(token);
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> anyhow::Result<Expr> {
+        let mut expr = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> anyhow::Result<Expr> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(anyhow::anyhow!("Missing closing parenthesis")),
+                }
+            }
+            Some(Token::Ident(symbol)) => {
+                let op = match self.peek() {
+                    Some(Token::Op(op)) => {
+                        let op = *op;
+                        self.pos += 1;
+                        match self.next() {
+                            Some(Token::Value(value)) => Some((op, value)),
+                            _ => return Err(anyhow::anyhow!("Missing value after comparison on {}", symbol)),
+                        }
+                    }
+                    _ => None,
+                };
+                if (symbol == "KERNEL" || symbol == "ARCH") && op.is_none() {
+                    return Err(anyhow::anyhow!("{} must be compared to a value", symbol));
+                }
+                Ok(Expr::Test { symbol, op })
+            }
+            Some(token) => Err(anyhow::anyhow!("Unexpected {:?}", token)),
+            None => Err(anyhow::anyhow!("Unexpected end of expression")),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! C API for linking kcc's checking logic into C and C++ tools.
+//!
+//! ```c
+//! #include "kcc.h"
+//!
+//! KccConfig *config = kcc_config_parse(data, len);
+//! if (!config) {
+//!     fprintf(stderr, "kcc: %s\n", kcc_last_error());
+//!     return 1;
+//! }
+//! if (kcc_check_flag(config, "CONFIG_BPF_SYSCALL") != KCC_STATUS_ENABLED) { ... }
+//! char *report = kcc_check_flags(config, "CONFIG_BPF\nCONFIG_USER_NS=y\n");
+//! kcc_string_free(report);
+//! kcc_config_free(config);
+//! ```
+//!
+//! Strings passed in are NUL-terminated UTF-8. Strings returned by kcc are released with
+//! `kcc_string_free`, configs with `kcc_config_free`. `include/kcc.h` is generated from this
+//! module with `cbindgen --config cbindgen.toml --output include/kcc.h`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use crate::check::FlagStatus;
+use crate::config::KernelConfig;
+use crate::report::Report;
+
+/// A parsed kernel config
+pub struct KccConfig(KernelConfig);
+
+/// Outcome of checking a single flag or rule
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KccStatus {
+    Enabled = 0,
+    Module = 1,
+    Missing = 2,
+    /// The flag doesn't exist in kernel config options
+    Invalid = 3,
+    RulePass = 4,
+    RuleFail = 5,
+    /// Compiled in, but switched off at runtime
+    AdminDisabled = 6,
+    /// Required to be disabled, and it is
+    Off = 7,
+    /// Required to be disabled, but enabled
+    Forbidden = 8,
+    /// In the config, but not set
+    Disabled = 9,
+    /// Set, but to a value that isn't accepted
+    WrongValue = 10,
+    /// The arguments were invalid, see `kcc_last_error`
+    Error = -1,
+}
+
+impl From<FlagStatus> for KccStatus {
+    fn from(status: FlagStatus) -> Self {
+        match status {
+            FlagStatus::EnabledInKernel => KccStatus::Enabled,
+            FlagStatus::EnabledAsModule => KccStatus::Module,
+            FlagStatus::Missing => KccStatus::Missing,
+            FlagStatus::Disabled => KccStatus::Disabled,
+            FlagStatus::WrongValue => KccStatus::WrongValue,
+            FlagStatus::Invalid => KccStatus::Invalid,
+            FlagStatus::RulePassed => KccStatus::RulePass,
+            FlagStatus::RuleFailed => KccStatus::RuleFail,
+            FlagStatus::AdminDisabled => KccStatus::AdminDisabled,
+            FlagStatus::Off => KccStatus::Off,
+            FlagStatus::Forbidden => KccStatus::Forbidden,
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: impl std::fmt::Display) {
+    let message = CString::new(message.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+/// Read a NUL-terminated UTF-8 argument
+///
+/// # Safety
+///
+/// `text` must be NULL or point to a NUL-terminated string.
+unsafe fn argument<'a>(text: *const c_char, name: &str) -> Option<&'a str> {
+    if text.is_null() {
+        set_error(format!("{} is NULL", name));
+        return None;
+    }
+    match CStr::from_ptr(text).to_str() {
+        Ok(text) => Some(text),
+        Err(_) => {
+            set_error(format!("{} is not valid UTF-8", name));
+            None
+        }
+    }
+}
+
+/// The message of the last error on this thread, or NULL. The string stays valid until the
+/// next failing call on the same thread and must not be freed.
+#[no_mangle]
+pub extern "C" fn kcc_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Parse a config from a buffer: plain text, gzip, xz or zstd, or a kernel image with an
+/// embedded config. Returns NULL on failure.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn kcc_config_parse(data: *const u8, len: usize) -> *mut KccConfig {
+    if data.is_null() {
+        set_error("data is NULL");
+        return ptr::null_mut();
+    }
+    let data = std::slice::from_raw_parts(data, len);
+    match KernelConfig::from_bytes("buffer", data) {
+        Ok(config) => Box::into_raw(Box::new(KccConfig(config))),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a config returned by `kcc_config_parse`; NULL is ignored
+///
+/// # Safety
+///
+/// `config` must be NULL or a config from `kcc_config_parse` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn kcc_config_free(config: *mut KccConfig) {
+    if !config.is_null() {
+        drop(Box::from_raw(config));
+    }
+}
+
+/// Check a flag, with or without its `CONFIG_` prefix, or a rule expression
+///
+/// # Safety
+///
+/// `config` must be a live config from `kcc_config_parse` and `flag` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kcc_check_flag(config: *const KccConfig, flag: *const c_char) -> KccStatus {
+    let Some(config) = config.as_ref() else {
+        set_error("config is NULL");
+        return KccStatus::Error;
+    };
+    match argument(flag, "flag") {
+        Some(flag) => config.0.check(flag.trim()).status.into(),
+        None => KccStatus::Error,
+    }
+}
+
+/// Check the config against the text of a flags file and return the report as JSON, the
+/// document `--format json` prints. Returns NULL when the flags don't parse.
+///
+/// # Safety
+///
+/// `config` must be a live config from `kcc_config_parse` and `flags` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kcc_check_flags(config: *const KccConfig, flags: *const c_char) -> *mut c_char {
+    let Some(config) = config.as_ref() else {
+        set_error("config is NULL");
+        return ptr::null_mut();
+    };
+    let Some(flags) = argument(flags, "flags") else {
+        return ptr::null_mut();
+    };
+    let report = Report::from_flags(&config.0, "flags", flags).and_then(|report| Ok(serde_json::to_string(&report)?));
+    match report {
+        Ok(json) => CString::new(json).map_or(ptr::null_mut(), CString::into_raw),
+        Err(e) => {
+            set_error(e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a string returned by kcc; NULL is ignored
+///
+/// # Safety
+///
+/// `text` must be NULL or a string returned by kcc that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn kcc_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
@@ -0,0 +1,32 @@
+//! Capturing the options a known-good kernel enables as a flags file, so the same
+//! requirements can be checked on other machines.
+
+use crate::pattern::{self, Pattern};
+use crate::{expr, KernelConfig, Tristate};
+
+/// The flags file requiring the enabled options and set values of a config selected by the
+/// patterns, or all of them without patterns. Built-in options are required built in, modules
+/// as modules or built in, and numbers and strings with their value; options that are not set
+/// are left out.
+pub fn flags_file(config: &KernelConfig, patterns: &[Pattern]) -> String {
+    let mut out = format!("# Kernel requirements generated by kcc from {}\n", config.source());
+    if let Some(version) = expr::kernel_version(config.text()) {
+        out.push_str(&format!("# Kernel version: {}\n", version));
+    }
+    if !patterns.is_empty() {
+        let patterns: Vec<&str> = patterns.iter().map(Pattern::as_str).collect();
+        out.push_str(&format!("# Options matching: {}\n", patterns.join(", ")));
+    }
+    out.push('\n');
+
+    for (name, value) in config.iter().filter(|(name, _)| pattern::matches_any(patterns, name)) {
+        let entry = match value.tristate() {
+            Some(Tristate::No) => continue,
+            Some(Tristate::Module) => format!("{}=m|y", name),
+            _ => format!("{}={}", name, value),
+        };
+        out.push_str(&entry);
+        out.push('\n');
+    }
+    out
+}
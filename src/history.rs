@@ -0,0 +1,165 @@
+//! Local history of check runs, for spotting when a host started or stopped
+//! satisfying its requirements.
+//!
+//! Every check appends a one-line JSON summary to `$XDG_STATE_HOME/kcc/history.jsonl`
+//! (`~/.local/state/kcc/history.jsonl` by default).
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::*;
+use serde::{Deserialize, Serialize};
+
+/// Summary of a single check run
+#[derive(Serialize, Deserialize)]
+pub struct Run {
+    /// Seconds since the Unix epoch
+    pub time: u64,
+    pub host: String,
+    pub config: String,
+    pub config_sha256: String,
+    /// The profiles, flags files and --set-flags that were checked
+    pub spec: String,
+    pub spec_sha256: String,
+    pub checked: usize,
+    pub missing: usize,
+    pub invalid: usize,
+    pub failed: usize,
+    pub exit_code: i32,
+}
+
+/// Where the history is kept
+pub fn path() -> Option<PathBuf> {
+    let state = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state.join("kcc/history.jsonl"))
+}
+
+/// The current time and host name, for a new run
+pub fn now_and_host() -> (u64, String) {
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let host = fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    (time, host)
+}
+
+/// Append a run to the history
+pub fn record(run: &Run) -> anyhow::Result<()> {
+    let path = path().ok_or_else(|| anyhow::anyhow!("Failed to locate the history file: HOME is not set"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| anyhow::anyhow!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+    writeln!(file, "{}", serde_json::to_string(run)?).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Print the recorded runs as a timeline per spec
+pub fn show(spec_filter: Option<&str>, changes_only: bool, limit: Option<usize>) -> anyhow::Result<()> {
+    let path = path().ok_or_else(|| anyhow::anyhow!("Failed to locate the history file: HOME is not set"))?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("📜 No runs recorded yet in {}", path.display());
+            return Ok(());
+        }
+        Err(e) => return Err(anyhow::anyhow!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    // A line cut short by an interrupted write shouldn't hide the rest
+    let runs: Vec<Run> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .filter(|run: &Run| spec_filter.is_none_or(|filter| run.spec.contains(filter)))
+        .collect();
+
+    println!("📜 {} run(s) recorded in {}", runs.len(), path.display());
+
+    // Group by spec, in the order each spec was first checked
+    let mut specs: Vec<(&str, &str)> = Vec::new();
+    for run in &runs {
+        if !specs.contains(&(run.spec.as_str(), run.spec_sha256.as_str())) {
+            specs.push((&run.spec, &run.spec_sha256));
+        }
+    }
+
+    for (spec, spec_sha256) in specs {
+        println!();
+        println!("{} (spec {})", spec.bold(), short(spec_sha256));
+
+        let mut lines = Vec::new();
+        let mut previous: Option<&Run> = None;
+        for run in runs.iter().filter(|run| run.spec == spec && run.spec_sha256 == spec_sha256) {
+            let change = match previous {
+                Some(prev) if (prev.exit_code == 0) != (run.exit_code == 0) => {
+                    if run.exit_code == 0 {
+                        "⬆ started satisfying".green().to_string()
+                    } else {
+                        "⬇ stopped satisfying".red().to_string()
+                    }
+                }
+                Some(prev) if prev.config_sha256 != run.config_sha256 => "config changed".to_string(),
+                Some(prev) if prev.host != run.host => "host changed".to_string(),
+                Some(_) => String::new(),
+                None => "first run".to_string(),
+            };
+            previous = Some(run);
+            if changes_only && change.is_empty() {
+                continue;
+            }
+
+            let status = if run.exit_code == 0 {
+                format!("{:<14}", "✅ satisfied").green()
+            } else {
+                format!("{:<14}", format!("❌ {} failing", run.missing + run.invalid + run.failed)).red()
+            };
+            lines.push(format!(
+                "  {}  {}  {}  config {} ({})  {}",
+                format_time(run.time),
+                status,
+                run.host,
+                short(&run.config_sha256),
+                run.config,
+                change
+            ));
+        }
+
+        let skip = limit.map_or(0, |limit| lines.len().saturating_sub(limit));
+        for line in &lines[skip..] {
+            println!("{}", line.trim_end());
+        }
+    }
+
+    Ok(())
+}
+
+fn short(digest: &str) -> &str {
+    digest.get(..12).unwrap_or(digest)
+}
+
+/// "YYYY-MM-DD HH:MM" in UTC
+pub fn format_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let minutes = secs % 86400 / 60;
+
+    // Civil date from days since 1970-01-01, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, minutes / 60, minutes % 60)
+}
@@ -0,0 +1,47 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Run the pre-hook before checking; a failing pre-hook aborts the run
+pub fn run_pre_hook(cmd: &str, env: &[(&str, String)]) -> anyhow::Result<()> {
+    let status = shell(cmd)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run pre-hook: {}", e))?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("Pre-hook failed with {}: {}", status, cmd));
+    }
+
+    Ok(())
+}
+
+/// Run the post-hook with the report on stdin and the results in the environment
+pub fn run_post_hook(cmd: &str, report: &str, env: &[(&str, String)]) -> anyhow::Result<()> {
+    let mut child = shell(cmd)
+        .envs(env.iter().map(|(k, v)| (k, v)))
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to run post-hook: {}", e))?;
+
+    // A hook that doesn't read its stdin closes the pipe early, which is fine
+    let _ = child.stdin.take().expect("stdin is piped").write_all(report.as_bytes());
+    let status = child.wait()?;
+
+    if !status.success() {
+        return Err(anyhow::anyhow!("Post-hook failed with {}: {}", status, cmd));
+    }
+
+    Ok(())
+}
+
+fn shell(cmd: &str) -> Command {
+    if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.args(["/C", cmd]);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.args(["-c", cmd]);
+        command
+    }
+}
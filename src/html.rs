@@ -0,0 +1,162 @@
+//! Standalone HTML report of a check, for sharing results with people who
+//! don't use the command line.
+//!
+//! The page has no external resources: styles and the small script that filters
+//! the table by status and text are inlined.
+
+use crate::report::Report;
+
+const STYLE: &str = r#"
+body { font: 14px/1.5 system-ui, sans-serif; margin: 2em auto; max-width: 72em; padding: 0 1em; color: #222; }
+h1 { font-size: 1.5em; margin-bottom: 0.2em; }
+.verdict { font-weight: bold; }
+.verdict.ok { color: #1a7f37; }
+.verdict.failed { color: #cf222e; }
+dl { display: grid; grid-template-columns: max-content auto; gap: 0.2em 1.5em; }
+dt { color: #666; }
+dd { margin: 0; font-family: ui-monospace, monospace; }
+.counts span { margin-right: 1.5em; }
+.filters { margin: 1.5em 0 0.8em; display: flex; gap: 1em; flex-wrap: wrap; align-items: center; }
+.filters input[type=search] { padding: 0.3em 0.5em; min-width: 20em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.35em 0.6em; border-bottom: 1px solid #ddd; vertical-align: top; }
+th { background: #f6f8fa; }
+td.name { font-family: ui-monospace, monospace; }
+.badge { display: inline-block; padding: 0 0.6em; border-radius: 1em; font-size: 0.85em; font-weight: 600; white-space: nowrap; }
+.badge.pass { background: #dafbe1; color: #1a7f37; }
+.badge.warn { background: #fff8c5; color: #9a6700; }
+.badge.fail { background: #ffebe9; color: #cf222e; }
+.controls { color: #666; font-size: 0.9em; }
+"#;
+
+const SCRIPT: &str = r#"
+function applyFilters() {
+  const text = document.getElementById('search').value.toLowerCase();
+  const shown = Array.from(document.querySelectorAll('.filters input[type=checkbox]'))
+    .filter(box => box.checked).map(box => box.value);
+  document.querySelectorAll('tbody tr').forEach(row => {
+    const visible = shown.includes(row.dataset.outcome) && row.textContent.toLowerCase().includes(text);
+    row.style.display = visible ? '' : 'none';
+  });
+}
+document.querySelectorAll('.filters input').forEach(input => input.addEventListener('input', applyFilters));
+"#;
+
+/// The kernel version line and compiler from the comment header of a config
+pub fn config_metadata(config_content: &str) -> Vec<(&'static str, String)> {
+    let mut metadata = Vec::new();
+    for line in config_content.lines().take(10) {
+        if let Some(kernel) = line.strip_prefix("# ").and_then(|line| line.strip_suffix(" Kernel Configuration")) {
+            metadata.push(("Kernel", kernel.to_string()));
+        }
+    }
+    if let Some(compiler) = config_content.lines().find_map(|line| line.strip_prefix("CONFIG_CC_VERSION_TEXT=")) {
+        metadata.push(("Compiler", compiler.trim_matches('"').to_string()));
+    }
+    metadata
+}
+
+/// Render the report as a standalone page, with the given metadata listed under the title
+pub fn render(report: &Report, metadata: &[(&str, String)]) -> String {
+    let passed = report.exit_code == 0;
+    let mut out = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>kcc report: {}</title>\n", escape(&report.config)));
+    out.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+
+    out.push_str("<h1>Kernel Config Checker report</h1>\n");
+    out.push_str(&format!(
+        "<p class=\"verdict {}\">{}</p>\n",
+        if passed { "ok" } else { "failed" },
+        match (passed, report.summary.checked) {
+            (true, 0) => "No requirements apply to this config",
+            (true, _) => "All required kernel flags are enabled",
+            (false, _) => "Some requirements are not met",
+        }
+    ));
+
+    out.push_str("<dl>\n");
+    out.push_str(&format!("<dt>Config</dt><dd>{}</dd>\n", escape(&report.config)));
+    for (label, value) in metadata {
+        out.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", escape(label), escape(value)));
+    }
+    out.push_str("</dl>\n");
+
+    let summary = &report.summary;
+    out.push_str("<p class=\"counts\">");
+    for (label, count) in [
+        ("checked", summary.checked),
+        ("passed", summary.passed),
+        ("missing", summary.missing),
+        ("not set", summary.disabled),
+        ("wrong value", summary.wrong_value),
+        ("invalid", summary.invalid),
+        ("failed rules", summary.failed_rules),
+        ("administratively disabled", summary.admin_disabled),
+        ("forbidden", summary.forbidden),
+        ("failed probes", summary.failed_probes),
+        ("failed container checks", summary.failed_container_checks),
+        ("failed plugin checks", summary.failed_plugin_checks),
+        ("failed policies", summary.failed_policies),
+        ("advisory", summary.advisory),
+    ] {
+        if count > 0 || label == "checked" || label == "passed" {
+            out.push_str(&format!("<span><strong>{}</strong> {}</span>", count, label));
+        }
+    }
+    out.push_str("</p>\n");
+
+    out.push_str("<div class=\"filters\">\n<input id=\"search\" type=\"search\" placeholder=\"Filter by name, source, reason…\">\n");
+    for (outcome, label) in [("fail", "Failed"), ("warn", "Warnings"), ("pass", "Passed")] {
+        out.push_str(&format!("<label><input type=\"checkbox\" value=\"{}\" checked> {}</label>\n", outcome, label));
+    }
+    out.push_str("</div>\n");
+
+    out.push_str("<table>\n<thead><tr><th>Status</th><th>Name</th><th>Required</th><th>Source</th><th>Details</th></tr></thead>\n<tbody>\n");
+    for result in &report.results {
+        let outcome = if result.failed() {
+            "fail"
+        } else if result.warned() {
+            "warn"
+        } else {
+            "pass"
+        };
+
+        let mut details = Vec::new();
+        if let Some(detail) = &result.detail {
+            details.push(escape(detail));
+        }
+        if let Some(reason) = &result.reason {
+            details.push(escape(reason));
+        }
+        match &result.url {
+            // Only web links, a spec shouldn't be able to put a script in the page
+            Some(url) if url.starts_with("https://") || url.starts_with("http://") => {
+                details.push(format!("<a href=\"{}\">{}</a>", escape(url), escape(url)))
+            }
+            Some(url) => details.push(escape(url)),
+            None => {}
+        }
+        if !result.controls.is_empty() {
+            details.push(format!("<span class=\"controls\">{}</span>", escape(&result.controls.join(", "))));
+        }
+
+        out.push_str(&format!(
+            "<tr data-outcome=\"{}\"><td><span class=\"badge {}\">{}</span></td><td class=\"name\">{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            outcome,
+            outcome,
+            escape(result.status.as_str()),
+            escape(&result.name),
+            escape(result.required.as_deref().unwrap_or("")),
+            escape(result.source.as_deref().unwrap_or("")),
+            details.join("<br>")
+        ));
+    }
+    out.push_str("</tbody>\n</table>\n");
+
+    out.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
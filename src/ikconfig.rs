@@ -0,0 +1,84 @@
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::process::Command;
+
+use crate::compress;
+
+/// Marks the gzip-compressed config that CONFIG_IKCONFIG embeds in the kernel
+const IKCFG_ST: &[u8] = b"IKCFG_ST";
+
+/// Returns true if the running kernel builds IKCONFIG as a module (CONFIG_IKCONFIG=m),
+/// i.e. `modprobe configs` would make /proc/config.gz appear
+pub fn configs_module_available(release: &str) -> bool {
+    let boot_config = format!("/boot/config-{}", release);
+    if let Ok(content) = fs::read_to_string(boot_config) {
+        if content.lines().any(|line| line == "CONFIG_IKCONFIG=m") {
+            return true;
+        }
+    }
+
+    let modules_dep = format!("/lib/modules/{}/modules.dep", release);
+    fs::read_to_string(modules_dep)
+        .map(|content| content.lines().any(|line| line.starts_with("kernel/kernel/configs.ko")))
+        .unwrap_or(false)
+}
+
+/// Load the configs module, asking for confirmation on a terminal unless `allowed` is set
+pub fn load_configs_module(allowed: bool) -> bool {
+    if !allowed {
+        if !io::stdin().is_terminal() {
+            eprintln!("💡 /proc/config.gz can be enabled with `modprobe configs` (pass --allow-modprobe to do it automatically)");
+            return false;
+        }
+
+        eprint!("❓ /proc/config.gz is missing but the configs module is available. Load it with modprobe? [y/N] ");
+        let _ = io::stderr().flush();
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer).is_err() || !answer.trim().eq_ignore_ascii_case("y") {
+            return false;
+        }
+    }
+
+    match Command::new("modprobe").arg("configs").status() {
+        Ok(status) if status.success() => Path::new(crate::PROC_CONFIG).exists(),
+        Ok(status) => {
+            eprintln!("⚠️  modprobe configs failed with {}", status);
+            false
+        }
+        Err(e) => {
+            eprintln!("⚠️  Failed to run modprobe: {}", e);
+            false
+        }
+    }
+}
+
+/// Returns true for a kernel image: an ELF vmlinux, an x86 bzImage, an arm64
+/// Image or an EFI zboot image
+pub fn is_kernel_image(data: &[u8]) -> bool {
+    data.starts_with(b"\x7fELF")
+        || data.get(0x202..0x206) == Some(b"HdrS")
+        || data.get(0x38..0x3c) == Some(b"ARM\x64")
+        || (data.starts_with(b"MZ") && data.get(4..8) == Some(b"zimg"))
+}
+
+/// Extract the config embedded in a kernel image, like scripts/extract-ikconfig:
+/// look for the IKCFG_ST marker, then inside every compressed stream the image carries
+pub fn extract(image: &[u8]) -> Option<String> {
+    if let Some(config) = find_embedded_config(image) {
+        return Some(config);
+    }
+
+    // bzImage, zImage, vmlinuz.efi and Image.gz carry the real kernel compressed
+    compress::find_streams(image, 8)
+        .into_iter()
+        .filter_map(|offset| compress::decompress(&image[offset..]))
+        .find_map(|kernel| find_embedded_config(&kernel))
+}
+
+fn find_embedded_config(data: &[u8]) -> Option<String> {
+    data.windows(IKCFG_ST.len() + 3)
+        .enumerate()
+        .filter(|(_, window)| window.starts_with(IKCFG_ST) && window.ends_with(&[0x1f, 0x8b, 0x08]))
+        .find_map(|(offset, _)| compress::gunzip(&data[offset + IKCFG_ST.len()..]).ok())
+}
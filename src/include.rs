@@ -0,0 +1,211 @@
+//! `include` directives, to compose flags files from shared parts instead of
+//! concatenating them in the shell.
+//!
+//! A line `include base.flags` is replaced by the entries of that file, named
+//! relative to the including file's directory. A `*` or `?` in the file name
+//! includes every matching file, in name order, so a profile can pull in a
+//! whole directory of fragments:
+//!
+//! ```text
+//! include base.flags
+//! include docker.yaml
+//!
+//! [Security]
+//! include security.d/*.flags
+//! ```
+//!
+//! Included files can include others, YAML and TOML specs among them; including
+//! a file that is already being included is an error. Entries of an included
+//! file before its first section header land in the section of the include, and
+//! the included file's sections end with it.
+//!
+//! ```no_run
+//! let path = "host.flags";
+//! let (flags, _metadata) = kcc::include::expand(path, &std::fs::read_to_string(path)?)?;
+//! println!("{} entries", kcc::parse_flags_file(path, &flags)?.len());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::spec::{self, Requirement, SpecFormat};
+use crate::{check, pattern, remote};
+
+/// A flags file with its includes replaced by the entries of the included files, along with the
+/// requirements of included YAML and TOML specs that carry metadata a flags file can't hold
+pub fn expand(path: &str, content: &str) -> anyhow::Result<(String, Vec<Requirement>)> {
+    let mut including: Vec<PathBuf> = fs::canonicalize(path).into_iter().collect();
+    expand_file(path, content, &mut including)
+}
+
+/// Expand a flags file, `including` holding the files whose includes are being followed
+fn expand_file(path: &str, content: &str, including: &mut Vec<PathBuf>) -> anyhow::Result<(String, Vec<Requirement>)> {
+    if !content.lines().any(|line| check::include_directive(line.trim()).is_some()) {
+        return Ok((content.to_string(), Vec::new()));
+    }
+    // Check the file on its own first, so errors point at its lines rather than the expanded text
+    check::flag_lines(content).map_err(|(line, e)| anyhow::anyhow!("{}: line {}: {}", path, line, e))?;
+    let own: Vec<&str> =
+        content.lines().map(|line| if check::include_directive(line.trim()).is_some() { "" } else { line }).collect();
+    check::parse_flags_file(path, &own.join("\n"))?;
+
+    let mut out = String::new();
+    let mut metadata = Vec::new();
+    // The section of the lines read so far, empty outside any
+    let mut section = "";
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some(name) = check::section_header(trimmed) {
+            section = name;
+        }
+        let Some(files) = check::include_directive(trimmed) else {
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        };
+        let at = |e: String| anyhow::anyhow!("{}: line {}: {}", path, index + 1, e);
+        if remote::is_remote(path) {
+            return Err(at("includes are only followed in local flags files".to_string()));
+        }
+
+        for file in matching_files(path, files).map_err(|e| at(e.to_string()))? {
+            let included = file.display().to_string();
+            let canonical = fs::canonicalize(&file).map_err(|e| at(format!("Failed to read {}: {}", included, e)))?;
+            if including.contains(&canonical) {
+                return Err(at(format!("{} includes itself", included)));
+            }
+            let text = fs::read_to_string(&file).map_err(|e| at(format!("Failed to read {}: {}", included, e)))?;
+            let (text, requirements) = match SpecFormat::of_flags_file(&included) {
+                SpecFormat::Plain => {
+                    including.push(canonical);
+                    let expanded = expand_file(&included, &text, including)?;
+                    including.pop();
+                    expanded
+                }
+                _ => spec::as_flags_file(&included, &text)?,
+            };
+            metadata.extend(requirements);
+
+            // Blank lines keep comments from explaining or tagging entries across files
+            out.push('\n');
+            out.push_str(&text);
+            out.push('\n');
+            // The included file's sections end with it, `[]` going back to none
+            out.push_str(&format!("[{}]\n", section));
+        }
+    }
+    Ok((out, metadata))
+}
+
+/// The files an include names, relative to the including file; a glob in the file name matches
+/// every file in its directory but hidden ones, sorted by name
+fn matching_files(path: &str, files: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let base = Path::new(path).parent().unwrap_or(Path::new(""));
+    let named = base.join(files);
+    let name = named.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return Ok(vec![named]);
+    }
+
+    let dir = named.parent().unwrap_or(Path::new(""));
+    let listed = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let entries = fs::read_dir(listed).map_err(|e| anyhow::anyhow!("Failed to list {}: {}", listed.display(), e))?;
+    let mut matches: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|file| !file.starts_with('.') || name.starts_with('.'))
+        .filter(|file| pattern::glob(name.as_bytes(), file.as_bytes()))
+        .map(|file| dir.join(file))
+        .collect();
+    if matches.is_empty() {
+        return Err(anyhow::anyhow!("include {} matches no files", files));
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory holding the files, removed again when dropped
+    struct Files(PathBuf);
+
+    impl Files {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("kcc-include-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            for (file, content) in files {
+                let path = dir.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, content).unwrap();
+            }
+            Files(dir)
+        }
+
+        fn expand(&self, file: &str) -> anyhow::Result<String> {
+            let path = self.0.join(file).display().to_string();
+            Ok(expand(&path, &fs::read_to_string(&path)?)?.0)
+        }
+    }
+
+    impl Drop for Files {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn sections(flags: &str) -> Vec<(String, Option<String>)> {
+        let spec = spec::parse(flags, SpecFormat::Plain).unwrap();
+        spec.requirements.into_iter().map(|requirement| (requirement.flag.unwrap(), requirement.section)).collect()
+    }
+
+    #[test]
+    fn included_sections_end_with_the_include() {
+        let files = Files::new("sections", &[("top.flags", "include part.flags\nCONFIG_SMP\n"), ("part.flags", "[Net]\nCONFIG_NET\n")]);
+        let expanded = files.expand("top.flags").unwrap();
+        assert_eq!(
+            sections(&expanded),
+            [("CONFIG_NET".to_string(), Some("Net".to_string())), ("CONFIG_SMP".to_string(), None)]
+        );
+    }
+
+    #[test]
+    fn includes_land_in_the_section_of_the_include() {
+        let files = Files::new(
+            "nested",
+            &[
+                ("top.flags", "[Security]\ninclude security.d/*.flags\nCONFIG_AUDIT\n"),
+                ("security.d/a.flags", "CONFIG_SECURITY\n[Lsm]\nCONFIG_BPF_LSM\n"),
+                ("security.d/b.flags", "CONFIG_SECCOMP\n"),
+            ],
+        );
+        let expanded = files.expand("top.flags").unwrap();
+        let section = |name: &str| Some(name.to_string());
+        assert_eq!(
+            sections(&expanded),
+            [
+                ("CONFIG_SECURITY".to_string(), section("Security")),
+                ("CONFIG_BPF_LSM".to_string(), section("Lsm")),
+                ("CONFIG_SECCOMP".to_string(), section("Security")),
+                ("CONFIG_AUDIT".to_string(), section("Security")),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_file_including_itself_is_an_error() {
+        let files = Files::new("cycle", &[("a.flags", "CONFIG_A\ninclude b.flags\n"), ("b.flags", "include a.flags\n")]);
+        let error = files.expand("a.flags").unwrap_err().to_string();
+        assert!(error.contains("b.flags: line 1:") && error.contains("a.flags includes itself"), "{}", error);
+    }
+
+    #[test]
+    fn an_include_matching_nothing_is_an_error() {
+        let files = Files::new("nothing", &[("a.flags", "include none/*.flags\n"), ("none/readme", "")]);
+        let error = files.expand("a.flags").unwrap_err().to_string();
+        assert!(error.contains("matches no files"), "{}", error);
+    }
+}
@@ -0,0 +1,809 @@
+//! Databases of the config symbols a kernel version has, with their types, prompts,
+//! defaults, dependencies and help texts, built from the Kconfig files of its source tree.
+//!
+//! A config only lists the symbols whose dependencies were met when it was generated,
+//! so validating flags against another config reports options that exist but were
+//! never offered as invalid. The Kconfig files declare every symbol:
+//!
+//! ```no_run
+//! use kcc::kconfig::KconfigDb;
+//! use kcc::{Checker, FlagRequirement, KernelConfig};
+//!
+//! let db = KconfigDb::from_source("/usr/src/linux")?;
+//! db.save_to_cache(false)?;
+//! let config = KernelConfig::load("/boot/config-6.12.9")?;
+//! let requirements = [FlagRequirement::builder("CONFIG_BPF_JIT").build()?];
+//! for (_, result) in Checker::new().validate_against(db).check(&config, &requirements) {
+//!     println!("{}: {}", result.name, result.status.as_str());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Databases are cached as JSON under `$XDG_CACHE_HOME/kcc/kconfig/` (`~/.cache/kcc/kconfig/`
+//! by default), one per kernel version like `6.12.9.json`, and picked up for configs of that version.
+//! An existing entry is only replaced when asked to.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::KernelConfig;
+use crate::{arch, compress, remote};
+use crate::expr::{self, Context, Expr, Op};
+
+/// Symbols a flag can be validated against
+pub trait KnownSymbols: Send + Sync {
+    /// Whether the symbol exists, with or without its `CONFIG_` prefix
+    fn knows(&self, name: &str) -> bool;
+
+    /// Every symbol, with its `CONFIG_` prefix, for suggesting names; empty when they can't be listed
+    fn names(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+impl KnownSymbols for KernelConfig {
+    fn knows(&self, name: &str) -> bool {
+        self.contains(name)
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.iter().map(|(name, _)| name.to_string()).collect()
+    }
+}
+
+impl<T: KnownSymbols + ?Sized> KnownSymbols for Box<T> {
+    fn knows(&self, name: &str) -> bool {
+        (**self).knows(name)
+    }
+
+    fn names(&self) -> Vec<String> {
+        (**self).names()
+    }
+}
+
+/// The known symbols closest to a name that isn't known, best first, for "did you mean"
+/// hints on typos like `CONFIG_CGROUP` for `CONFIG_CGROUPS`
+pub fn suggestions(name: &str, known: &dyn KnownSymbols) -> Vec<String> {
+    let name = canonical(&name.trim().to_uppercase());
+    // Allow about one edit per four characters of the name proper
+    let limit = (name.len() - "CONFIG_".len()).div_ceil(4).clamp(1, 3);
+    let mut candidates: Vec<(usize, String)> = known
+        .names()
+        .into_iter()
+        .filter(|candidate| candidate.len().abs_diff(name.len()) <= limit)
+        .map(|candidate| (edit_distance(&name, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    candidates.sort();
+    candidates.into_iter().take(3).map(|(_, candidate)| candidate).collect()
+}
+
+/// Levenshtein distance between two names
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// A config symbol as declared in Kconfig
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Symbol {
+    /// `bool`, `tristate`, `int`, `hex` or `string`
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// The title shown by menuconfig; symbols without one can't be set directly
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// `default` lines as written, e.g. `m if NETFILTER_ADVANCED=n`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub defaults: Vec<String>,
+    /// `depends on` expressions, including those of the enclosing `if` and `menu` blocks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// `select` lines as written, e.g. `BPF_JIT_DEFAULT_ON if ARCH_WANT_DEFAULT_BPF_JIT`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selects: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub help: Option<String>,
+    /// The `choice` block the symbol is a member of, as a key of `KconfigDb::choices`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub choice: Option<String>,
+    /// The Kconfig files declaring the symbol, relative to the source tree
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+/// The config symbols of a kernel version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KconfigDb {
+    /// The kernel version as `major.minor.sublevel`, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Symbols by name, without the `CONFIG_` prefix
+    pub symbols: BTreeMap<String, Symbol>,
+    /// Prompts of the `choice` blocks, by `file:line` of the block
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub choices: BTreeMap<String, String>,
+}
+
+/// Options of which only one can be selected, like the preemption models
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    pub prompt: String,
+    /// The options, with their `CONFIG_` prefix
+    pub members: Vec<String>,
+}
+
+/// Choices found in most configs, for when there's no Kconfig database to look them up in
+pub const COMMON_CHOICES: &[(&str, &[&str])] = &[
+    ("Preemption Model", &["PREEMPT_NONE", "PREEMPT_VOLUNTARY", "PREEMPT", "PREEMPT_LAZY", "PREEMPT_RT"]),
+    ("Timer frequency", &["HZ_100", "HZ_250", "HZ_300", "HZ_1000"]),
+    ("Timer tick handling", &["HZ_PERIODIC", "NO_HZ_IDLE", "NO_HZ_FULL"]),
+    (
+        "Default CPUFreq governor",
+        &[
+            "CPU_FREQ_DEFAULT_GOV_PERFORMANCE",
+            "CPU_FREQ_DEFAULT_GOV_POWERSAVE",
+            "CPU_FREQ_DEFAULT_GOV_USERSPACE",
+            "CPU_FREQ_DEFAULT_GOV_ONDEMAND",
+            "CPU_FREQ_DEFAULT_GOV_CONSERVATIVE",
+            "CPU_FREQ_DEFAULT_GOV_SCHEDUTIL",
+        ],
+    ),
+    (
+        "Kernel compression mode",
+        &["KERNEL_GZIP", "KERNEL_BZIP2", "KERNEL_LZMA", "KERNEL_XZ", "KERNEL_LZO", "KERNEL_LZ4", "KERNEL_ZSTD"],
+    ),
+    ("Module compression mode", &["MODULE_COMPRESS_NONE", "MODULE_COMPRESS_GZIP", "MODULE_COMPRESS_XZ", "MODULE_COMPRESS_ZSTD"]),
+    ("Choose SLAB allocator", &["SLAB", "SLUB", "SLOB"]),
+    (
+        "Default TCP congestion control",
+        &[
+            "DEFAULT_BIC",
+            "DEFAULT_CUBIC",
+            "DEFAULT_HTCP",
+            "DEFAULT_HYBLA",
+            "DEFAULT_VEGAS",
+            "DEFAULT_VENO",
+            "DEFAULT_WESTWOOD",
+            "DEFAULT_DCTCP",
+            "DEFAULT_CDG",
+            "DEFAULT_BBR",
+            "DEFAULT_RENO",
+        ],
+    ),
+    (
+        "First legacy 'major LSM' to be initialized",
+        &[
+            "DEFAULT_SECURITY_SELINUX",
+            "DEFAULT_SECURITY_SMACK",
+            "DEFAULT_SECURITY_TOMOYO",
+            "DEFAULT_SECURITY_APPARMOR",
+            "DEFAULT_SECURITY_DAC",
+        ],
+    ),
+];
+
+/// The common choice an option is a member of, see `COMMON_CHOICES`
+pub fn common_choice(name: &str) -> Option<Choice> {
+    let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+    let (prompt, members) = COMMON_CHOICES.iter().find(|(_, members)| members.contains(&name))?;
+    Some(Choice { prompt: prompt.to_string(), members: members.iter().map(|member| canonical(member)).collect() })
+}
+
+impl KnownSymbols for KconfigDb {
+    fn knows(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.symbols.keys().map(|name| format!("CONFIG_{}", name)).collect()
+    }
+}
+
+impl KconfigDb {
+    /// A symbol by name, with or without its `CONFIG_` prefix
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name.strip_prefix("CONFIG_").unwrap_or(name))
+    }
+
+    /// Read every Kconfig file of a kernel source tree, for all architectures
+    pub fn from_source(dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let dir = dir.as_ref();
+        if !dir.join("Kconfig").is_file() {
+            return Err(anyhow::anyhow!("{} is not a kernel source tree, it has no top-level Kconfig", dir.display()));
+        }
+
+        let mut db = KconfigDb { version: source_version(dir), ..Default::default() };
+        for path in kconfig_files(dir) {
+            let content = fs::read_to_string(&path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+            let relative = path.strip_prefix(dir).unwrap_or(&path).display().to_string();
+            db.add_file(&relative, &content);
+        }
+        Ok(db)
+    }
+
+    /// Read the Kconfig files of a kernel source tarball as it arrives, plain or compressed with
+    /// gzip, xz or zstd, like the `linux-6.8.tar.xz` releases on kernel.org; `name` is used in errors
+    pub fn from_tarball(reader: impl Read, name: &str) -> anyhow::Result<Self> {
+        let mut tar = TarSource::default();
+        compress::decode_stream(reader, &mut tar, name)?;
+        if !tar.files.contains_key("Kconfig") {
+            return Err(anyhow::anyhow!("{} is not a kernel source tarball, it has no top-level Kconfig", name));
+        }
+
+        let mut db = KconfigDb { version: tar.makefile.as_deref().and_then(makefile_version), ..Default::default() };
+        for (file, content) in &tar.files {
+            db.add_file(file, content);
+        }
+        Ok(db)
+    }
+
+    /// Add the symbols declared in the text of a Kconfig file
+    pub fn add_file(&mut self, file: &str, content: &str) {
+        // Conditions of the enclosing `if`, `menu` and `choice` blocks, inherited by their symbols
+        let mut blocks: Vec<Vec<String>> = Vec::new();
+        // Whether `depends on` lines belong to the `menu` or `choice` just opened
+        let mut block_header = false;
+        let mut current: Option<String> = None;
+        // The `choice` block being read, as `file:line`
+        let mut choice: Option<String> = None;
+        // Indentation of the `help` keyword and the help text read so far
+        let mut help: Option<(usize, Vec<String>)> = None;
+
+        for (number, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if let Some((keyword_indent, text)) = &mut help {
+                if trimmed.is_empty() || indentation(line) > *keyword_indent {
+                    text.push(trimmed.to_string());
+                    continue;
+                }
+                self.set_help(current.as_deref(), text);
+                help = None;
+            }
+
+            let (keyword, rest) = trimmed.split_once(char::is_whitespace).map_or((trimmed, ""), |(k, rest)| (k, rest.trim()));
+            match keyword {
+                "config" | "menuconfig" if !rest.is_empty() => {
+                    let symbol = self.symbols.entry(rest.to_string()).or_default();
+                    if !symbol.files.iter().any(|declared| declared == file) {
+                        symbol.files.push(file.to_string());
+                    }
+                    for condition in blocks.iter().flatten() {
+                        push_new(&mut symbol.depends_on, condition);
+                    }
+                    if let Some(choice) = &choice {
+                        symbol.choice.get_or_insert_with(|| choice.clone());
+                    }
+                    current = Some(rest.to_string());
+                    block_header = false;
+                }
+                "bool" | "tristate" | "int" | "hex" | "string" | "def_bool" | "def_tristate" => {
+                    if let Some(symbol) = self.attributes(current.as_deref()) {
+                        symbol.kind.get_or_insert_with(|| keyword.trim_start_matches("def_").to_string());
+                        if keyword.starts_with("def_") {
+                            push_new(&mut symbol.defaults, rest);
+                        } else if let Some(prompt) = quoted(rest) {
+                            symbol.prompt.get_or_insert(prompt);
+                        }
+                    }
+                }
+                "prompt" => {
+                    if let (true, Some(choice), Some(prompt)) = (block_header, &choice, quoted(rest)) {
+                        self.choices.insert(choice.clone(), prompt);
+                    } else if let Some((symbol, prompt)) = self.attributes(current.as_deref()).zip(quoted(rest)) {
+                        symbol.prompt.get_or_insert(prompt);
+                    }
+                }
+                "default" => {
+                    if let Some(symbol) = self.attributes(current.as_deref()) {
+                        push_new(&mut symbol.defaults, rest);
+                    }
+                }
+                "depends" => {
+                    let Some(condition) = rest.strip_prefix("on").map(str::trim) else { continue };
+                    if let Some(symbol) = self.attributes(current.as_deref()) {
+                        push_new(&mut symbol.depends_on, condition);
+                    } else if let (true, Some(block)) = (block_header, blocks.last_mut()) {
+                        block.push(condition.to_string());
+                    }
+                }
+                "select" => {
+                    if let Some(symbol) = self.attributes(current.as_deref()) {
+                        push_new(&mut symbol.selects, rest);
+                    }
+                }
+                "help" | "---help---" => help = Some((indentation(line), Vec::new())),
+                "if" => {
+                    blocks.push(vec![rest.to_string()]);
+                    (current, block_header) = (None, false);
+                }
+                "menu" | "choice" => {
+                    blocks.push(Vec::new());
+                    (current, block_header) = (None, true);
+                    if keyword == "choice" {
+                        choice = Some(format!("{}:{}", file, number + 1));
+                    }
+                }
+                "endif" | "endmenu" | "endchoice" => {
+                    blocks.pop();
+                    (current, block_header) = (None, false);
+                    if keyword == "endchoice" {
+                        choice = None;
+                    }
+                }
+                "source" | "comment" | "mainmenu" => (current, block_header) = (None, false),
+                _ => {}
+            }
+        }
+        if let Some((_, text)) = help {
+            self.set_help(current.as_deref(), &text);
+        }
+    }
+
+    /// The symbol whose attributes are being read
+    fn attributes(&mut self, current: Option<&str>) -> Option<&mut Symbol> {
+        self.symbols.get_mut(current?)
+    }
+
+    fn set_help(&mut self, current: Option<&str>, lines: &[String]) {
+        let text = lines.join("\n").trim().to_string();
+        if let (Some(symbol), false) = (self.attributes(current), text.is_empty()) {
+            symbol.help.get_or_insert(text);
+        }
+    }
+
+    /// Why an option can't be enabled in a config: the tests of its `depends on` expressions that
+    /// fail, then those of the options they name, and so on down the chain
+    pub fn dependency_chain(&self, config: &KernelConfig, name: &str) -> Vec<UnmetDependency> {
+        let ctx = Context::new(config);
+        let mut chain = Vec::new();
+        let mut seen = HashSet::from([canonical(name)]);
+        let mut pending = vec![canonical(name)];
+        while !pending.is_empty() {
+            for name in std::mem::take(&mut pending) {
+                let Some(symbol) = self.get(&name) else { continue };
+                let (mut needs, mut enable) = (Vec::new(), Vec::new());
+                for rule in symbol.depends_on.iter().filter_map(|condition| expr::parse(condition).ok()) {
+                    self.unmet_tests(&rule, &ctx, &mut needs, &mut enable);
+                }
+                pending.extend(enable.into_iter().filter(|option| seen.insert(option.clone())));
+                if !needs.is_empty() {
+                    chain.push(UnmetDependency { name, needs });
+                }
+            }
+        }
+        chain
+    }
+
+    /// What to enable so an option's `depends on` expressions are met, down the chain
+    pub fn prerequisites(&self, config: &KernelConfig, name: &str) -> Prerequisites {
+        let mut plan = Prerequisites::default();
+        // The chain goes from the option down to its farthest parents, which are enabled first
+        for unmet in self.dependency_chain(config, name).iter().rev() {
+            for need in &unmet.needs {
+                let (option, value) = need.split_once('=').unwrap_or((need, "y"));
+                let settable = matches!(value, "y" | "m") && option.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                if !settable {
+                    push_new(&mut plan.unresolved, &format!("{} requires {}", unmet.name, need));
+                } else if self.get(option).is_some_and(|symbol| symbol.prompt.is_none()) {
+                    let reason = match self.selected_by(option).as_slice() {
+                        [] => format!("{} has no prompt", option),
+                        selecting => format!("{} has no prompt, it is enabled by {}", option, selecting.join(" or ")),
+                    };
+                    push_new(&mut plan.unresolved, &reason);
+                } else if !plan.enable.iter().any(|(enabled, _)| enabled == option) && option != canonical(name) {
+                    plan.enable.push((option.to_string(), value.to_string()));
+                }
+            }
+        }
+        plan
+    }
+
+    /// Collect the failing parts of a dependency: plain tests of an `&&` chain are listed one by
+    /// one and followed further, anything else is listed as a whole
+    fn unmet_tests(&self, rule: &Expr, ctx: &Context, needs: &mut Vec<String>, enable: &mut Vec<String>) {
+        if expr::evaluate(rule, ctx) || expr::symbols(rule).iter().all(|symbol| !self.knows(symbol)) {
+            return;
+        }
+        match rule {
+            Expr::And(a, b) => {
+                self.unmet_tests(a, ctx, needs, enable);
+                self.unmet_tests(b, ctx, needs, enable);
+                return;
+            }
+            Expr::Test { symbol, op: None } => enable.push(canonical(symbol)),
+            Expr::Test { symbol, op: Some((Op::Eq, value)) } if value != "n" => enable.push(canonical(symbol)),
+            _ => {}
+        }
+        push_new(needs, &describe(rule));
+    }
+
+    /// The choice an option is a member of
+    pub fn choice(&self, name: &str) -> Option<Choice> {
+        let id = self.get(name)?.choice.as_ref()?;
+        Some(Choice {
+            prompt: self.choices.get(id).cloned().unwrap_or_else(|| format!("choice at {}", id)),
+            members: self
+                .symbols
+                .iter()
+                .filter(|(_, symbol)| symbol.choice.as_ref() == Some(id))
+                .map(|(member, _)| canonical(member))
+                .collect(),
+        })
+    }
+
+    /// The options whose `select` lines name an option, which is how options without a prompt are enabled
+    pub fn selected_by(&self, name: &str) -> Vec<String> {
+        let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+        self.symbols
+            .iter()
+            .filter(|(_, symbol)| symbol.selects.iter().any(|select| select.split_whitespace().next() == Some(name)))
+            .map(|(selecting, _)| format!("CONFIG_{}", selecting))
+            .collect()
+    }
+
+    /// Where databases are cached
+    pub fn cache_dir() -> Option<PathBuf> {
+        let cache = match std::env::var_os("XDG_CACHE_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+        };
+        Some(cache.join("kcc/kconfig"))
+    }
+
+    /// Where the database of a kernel version is cached, whether or not it is
+    pub fn cache_path(version: &str) -> Option<PathBuf> {
+        Some(Self::cache_dir()?.join(format!("{}.json", version_key(version)?)))
+    }
+
+    /// The cached database for a kernel version like `6.12.9`, `6.12` being `6.12.0`; an incomplete one is ignored
+    pub fn cached(version: &str) -> Option<Self> {
+        let db: Self = serde_json::from_str(&fs::read_to_string(Self::cache_path(version)?).ok()?).ok()?;
+        db.is_complete().then_some(db)
+    }
+
+    /// Whether the database looks read from a whole source tree rather than part of one: a tree
+    /// missing most of its Kconfig files would make every flag it doesn't declare invalid
+    pub fn is_complete(&self) -> bool {
+        let arch_symbols =
+            self.symbols.values().filter(|symbol| symbol.files.iter().any(|file| file.starts_with("arch/"))).count();
+        self.symbols.len() >= MIN_SYMBOLS && arch_symbols >= MIN_ARCH_SYMBOLS
+    }
+
+    /// The cached database for the kernel version in a config's header
+    pub fn cached_for(config: &KernelConfig) -> Option<Self> {
+        Self::cached(&expr::kernel_version(config.text())?)
+    }
+
+    /// The versions with a cached database, oldest first
+    pub fn cached_versions() -> Vec<String> {
+        let Some(entries) = Self::cache_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+            return Vec::new();
+        };
+        let mut versions: Vec<String> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".json").map(str::to_string))
+            .collect();
+        versions.sort_by(|a, b| expr::compare_versions(a, b));
+        versions
+    }
+
+    /// Whether a database is cached for the version of this one
+    pub fn is_cached(&self) -> bool {
+        self.version.as_deref().and_then(Self::cache_path).is_some_and(|path| path.exists())
+    }
+
+    /// Save the database to the cache under its version, returning where; an incomplete one is refused,
+    /// and so is replacing a cached one unless `force` is set
+    pub fn save_to_cache(&self, force: bool) -> anyhow::Result<PathBuf> {
+        let version = self.version.as_deref().ok_or_else(|| anyhow::anyhow!("The Kconfig database has no kernel version"))?;
+        if !self.is_complete() {
+            return Err(anyhow::anyhow!(
+                "only {} symbols were read, too few for a whole kernel source tree",
+                self.symbols.len()
+            ));
+        }
+        let dir = Self::cache_dir().ok_or_else(|| anyhow::anyhow!("Failed to locate the cache: HOME is not set"))?;
+        fs::create_dir_all(&dir).map_err(|e| anyhow::anyhow!("Failed to create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("{}.json", version_key(version).unwrap_or_else(|| version.to_string())));
+        if path.exists() && !force {
+            return Err(anyhow::anyhow!("{} is already cached in {}, pass --force to replace it", version, path.display()));
+        }
+        fs::write(&path, serde_json::to_string(self)?).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// Run `make olddefconfig` in a kernel source tree for a config, which fills in the defaults of
+/// new symbols and drops the ones whose dependencies aren't met, as the next build would. A config
+/// outside the tree is treated as one in an `O=` build directory; `arch` cross-configures.
+pub fn olddefconfig(tree: &Path, config: &Path, arch: Option<&str>) -> anyhow::Result<()> {
+    let locate = |path: &Path| path.canonicalize().map_err(|e| anyhow::anyhow!("Failed to locate {}: {}", path.display(), e));
+    let (tree, config) = (locate(tree)?, locate(config)?);
+    let dir = config.parent().unwrap_or(&tree);
+
+    let mut make = Command::new("make");
+    make.arg("-C").arg(&tree);
+    if dir != tree {
+        make.arg(format!("O={}", dir.display()));
+    }
+    if config.file_name().is_some_and(|name| name != ".config") {
+        make.arg(format!("KCONFIG_CONFIG={}", config.display()));
+    }
+    if let Some(arch) = arch {
+        let arch = arch::kernel_arch(arch).ok_or_else(|| anyhow::anyhow!("Unknown architecture '{}'", arch))?;
+        make.arg(format!("ARCH={}", arch));
+    }
+    remote::command_output("make olddefconfig", make.arg("olddefconfig").output())?;
+    Ok(())
+}
+
+/// An option a config can't enable yet, and the parts of its dependencies that aren't met
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnmetDependency {
+    /// The option, with its `CONFIG_` prefix
+    pub name: String,
+    /// What it needs, like `CONFIG_NETFILTER`, `CONFIG_NF_CONNTRACK=y` or `CONFIG_HAVE_CBPF_JIT || CONFIG_HAVE_EBPF_JIT`
+    pub needs: Vec<String>,
+}
+
+/// The options to enable along with another one, so its dependencies are met
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Prerequisites {
+    /// Options and their values, the farthest parents first
+    pub enable: Vec<(String, String)>,
+    /// Dependencies enabling options can't meet, like `||` alternatives, negations and options
+    /// without a prompt, which only the options selecting them enable
+    pub unresolved: Vec<String>,
+}
+
+/// A symbol with its `CONFIG_` prefix
+fn canonical(name: &str) -> String {
+    format!("CONFIG_{}", name.strip_prefix("CONFIG_").unwrap_or(name))
+}
+
+/// A dependency written with `CONFIG_` prefixes, as flags files name symbols
+fn describe(rule: &Expr) -> String {
+    let operand = |rule: &Expr| match rule {
+        Expr::And(..) | Expr::Or(..) => format!("({})", describe(rule)),
+        _ => describe(rule),
+    };
+    match rule {
+        Expr::And(a, b) => format!("{} && {}", operand(a), operand(b)),
+        Expr::Or(a, b) => format!("{} || {}", operand(a), operand(b)),
+        Expr::Not(e) => format!("!{}", operand(e)),
+        Expr::Test { symbol, op: None } => canonical(symbol),
+        Expr::Test { symbol, op: Some((op, value)) } => {
+            let op = match op {
+                Op::Eq => "=",
+                Op::Ne => "!=",
+                Op::Lt => "<",
+                Op::Le => "<=",
+                Op::Gt => ">",
+                Op::Ge => ">=",
+            };
+            format!("{}{}{}", canonical(symbol), op, value)
+        }
+    }
+}
+
+/// Add a value unless it is already there, for symbols declared in several places
+fn push_new(values: &mut Vec<String>, value: &str) {
+    if !values.iter().any(|existing| existing == value) {
+        values.push(value.to_string());
+    }
+}
+
+/// The text of a quoted string at the start of `text`, like the prompt of `bool "Enable foo" if BAR`
+fn quoted(text: &str) -> Option<String> {
+    let quote = text.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let (inner, _) = text[1..].split_once(quote)?;
+    Some(inner.to_string())
+}
+
+/// Width of a line's leading whitespace, with tabs up to the next multiple of 8 as in Kconfig
+fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .fold(0, |width, c| if c == '\t' { (width / 8 + 1) * 8 } else { width + 1 })
+}
+
+/// Fewest symbols a database read from a whole source tree has; kernels have had over ten thousand since 2.6
+const MIN_SYMBOLS: usize = 5000;
+/// Fewest symbols declared under `arch/` a database read from a whole source tree has
+const MIN_ARCH_SYMBOLS: usize = 200;
+
+/// `major.minor.sublevel` of a kernel version like `6.12.9-arch1-1`, the sublevel being 0 when left out
+fn version_key(version: &str) -> Option<String> {
+    let release = version.split(['-', '+']).next()?;
+    let parts: Vec<u32> = release.split('.').map(|part| part.parse().ok()).collect::<Option<_>>()?;
+    match parts.as_slice() {
+        [major, minor] => Some(format!("{}.{}.0", major, minor)),
+        [major, minor, sublevel, ..] => Some(format!("{}.{}.{}", major, minor, sublevel)),
+        _ => None,
+    }
+}
+
+/// `major.minor.sublevel` from the VERSION, PATCHLEVEL and SUBLEVEL of the tree's top-level Makefile
+fn source_version(dir: &Path) -> Option<String> {
+    makefile_version(&fs::read_to_string(dir.join("Makefile")).ok()?)
+}
+
+/// `major.minor.sublevel` from the text of a top-level Makefile
+fn makefile_version(makefile: &str) -> Option<String> {
+    let variable = |name: &str| {
+        makefile.lines().find_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            (key.trim() == name).then(|| value.trim().to_string())
+        })
+    };
+    let sublevel = variable("SUBLEVEL").filter(|sublevel| !sublevel.is_empty()).unwrap_or_else(|| "0".to_string());
+    version_key(&format!("{}.{}.{}", variable("VERSION")?, variable("PATCHLEVEL")?, sublevel))
+}
+
+/// Kconfig files under a directory, in a stable order; hidden directories are skipped
+fn kconfig_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    let mut entries: Vec<_> = entries.filter_map(Result::ok).collect();
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() && !name.starts_with('.') => files.extend(kconfig_files(&path)),
+            Ok(kind) if kind.is_file() && name.starts_with("Kconfig") => files.push(path),
+            _ => {}
+        }
+    }
+    files
+}
+
+/// Size of tar headers and the unit entries are padded to
+const TAR_BLOCK: usize = 512;
+
+/// An entry of a tar stream whose data is being collected
+struct TarEntry {
+    kind: u8,
+    path: String,
+    size: usize,
+}
+
+/// The Kconfig files and top-level Makefile of a source tarball, picked out of the tar
+/// stream as it is written; everything else is skipped without being held in memory
+#[derive(Default)]
+struct TarSource {
+    buffer: Vec<u8>,
+    /// Bytes of a skipped entry still to come
+    skip: usize,
+    entry: Option<TarEntry>,
+    /// The path of the next entry, from a pax or GNU long name header
+    next_path: Option<String>,
+    /// Kconfig files by path, relative to the top directory of the tarball
+    files: BTreeMap<String, String>,
+    makefile: Option<String>,
+}
+
+impl TarSource {
+    fn process(&mut self) -> io::Result<()> {
+        loop {
+            if self.skip > 0 {
+                let skipped = self.skip.min(self.buffer.len());
+                self.buffer.drain(..skipped);
+                self.skip -= skipped;
+                if self.skip > 0 {
+                    return Ok(());
+                }
+            }
+            if let Some(size) = self.entry.as_ref().map(|entry| entry.size) {
+                let padded = size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+                if self.buffer.len() < padded {
+                    return Ok(());
+                }
+                let data: Vec<u8> = self.buffer.drain(..padded).take(size).collect();
+                let entry = self.entry.take().expect("checked above");
+                self.read_entry(entry, &data);
+                continue;
+            }
+            if self.buffer.len() < TAR_BLOCK {
+                return Ok(());
+            }
+
+            let header: Vec<u8> = self.buffer.drain(..TAR_BLOCK).collect();
+            // The archive ends with blocks of zeros
+            if header.iter().all(|&b| b == 0) {
+                continue;
+            }
+            let size = tar_field(&header[124..136])
+                .and_then(|size| usize::from_str_radix(&size, 8).ok())
+                .ok_or_else(|| io::Error::other("Malformed tar header"))?;
+            let kind = header[156];
+            let path = match self.next_path.take() {
+                Some(path) => path,
+                None => tar_path(&header),
+            };
+            let wanted = match kind {
+                b'x' | b'L' => true,
+                b'0' | 0 => relative_path(&path).is_some_and(|path| {
+                    path == "Makefile" || path.rsplit('/').next().is_some_and(|name| name.starts_with("Kconfig"))
+                }),
+                _ => false,
+            };
+            if wanted {
+                self.entry = Some(TarEntry { kind, path, size });
+            } else {
+                self.skip = size.div_ceil(TAR_BLOCK) * TAR_BLOCK;
+            }
+        }
+    }
+
+    fn read_entry(&mut self, entry: TarEntry, data: &[u8]) {
+        let text = String::from_utf8_lossy(data);
+        match entry.kind {
+            // Pax records are `<length> <key>=<value>` lines
+            b'x' => {
+                self.next_path = text.lines().find_map(|record| {
+                    let (_, field) = record.split_once(' ')?;
+                    field.strip_prefix("path=").map(str::to_string)
+                })
+            }
+            b'L' => self.next_path = Some(text.trim_end_matches('\0').to_string()),
+            _ => match relative_path(&entry.path) {
+                Some("Makefile") => self.makefile = Some(text.into_owned()),
+                Some(path) => {
+                    self.files.insert(path.to_string(), text.into_owned());
+                }
+                None => {}
+            },
+        }
+    }
+}
+
+impl Write for TarSource {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        self.process()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A NUL-terminated text field of a tar header
+fn tar_field(field: &[u8]) -> Option<String> {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    let text = std::str::from_utf8(&field[..end]).ok()?.trim();
+    Some(text.to_string())
+}
+
+/// The path in a tar header, joined with its ustar prefix
+fn tar_path(header: &[u8]) -> String {
+    let name = tar_field(&header[..100]).unwrap_or_default();
+    match tar_field(&header[345..500]) {
+        Some(prefix) if header[257..262] == *b"ustar" && !prefix.is_empty() => format!("{}/{}", prefix, name),
+        _ => name,
+    }
+}
+
+/// A path below the top directory of a tarball, like `kernel/bpf/Kconfig` for `linux-6.8/kernel/bpf/Kconfig`
+fn relative_path(path: &str) -> Option<&str> {
+    path.trim_start_matches("./").split_once('/').map(|(_, rest)| rest).filter(|rest| !rest.is_empty())
+}
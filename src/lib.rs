@@ -0,0 +1,76 @@
+//! Check Linux kernel configs against the flags and rules a workload needs.
+//!
+//! The `kcc` binary is a thin layer over this crate. Tools that want to check
+//! kernel configs without shelling out to it (installers, provisioning agents)
+//! can load a [`KernelConfig`], read requirements with [`parse_flags`] and check
+//! each of them:
+//!
+//! ```no_run
+//! let config = kcc::KernelConfig::load("/boot/config-6.12.9")?;
+//! for flag in kcc::parse_flags("CONFIG_BPF\nCONFIG_USER_NS=y\nKERNEL>=6.1\n")? {
+//!     let result = config.check(&flag);
+//!     println!("{}: {}", result.name, result.status.as_str());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod arch;
+pub mod audit;
+pub mod backend;
+pub mod bpf;
+pub mod buildsys;
+pub mod check;
+pub mod compress;
+pub mod config;
+pub mod container;
+pub mod controls;
+pub mod derive;
+pub mod diff;
+pub mod dkms;
+pub mod dtb;
+pub mod edit;
+pub mod emit;
+pub mod expr;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod generate;
+pub mod history;
+pub mod hooks;
+pub mod html;
+pub mod ikconfig;
+pub mod include;
+pub mod kconfig;
+pub mod lint;
+pub mod matrix;
+pub mod merge;
+pub mod openwrt;
+pub mod pattern;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod plugin;
+pub mod policy;
+pub mod probes;
+pub mod profiles;
+#[cfg(feature = "python")]
+mod python;
+pub mod remote;
+pub mod renames;
+#[cfg(feature = "async")]
+pub mod remote_async;
+pub mod report;
+pub mod requirement;
+pub mod search;
+pub mod snapshot;
+pub mod spec;
+pub mod stream;
+pub mod sysroot;
+pub mod systemd;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod wizard;
+pub mod wsl;
+
+pub use check::{check_config, check_flag, parse_flags, parse_flags_file, FlagCheckResult, FlagStatus};
+pub use config::{read_kernel_config, ConfigValue, KernelConfig, Tristate, PROC_CONFIG};
+pub use requirement::{Checker, FlagRequirement, FlagRequirementBuilder, Severity};
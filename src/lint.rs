@@ -0,0 +1,470 @@
+//! Static checks for flags files.
+//!
+//! Entries from every linted file are considered together, as they would be
+//! when passed to kcc with several `-f`/`-p` options.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::check;
+use crate::kconfig::{self, KnownSymbols};
+use crate::expr::{self, Expr, Op};
+use crate::renames;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a flags file
+pub struct Diagnostic {
+    pub source: String,
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (icon, label) = match self.severity {
+            Severity::Error => ("❌", "error"),
+            Severity::Warning => ("⚠️ ", "warning"),
+        };
+        write!(f, "{} {}:{}: {}: {}", icon, self.source, self.line, label, self.message)
+    }
+}
+
+/// What an entry demands of a single symbol
+#[derive(Debug, Clone, PartialEq)]
+enum Demand {
+    Enabled,
+    Disabled,
+    Value(String),
+}
+
+impl Demand {
+    fn conflicts_with(&self, other: &Demand) -> bool {
+        match (self, other) {
+            (Demand::Enabled, Demand::Disabled) | (Demand::Disabled, Demand::Enabled) => true,
+            (Demand::Value(_), Demand::Disabled) | (Demand::Disabled, Demand::Value(_)) => true,
+            (Demand::Value(a), Demand::Value(b)) => a != b,
+            _ => false,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Demand::Enabled => "required".to_string(),
+            Demand::Disabled => "forbidden".to_string(),
+            Demand::Value(value) => format!("required to be {}", value),
+        }
+    }
+}
+
+/// A version range `lower..upper`, each bound optional and flagged inclusive or not
+#[derive(Clone, Default)]
+struct VersionRange {
+    lower: Option<(String, bool)>,
+    upper: Option<(String, bool)>,
+}
+
+impl VersionRange {
+    fn constrain(&mut self, op: Op, version: &str) {
+        match op {
+            Op::Ge => self.raise(version, true),
+            Op::Gt => self.raise(version, false),
+            Op::Le => self.cap(version, true),
+            Op::Lt => self.cap(version, false),
+            Op::Eq => {
+                self.raise(version, true);
+                self.cap(version, true);
+            }
+            Op::Ne => {}
+        }
+    }
+
+    fn raise(&mut self, version: &str, inclusive: bool) {
+        let tighter = match &self.lower {
+            None => true,
+            Some((current, current_inclusive)) => match expr::compare_versions(version, current) {
+                Ordering::Greater => true,
+                Ordering::Equal => *current_inclusive && !inclusive,
+                Ordering::Less => false,
+            },
+        };
+        if tighter {
+            self.lower = Some((version.to_string(), inclusive));
+        }
+    }
+
+    fn cap(&mut self, version: &str, inclusive: bool) {
+        let tighter = match &self.upper {
+            None => true,
+            Some((current, current_inclusive)) => match expr::compare_versions(version, current) {
+                Ordering::Less => true,
+                Ordering::Equal => *current_inclusive && !inclusive,
+                Ordering::Greater => false,
+            },
+        };
+        if tighter {
+            self.upper = Some((version.to_string(), inclusive));
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        let (Some((lower, lower_inclusive)), Some((upper, upper_inclusive))) = (&self.lower, &self.upper) else {
+            return false;
+        };
+        match expr::compare_versions(lower, upper) {
+            Ordering::Greater => true,
+            Ordering::Equal => !(*lower_inclusive && *upper_inclusive),
+            Ordering::Less => false,
+        }
+    }
+}
+
+struct Entry<'a> {
+    source: &'a str,
+    line: usize,
+    text: String,
+    rule: Option<Expr>,
+    /// Architectures the entry is qualified with, empty for all
+    arch: Vec<String>,
+}
+
+impl Entry<'_> {
+    /// Whether both entries apply on some architecture
+    fn shares_arch(&self, other: &Entry) -> bool {
+        self.arch.is_empty() || other.arch.is_empty() || self.arch.iter().any(|arch| other.arch.contains(arch))
+    }
+}
+
+/// Lint flags files given as (name, content) pairs. `known_symbols`, a Kconfig database or
+/// a kernel config, is used to spot unknown entries.
+pub fn lint(files: &[(String, String)], known_symbols: Option<&dyn KnownSymbols>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut entries = Vec::new();
+
+    for (source, content) in files {
+        let lines = match check::flag_lines(content) {
+            Ok(lines) => lines,
+            Err((line, message)) => {
+                diagnostics.push(Diagnostic { source: source.clone(), line, severity: Severity::Error, message });
+                continue;
+            }
+        };
+        for (line, text) in lines {
+            if text.is_empty() || text.starts_with('#') || check::section_header(&text).is_some() {
+                continue;
+            }
+            // The severity only decides whether a failure fails the check
+            let text = check::unmarked(&text).to_string();
+            let (text, arch) = match check::arch_qualifier(&text) {
+                Some((entry, arches)) => match check::parse_arches(&arches) {
+                    Ok(arches) => (entry.to_string(), arches),
+                    Err(e) => {
+                        let message = e.to_string();
+                        diagnostics.push(Diagnostic { source: source.clone(), line, severity: Severity::Error, message });
+                        continue;
+                    }
+                },
+                None => (text, Vec::new()),
+            };
+            // Dependencies only decide whether an entry is checked
+            let text = match check::dependency(&text) {
+                Some((entry, condition)) => match expr::parse(condition) {
+                    Ok(_) => entry.to_string(),
+                    Err(e) => {
+                        let message = format!("malformed dependency: {}", e);
+                        diagnostics.push(Diagnostic { source: source.clone(), line, severity: Severity::Error, message });
+                        continue;
+                    }
+                },
+                None => text,
+            };
+            let rule = if check::is_rule(&text) {
+                match expr::parse(&text) {
+                    Ok(rule) => Some(rule),
+                    Err(e) => {
+                        let message = format!("malformed rule: {}", e);
+                        diagnostics.push(Diagnostic { source: source.clone(), line, severity: Severity::Error, message });
+                        continue;
+                    }
+                }
+            } else {
+                None
+            };
+
+            entries.push(Entry { source, line, text, rule, arch });
+        }
+    }
+
+    check_duplicates(&entries, &mut diagnostics);
+    check_contradictions(&entries, &mut diagnostics);
+    check_versions(&entries, &mut diagnostics);
+    check_renames(&entries, &mut diagnostics);
+    if let Some(known) = known_symbols {
+        check_unknown_symbols(&entries, known, &mut diagnostics);
+    }
+
+    diagnostics.sort_by(|a, b| (a.source.as_str(), a.line).cmp(&(b.source.as_str(), b.line)));
+    diagnostics
+}
+
+fn symbol_name(name: &str) -> String {
+    let name = name.trim();
+    name.strip_prefix("CONFIG_").unwrap_or(name).to_string()
+}
+
+fn check_duplicates(entries: &[Entry], diagnostics: &mut Vec<Diagnostic>) {
+    // Entries for disjoint architectures don't duplicate each other
+    let mut seen: HashMap<String, Vec<&Entry>> = HashMap::new();
+
+    for entry in entries {
+        let key = match &entry.rule {
+            Some(_) => entry.text.split_whitespace().collect::<String>(),
+            None => symbol_name(entry.text.split_once('=').map_or(entry.text.as_str(), |(name, _)| name)),
+        };
+        let previous = seen.entry(key).or_default();
+        match previous.iter().find(|first| entry.shares_arch(first)) {
+            Some(first) => diagnostics.push(Diagnostic {
+                source: entry.source.to_string(),
+                line: entry.line,
+                severity: Severity::Warning,
+                message: format!("duplicate entry {} (first at {}:{})", entry.text, first.source, first.line),
+            }),
+            None => previous.push(entry),
+        }
+    }
+}
+
+/// Top-level conjuncts of an entry that constrain a single symbol
+fn demands(entry: &Entry) -> Vec<(String, Demand)> {
+    let Some(rule) = &entry.rule else {
+        // Plain entries with a single value demand exactly that value, others only that the flag is enabled
+        return match entry.text.split_once('=') {
+            Some((name, value)) => match check::accepted_values(value.trim()).as_slice() {
+                ["n"] => vec![(symbol_name(name), Demand::Disabled)],
+                [value] => vec![(symbol_name(name), Demand::Value(value.trim_matches('"').to_string()))],
+                values if values.contains(&"n") => Vec::new(),
+                _ => vec![(symbol_name(name), Demand::Enabled)],
+            },
+            None => vec![(symbol_name(&entry.text), Demand::Enabled)],
+        };
+    };
+
+    let mut demands = Vec::new();
+    for term in conjuncts(rule) {
+        let demand = match term {
+            Expr::Test { symbol, op: None } => Some((symbol, Demand::Enabled)),
+            Expr::Not(inner) => match inner.as_ref() {
+                Expr::Test { symbol, op: None } => Some((symbol, Demand::Disabled)),
+                _ => None,
+            },
+            Expr::Test { symbol, op: Some((Op::Eq, value)) } if value == "n" => Some((symbol, Demand::Disabled)),
+            Expr::Test { symbol, op: Some((Op::Ne, value)) } if value == "n" => Some((symbol, Demand::Enabled)),
+            Expr::Test { symbol, op: Some((Op::Eq, value)) } => {
+                Some((symbol, Demand::Value(value.trim_matches('"').to_string())))
+            }
+            _ => None,
+        };
+        if let Some((symbol, demand)) = demand {
+            if symbol != "KERNEL" && symbol != "ARCH" {
+                demands.push((symbol_name(symbol), demand));
+            }
+        }
+    }
+    demands
+}
+
+fn conjuncts(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::And(a, b) => {
+            let mut terms = conjuncts(a);
+            terms.extend(conjuncts(b));
+            terms
+        }
+        other => vec![other],
+    }
+}
+
+fn check_contradictions(entries: &[Entry], diagnostics: &mut Vec<Diagnostic>) {
+    let mut demanded: HashMap<String, Vec<(Demand, &Entry)>> = HashMap::new();
+
+    for entry in entries {
+        for (symbol, demand) in demands(entry) {
+            let previous = demanded.entry(symbol.clone()).or_default();
+            let conflict = previous.iter().find(|(other, first)| demand.conflicts_with(other) && entry.shares_arch(first));
+            if let Some((other, first)) = conflict {
+                diagnostics.push(Diagnostic {
+                    source: entry.source.to_string(),
+                    line: entry.line,
+                    severity: Severity::Error,
+                    message: format!(
+                        "{} is {} here but {} at {}:{}",
+                        symbol,
+                        demand.describe(),
+                        other.describe(),
+                        first.source,
+                        first.line
+                    ),
+                });
+            }
+            previous.push((demand, entry));
+        }
+    }
+}
+
+/// Flag version gates that can never hold, either on their own or given the
+/// spec's top-level KERNEL requirements
+fn check_versions(entries: &[Entry], diagnostics: &mut Vec<Diagnostic>) {
+    let mut error = |entry: &Entry, message: String| {
+        diagnostics.push(Diagnostic {
+            source: entry.source.to_string(),
+            line: entry.line,
+            severity: Severity::Error,
+            message,
+        })
+    };
+
+    // Top-level KERNEL terms narrow the versions the whole spec applies to
+    let mut spec_range = VersionRange::default();
+    let mut gated = Vec::new();
+    for entry in entries {
+        let Some(rule) = &entry.rule else {
+            continue;
+        };
+        if !mentions_kernel(rule) {
+            continue;
+        }
+
+        if has_empty_range(rule, &VersionRange::default()) {
+            error(entry, format!("{} has a kernel version condition that can never hold", entry.text));
+            continue;
+        }
+
+        let mut narrowed = spec_range.clone();
+        for term in conjuncts(rule) {
+            if let Expr::Test { symbol, op: Some((op, version)) } = term {
+                if symbol == "KERNEL" {
+                    narrowed.constrain(*op, version);
+                }
+            }
+        }
+        if narrowed.is_empty() {
+            error(entry, format!("{} contradicts the kernel versions required by earlier entries", entry.text));
+            continue;
+        }
+        spec_range = narrowed;
+        gated.push((entry, rule));
+    }
+
+    for (entry, rule) in gated {
+        if has_empty_range(rule, &spec_range) {
+            error(
+                entry,
+                format!("{} has a kernel version condition excluded by the spec's own KERNEL requirements", entry.text),
+            );
+        }
+    }
+}
+
+fn mentions_kernel(expr: &Expr) -> bool {
+    match expr {
+        Expr::And(a, b) | Expr::Or(a, b) => mentions_kernel(a) || mentions_kernel(b),
+        Expr::Not(inner) => mentions_kernel(inner),
+        Expr::Test { symbol, .. } => symbol == "KERNEL",
+    }
+}
+
+/// Walk every conjunction, accumulating KERNEL bounds on top of `outer`, and
+/// report whether any of them is empty. Negated subtrees are not inspected.
+fn has_empty_range(expr: &Expr, outer: &VersionRange) -> bool {
+    let terms = conjuncts(expr);
+    let mut range = outer.clone();
+    for term in &terms {
+        if let Expr::Test { symbol, op: Some((op, version)) } = term {
+            if symbol == "KERNEL" {
+                range.constrain(*op, version);
+            }
+        }
+    }
+    if range.is_empty() {
+        return true;
+    }
+
+    terms.iter().any(|term| match term {
+        Expr::Or(a, b) => has_empty_range(a, &range) || has_empty_range(b, &range),
+        _ => false,
+    })
+}
+
+/// Flags that current kernels no longer have. Rules are left alone: they often name the
+/// old symbol next to the new one to cover older kernels too.
+fn check_renames(entries: &[Entry], diagnostics: &mut Vec<Diagnostic>) {
+    for entry in entries.iter().filter(|entry| entry.rule.is_none()) {
+        let symbol = symbol_name(entry.text.split_once('=').map_or(entry.text.as_str(), |(name, _)| name));
+        if let Some(rename) = renames::find(&symbol) {
+            diagnostics.push(Diagnostic {
+                source: entry.source.to_string(),
+                line: entry.line,
+                severity: Severity::Warning,
+                message: format!("CONFIG_{} was {}", symbol, rename),
+            });
+        }
+    }
+}
+
+fn check_unknown_symbols(entries: &[Entry], known: &dyn KnownSymbols, diagnostics: &mut Vec<Diagnostic>) {
+    let is_known = |symbol: &str| known.knows(symbol);
+
+    for entry in entries {
+        let symbols = match &entry.rule {
+            Some(rule) => unknown_symbols(rule, &is_known),
+            None => {
+                let symbol = symbol_name(entry.text.split_once('=').map_or(entry.text.as_str(), |(name, _)| name));
+                // Renamed symbols are reported by `check_renames`
+                if is_known(&symbol) || renames::find(&symbol).is_some() { Vec::new() } else { vec![symbol] }
+            }
+        };
+        for symbol in symbols {
+            diagnostics.push(Diagnostic {
+                source: entry.source.to_string(),
+                line: entry.line,
+                severity: Severity::Warning,
+                message: match kconfig::suggestions(&symbol, known).as_slice() {
+                    [] => format!("unknown symbol CONFIG_{}", symbol),
+                    suggestions => format!("unknown symbol CONFIG_{} (did you mean {}?)", symbol, suggestions.join(" or ")),
+                },
+            });
+        }
+    }
+}
+
+/// Unknown symbols in a rule. Alternatives often name both the old and new name of a
+/// renamed option, so an `||` is only reported when none of its branches is fully known.
+fn unknown_symbols(expr: &Expr, is_known: &impl Fn(&str) -> bool) -> Vec<String> {
+    match expr {
+        Expr::And(a, b) => {
+            let mut symbols = unknown_symbols(a, is_known);
+            symbols.extend(unknown_symbols(b, is_known));
+            symbols
+        }
+        Expr::Or(a, b) => {
+            let (a, b) = (unknown_symbols(a, is_known), unknown_symbols(b, is_known));
+            if a.is_empty() || b.is_empty() {
+                Vec::new()
+            } else {
+                a.into_iter().chain(b).collect()
+            }
+        }
+        Expr::Not(inner) => unknown_symbols(inner, is_known),
+        Expr::Test { symbol, .. } if symbol != "KERNEL" && symbol != "ARCH" => {
+            let symbol = symbol_name(symbol);
+            if is_known(&symbol) { Vec::new() } else { vec![symbol] }
+        }
+        Expr::Test { .. } => Vec::new(),
+    }
+}
@@ -1,91 +1,1904 @@
+use std::collections::HashMap;
 use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::Path;
-use clap::Parser;
+use clap::{ArgGroup, Parser, Subcommand};
 use colored::*;
 
+use kcc::{
+    arch, audit, bpf, buildsys, check, compress, container, controls, diff, dkms, dtb, edit, emit, expr, generate, history, hooks, html, ikconfig,
+    include, kconfig, lint, matrix, merge, openwrt, pattern, plugin, policy, probes, profiles, remote, renames, report, search, snapshot, spec, sysroot,
+    systemd, wizard, wsl,
+};
+use kcc::{
+    parse_flags, parse_flags_file, read_kernel_config, Checker, ConfigValue, FlagCheckResult, FlagRequirement, FlagStatus,
+    KernelConfig, Severity, PROC_CONFIG,
+};
+
+use edit::Edit;
+use kconfig::{KconfigDb, KnownSymbols};
+use remote::FetchPolicy;
+use report::ResultStatus;
+
+/// Print human-readable check output, unless a machine-readable --format was chosen
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if report::human() {
+            println!($($arg)*);
+        }
+    };
+}
+
 #[derive(Parser)]
 #[command(name = "kcc", author, version, about, long_about = None)]
 struct Cli {
-    /// Path to kernel config file (default: /proc/config.gz)
-    #[arg(short, long, default_value = "/proc/config.gz")]
-    config: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path or http(s):// URL of kernel config file (default: /proc/config.gz); `matrix` takes several
+    #[arg(short, long, global = true)]
+    config: Vec<String>,
 
-    /// Path to flags file containing kernel config flags to check
-    #[arg(short, long, value_name = "FILE")]
+    /// Check the running kernel of user@machine over SSH; -c then names a config path on that host
+    #[arg(long, value_name = "USER@MACHINE", global = true, conflicts_with_all = ["yocto", "buildroot"])]
+    host: Option<String>,
+
+    /// Check the kernel as seen from inside a running docker or podman container
+    #[arg(long, value_name = "NAME", global = true, conflicts_with_all = ["config", "host", "yocto", "buildroot"])]
+    container: Option<String>,
+
+    /// Look for configs, kernels and packages under this directory instead of / (offline images, rescue mounts)
+    #[arg(long, value_name = "DIR", global = true)]
+    root: Option<String>,
+
+    /// Check the kernel config generated inside a Yocto build directory
+    #[arg(long, value_name = "BUILDDIR", global = true, conflicts_with_all = ["config", "buildroot"])]
+    yocto: Option<String>,
+
+    /// Check the kernel config generated inside a Buildroot output directory
+    #[arg(long, value_name = "BUILDDIR", global = true, conflicts_with = "config")]
+    buildroot: Option<String>,
+
+    /// Path or http(s):// URL of flags file containing kernel config flags to check
+    #[arg(short, long, value_name = "FILE", global = true)]
     flags: Vec<String>,
 
-    /// Specific kernel config flags to check (comma-separated)
-    #[arg(long, value_name = "FLAGS")]
-    set_flags: Vec<String>,
+    /// Built-in flags profile to check (can be specified multiple times)
+    #[arg(short, long, value_name = "NAME", global = true)]
+    profile: Vec<String>,
+
+    /// List the built-in profiles and exit (deprecated, use `kcc list --profiles`)
+    #[arg(long, hide = true)]
+    list_profiles: bool,
+
+    /// Specific kernel config flags to check (comma-separated)
+    #[arg(long, value_name = "FLAGS", global = true)]
+    set_flags: Vec<String>,
+
+    /// Add the missing flags to the config file (deprecated, use `kcc set`)
+    #[arg(long)]
+    set: bool,
+
+    /// Disable colored output
+    #[arg(short, long, global = true)]
+    no_color: bool,
+
+    /// Deprecated, has no effect
+    #[arg(long, hide = true)]
+    check_incomplete: bool,
+
+    /// Deprecated, has no effect
+    #[arg(long, hide = true)]
+    check_missing: bool,
+
+    /// Connect timeout in seconds for remote config sources
+    #[arg(long, value_name = "SECS", default_value_t = 10, global = true)]
+    connect_timeout: u64,
+
+    /// Abort a remote transfer that stalls for this many seconds
+    #[arg(long, value_name = "SECS", default_value_t = 30, global = true)]
+    read_timeout: u64,
+
+    /// Number of retries for remote fetches that fail transiently
+    #[arg(long, value_name = "N", default_value_t = 3, global = true)]
+    retries: u32,
+
+    /// Initial delay between retries in milliseconds (doubles on each retry)
+    #[arg(long, value_name = "MS", default_value_t = 500, global = true)]
+    retry_backoff: u64,
+
+    /// CA certificate bundle used to verify https:// sources instead of the system store
+    #[arg(long, value_name = "FILE", global = true)]
+    cacert: Option<String>,
+
+    /// Don't verify the TLS certificate of https:// sources
+    #[arg(long, global = true)]
+    insecure: bool,
+
+    /// Refuse remote configs and flags files unless their SHA-256 matches this digest (can be specified multiple times)
+    #[arg(long, value_name = "DIGEST", global = true)]
+    sha256: Vec<String>,
+
+    /// Refuse remote configs and flags files not pinned in this sha256sum-style file of digests
+    #[arg(long, value_name = "FILE", global = true)]
+    lockfile: Option<String>,
+
+    /// Architecture the checked config was built for, deciding which `@ arch` entries apply instead of
+    /// detecting it from the config; a foreign architecture disables all host probes
+    #[arg(long, visible_alias = "arch", value_name = "ARCH", global = true)]
+    target_arch: Option<String>,
+
+    /// Never consult the running system (/proc, WSL detection, default config discovery)
+    #[arg(long, global = true)]
+    no_host_probes: bool,
+
+    /// Load the configs module without asking when /proc/config.gz is missing
+    #[arg(long, global = true)]
+    allow_modprobe: bool,
+
+    /// Validate flag names against this config instead of the running kernel's
+    #[arg(long, value_name = "CONFIG", global = true)]
+    validate_against: Option<String>,
+
+    /// Validate flag names against the Kconfig files of this kernel source tree, caching its symbols
+    #[arg(long, value_name = "DIR", global = true, conflicts_with = "validate_against")]
+    kernel_src: Option<String>,
+
+    #[command(flatten)]
+    check: CheckArgs,
+
+    #[command(flatten)]
+    set_args: SetArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check a config against the flags files, profiles and --set-flags (the default without a subcommand)
+    Check(CheckArgs),
+
+    /// Add the flags that are missing to a config file
+    Set(SetArgs),
+
+    /// List the options set in a config, or the built-in profiles
+    List {
+        /// Prefixes or globs of the options to list, with or without CONFIG_
+        #[arg(value_name = "PATTERN")]
+        patterns: Vec<String>,
+
+        /// Only list options matching this regular expression, searched for in the name without CONFIG_
+        /// (can be specified multiple times)
+        #[arg(long, value_name = "REGEX")]
+        regex: Vec<String>,
+
+        /// Only list options in this state (can be specified multiple times; default: every state but n)
+        #[arg(long, value_enum)]
+        state: Vec<pattern::State>,
+
+        /// List the built-in profiles instead
+        #[arg(long)]
+        profiles: bool,
+    },
+
+    /// Find config options with a fuzzy query, interactively in a terminal built with the tui feature
+    Search {
+        /// Words that must match an option's name or Kconfig prompt, e.g. `nfct` for NF_CONNTRACK
+        #[arg(value_name = "QUERY")]
+        query: Vec<String>,
+
+        /// Print the best matches instead of searching interactively
+        #[arg(long)]
+        print: bool,
+
+        /// How many matches to print
+        #[arg(long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Check every installed kernel package against the flags
+    #[command(group(ArgGroup::new("package_manager").required(true).args(["dpkg", "rpm"])))]
+    AuditInstalled {
+        /// Enumerate linux-image packages installed with dpkg (Debian, Ubuntu)
+        #[arg(long)]
+        dpkg: bool,
+
+        /// Enumerate kernel-core packages installed with rpm (Fedora, RHEL)
+        #[arg(long)]
+        rpm: bool,
+    },
+
+    /// Derive a flags file from what a program needs from the kernel
+    #[command(group(ArgGroup::new("source").required(true).args(["bpf", "unit"])))]
+    Derive {
+        /// eBPF object file to inspect (program types, maps, helpers, BTF)
+        #[arg(long, value_name = "PROG.O")]
+        bpf: Option<String>,
+
+        /// systemd unit whose sandboxing and resource control directives to inspect
+        #[arg(long, value_name = "UNIT")]
+        unit: Option<String>,
+    },
+
+    /// Check the flags files and profiles given with -f/-p for mistakes
+    LintSpec,
+
+    /// Check every config given with -c against every flags file and profile, reporting which satisfy which
+    Matrix {
+        /// Print the matrix as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that an out-of-tree module can be built with DKMS (config, headers, Module.symvers, dkms.conf)
+    CheckDkms {
+        /// Module as registered with dkms, optionally with its version (zfs or zfs/2.2.4)
+        module: String,
+
+        /// Kernel release to build for (default: the running kernel)
+        #[arg(long, value_name = "RELEASE")]
+        kernel: Option<String>,
+    },
+
+    /// Convert a spec between flags files, YAML/TOML specs and kconfig-hardened-check JSON
+    Convert {
+        /// Spec to convert
+        input: String,
+
+        /// Write the converted spec here instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+
+        /// Format of the input (guessed from its extension by default)
+        #[arg(long, value_enum)]
+        from: Option<spec::SpecFormat>,
+
+        /// Format to convert to (guessed from the output extension by default)
+        #[arg(long, value_enum)]
+        to: Option<spec::SpecFormat>,
+    },
+
+    /// Show the recorded check runs as a timeline, marking when a spec started or stopped being satisfied
+    History {
+        /// Only show runs whose spec mentions this profile or file
+        #[arg(long, value_name = "TEXT")]
+        spec: Option<String>,
+
+        /// Only show runs where the result, config or host changed
+        #[arg(long)]
+        changes: bool,
+
+        /// Show at most this many of the latest runs per spec
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+
+    /// Set options to y in a config file, like scripts/config --enable
+    Enable {
+        #[arg(required = true, value_name = "OPTION")]
+        options: Vec<String>,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Set options to m in a config file, like scripts/config --module
+    Module {
+        #[arg(required = true, value_name = "OPTION")]
+        options: Vec<String>,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Mark options as not set in a config file, like scripts/config --disable
+    Disable {
+        #[arg(required = true, value_name = "OPTION")]
+        options: Vec<String>,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Set an option to a value as written, like scripts/config --set-val
+    SetVal {
+        option: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Set an option to a quoted string, like scripts/config --set-str
+    SetStr {
+        option: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Remove options from a config file, like scripts/config --undefine
+    Undefine {
+        #[arg(required = true, value_name = "OPTION")]
+        options: Vec<String>,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Print the state of options: their value, n when not set or undef, like scripts/config --state
+    State {
+        #[arg(required = true, value_name = "OPTION")]
+        options: Vec<String>,
+        #[command(flatten)]
+        target: EditTarget,
+    },
+
+    /// Capture the options a config enables as a flags file, to require them on other machines
+    Generate {
+        /// Prefixes or globs of the options to capture, with or without CONFIG_ (default: every enabled option)
+        #[arg(value_name = "PATTERN")]
+        patterns: Vec<String>,
+
+        /// Write the flags file here instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+
+    /// Record the options of a config with a digest, as the baseline `verify` detects drift from
+    Snapshot {
+        /// Prefixes or globs of the options to record, with or without CONFIG_ (default: the full config)
+        #[arg(value_name = "PATTERN")]
+        patterns: Vec<String>,
+
+        /// Where to write the snapshot
+        #[arg(short, long, value_name = "FILE", default_value = "kernel.lock")]
+        output: String,
+    },
+
+    /// Check that a config still has the options recorded by `snapshot`, failing when it drifted
+    Verify {
+        /// The snapshot to verify against
+        #[arg(default_value = "kernel.lock")]
+        lock: String,
+    },
+
+    /// Compare two configs and report the options added, removed, switched between y/m/n or changed in value
+    Diff {
+        /// The config to compare from, any source -c takes
+        old: String,
+
+        /// The config to compare to
+        new: String,
+
+        /// Only report options starting with this prefix, with or without CONFIG_ (can be specified multiple times)
+        #[arg(long, value_name = "PREFIX")]
+        prefix: Vec<String>,
+
+        /// Print the differences as JSON instead
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Merge config fragments into a base config like scripts/kconfig/merge_config.sh -m, later fragments winning
+    Merge {
+        /// The config to start from, any source -c takes
+        base: String,
+
+        /// Fragments to merge in order
+        #[arg(required = true, value_name = "FRAGMENT")]
+        fragments: Vec<String>,
+
+        /// Write the merged config here instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+
+        /// Also warn about fragments setting an option to the value it already has
+        #[arg(short, long)]
+        redundant: bool,
+    },
+
+    /// Manage the cached Kconfig symbol databases used for validation, explain and suggestions
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+
+    /// Show what flags control: their Kconfig prompt, type, defaults, dependencies and help text
+    Explain {
+        /// Flags to explain, with or without the CONFIG_ prefix
+        #[arg(required = true, value_name = "FLAG")]
+        flags: Vec<String>,
+
+        /// Kernel version whose cached Kconfig symbols to use when --kernel-src isn't given (default: the running kernel)
+        #[arg(long, value_name = "VERSION")]
+        kernel: Option<String>,
+    },
+
+    /// Build a requirements file from a short questionnaire
+    Wizard {
+        /// Write the generated flags file here instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<String>,
+    },
+}
+
+/// Options of checking a config, given to `kcc check` or to `kcc` without a subcommand
+#[derive(clap::Args)]
+struct CheckArgs {
+    /// Output format of the check results
+    #[arg(long, value_enum, default_value = "text")]
+    format: report::OutputFormat,
+
+    /// Print version-stable `status<TAB>flag<TAB>value` lines for scripts (same as --format porcelain)
+    #[arg(long, conflicts_with = "format")]
+    porcelain: bool,
+
+    /// Browse the results in a terminal UI, with the reason, help text and dependencies of each flag
+    #[arg(long, conflicts_with_all = ["format", "porcelain"])]
+    tui: bool,
+
+    /// After a failed check, ask how to set each missing flag and write the answers to the config
+    #[arg(long, conflicts_with_all = ["format", "porcelain", "tui"])]
+    fix: bool,
+
+    /// Lowest severity whose unmet flags and rules fail the check; the others are only reported
+    #[arg(long, value_enum, value_name = "SEVERITY", default_value = "required")]
+    fail_on: Severity,
+
+    /// Stop at the first missing or invalid flag (the summary is still printed)
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Print missing flags as a NixOS boot.kernelPatches snippet instead of suggesting `kcc set`
+    #[arg(long)]
+    emit_nixos: bool,
+
+    /// Print missing flags as a Gentoo /etc/kernel/config.d fragment instead of suggesting `kcc set`
+    #[arg(long)]
+    emit_gentoo: bool,
+
+    /// After a failed check, write a commented shell script with the steps that fix it
+    #[arg(long, value_name = "FILE")]
+    emit_fixplan: Option<String>,
+
+    /// Write a standalone HTML page with the results, filterable by status
+    #[arg(long, value_name = "FILE")]
+    report: Option<String>,
+
+    /// Shared library plugin contributing additional checks (can be specified multiple times)
+    #[arg(long, value_name = "PATH")]
+    plugin: Vec<String>,
+
+    /// CEL (.cel) or Rego (.rego) policy evaluated against the config (can be specified multiple times)
+    #[arg(long, value_name = "FILE")]
+    policy: Vec<String>,
+
+    /// Shell command to run before checking (KCC_CONFIG is set in its environment)
+    #[arg(long, value_name = "CMD")]
+    pre_hook: Option<String>,
+
+    /// Shell command to run after checking, with the report on stdin and results in KCC_* variables
+    #[arg(long, value_name = "CMD")]
+    post_hook: Option<String>,
+
+    /// Device tree blob of the target board; drivers for its enabled devices are checked too
+    #[arg(long, value_name = "FILE")]
+    dtb: Option<String>,
+
+    /// Don't record this run in the local history (see `kcc history`)
+    #[arg(long)]
+    no_history: bool,
+}
+
+/// Options of adding flags to a config, given to `kcc set` or to `kcc --set`
+#[derive(clap::Args)]
+struct SetArgs {
+    /// Afterwards, run `make olddefconfig` in the kernel source tree holding the config (or --kernel-src)
+    /// and report the flags it dropped
+    #[arg(long)]
+    olddefconfig: bool,
+
+    /// Turn off the selected member of a Kconfig choice (e.g. the preemption model) to
+    /// select the requested one, instead of skipping the requested one
+    #[arg(long)]
+    replace_choice: bool,
+
+    /// Also enable the options the flags depend on, after printing the plan and asking for
+    /// confirmation; needs the Kconfig of --kernel-src or a cached symbol database
+    #[arg(long)]
+    with_deps: bool,
+
+    /// Enable the dependencies of --with-deps without asking
+    #[arg(long, requires = "with_deps")]
+    yes: bool,
+}
+
+/// Turn the `check` and `set` subcommands into the options of the single-mode interface, and point
+/// its flags that are going away to the subcommands replacing them
+fn fold_modes(cli: &mut Cli) -> anyhow::Result<()> {
+    match cli.command.take() {
+        Some(Command::Check(args)) => cli.check = args,
+        Some(Command::Set(args)) => {
+            cli.set_args = args;
+            cli.set = true;
+        }
+        command => {
+            cli.command = command;
+            if cli.set {
+                eprintln!("⚠️  --set is deprecated and will be removed in the next release, use `kcc set`");
+            }
+        }
+    }
+    if cli.list_profiles {
+        eprintln!("⚠️  --list-profiles is deprecated and will be removed in the next release, use `kcc list --profiles`");
+    }
+    if cli.check_missing || cli.check_incomplete {
+        eprintln!("⚠️  --check-missing and --check-incomplete have no effect and will be removed in the next release");
+    }
+    let SetArgs { olddefconfig, replace_choice, with_deps, .. } = cli.set_args;
+    if !cli.set && (olddefconfig || replace_choice || with_deps) {
+        return Err(anyhow::anyhow!("--olddefconfig, --replace-choice and --with-deps only apply to `kcc set`"));
+    }
+    Ok(())
+}
+
+/// The config file edited by the scripts/config style subcommands
+#[derive(clap::Args)]
+struct EditTarget {
+    /// Config file to edit (default: the -c config, or .config)
+    #[arg(long, value_name = "CONFIG")]
+    file: Option<String>,
+
+    /// Use option names as given instead of upper-casing them
+    #[arg(short, long)]
+    keep_case: bool,
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Build the database of a kernel version from its source tarball, downloaded from kernel.org by default
+    Fetch {
+        /// Kernel version, e.g. 6.8 or 6.8.12
+        version: String,
+
+        /// Source tarball to read instead, a local file or URL (.tar, .tar.gz, .tar.xz or .tar.zst)
+        #[arg(long, value_name = "FILE|URL")]
+        tarball: Option<String>,
+
+        /// Replace the cached database of the version
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// List the kernel versions with a cached database
+    List,
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut cli = Cli::parse();
+    fold_modes(&mut cli)?;
+
+    // Set up color output
+    if cli.no_color {
+        colored::control::set_override(true);
+    }
+    if cli.check.porcelain {
+        cli.check.format = report::OutputFormat::Porcelain;
+    }
+    report::set_format(cli.check.format);
+    if cli.check.tui {
+        if cfg!(not(feature = "tui")) {
+            return Err(anyhow::anyhow!("--tui is not available: kcc was built without the `tui` feature"));
+        }
+        if !io::stdout().is_terminal() {
+            return Err(anyhow::anyhow!("--tui needs a terminal, use --format for output to pipes and files"));
+        }
+        report::silence();
+    }
+    if cli.check.fix && !io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!("--fix asks about each missing flag and needs a terminal, use `kcc set` in scripts"));
+    }
+
+    if let Some(root) = &cli.root {
+        if cli.host.is_some() || cli.container.is_some() {
+            return Err(anyhow::anyhow!("--root can't be combined with --host or --container"));
+        }
+        sysroot::set(root)?;
+    }
+
+    if cli.list_profiles || matches!(cli.command, Some(Command::List { profiles: true, .. })) {
+        for profile in profiles::PROFILES {
+            println!("{:<16} {}", profile.name.bold(), profile.description);
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Derive { bpf, unit }) = &cli.command {
+        let (source, requirements) = match (bpf, unit) {
+            (Some(object), _) => (object, bpf::derive(object)?),
+            (_, Some(unit)) => (unit, systemd::derive(unit)?),
+            _ => unreachable!("clap requires --bpf or --unit"),
+        };
+        print!("{}", requirements.to_flags_file(source));
+        return Ok(());
+    }
+
+    if let Some(Command::Convert { input, output, from, to }) = &cli.command {
+        let from = from.unwrap_or_else(|| spec::SpecFormat::from_path(input).unwrap_or(spec::SpecFormat::Plain));
+        let to = to
+            .or_else(|| output.as_deref().and_then(spec::SpecFormat::from_path))
+            .ok_or_else(|| anyhow::anyhow!("Can't tell which format to convert to, pass --to"))?;
+
+        let content = fs::read_to_string(input).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", input, e))?;
+        // Included files are converted along with the flags file including them
+        let (content, metadata) = match from {
+            spec::SpecFormat::Plain => include::expand(input, &content)?,
+            _ => (content, Vec::new()),
+        };
+        let mut parsed = spec::parse(&content, from)?;
+        for requirement in &mut parsed.requirements {
+            if let Some(listed) = metadata.iter().find(|listed| listed.entry() == requirement.entry()) {
+                requirement.url = listed.url.clone();
+                requirement.min_kernel = listed.min_kernel.clone();
+                requirement.max_kernel = listed.max_kernel.clone();
+            }
+        }
+        if to == spec::SpecFormat::Plain && parsed.requirements.iter().any(spec::Requirement::has_metadata) {
+            eprintln!("⚠️  Flags files can't hold reference URLs and kernel versions, dropping them");
+        }
+        let converted = spec::render(&parsed, to)?;
+        match output {
+            Some(path) => {
+                fs::write(path, converted).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+                eprintln!("✅ Converted {} to {}", input, path);
+            }
+            None => print!("{}", converted),
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::CheckDkms { module, kernel }) = &cli.command {
+        let release = match kernel {
+            Some(release) => release.clone(),
+            None if cli.root.is_some() => sysroot::installed_release()
+                .ok_or_else(|| anyhow::anyhow!("No kernel installed under the root directory, pass --kernel"))?,
+            None => audit::running_version().ok_or_else(|| anyhow::anyhow!("Can't determine the running kernel, pass --kernel"))?,
+        };
+        let code = dkms::check(module, &release, cli.config.first().map(String::as_str))?;
+        std::process::exit(code);
+    }
+
+    if let Some(Command::History { spec, changes, limit }) = &cli.command {
+        return history::show(spec.as_deref(), *changes, *limit);
+    }
+
+    if let Some((target, edits)) = config_edits(&cli) {
+        let path = target.file.as_deref().or(cli.config.first().map(String::as_str)).unwrap_or(".config");
+        let mut content = fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        for (option, edit) in edits {
+            let option = edit::option_name(&option, target.keep_case);
+            match edit {
+                Some(edit) => content = edit::apply(&content, &option, &edit),
+                None => println!("{}", edit::state(&content, &option)),
+            }
+        }
+        if !matches!(cli.command, Some(Command::State { .. })) {
+            fs::write(path, content).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Diff { old, new, prefix, json }) = &cli.command {
+        let policy = fetch_policy(&cli);
+        let pins = remote::Pins::load(&cli.sha256, cli.lockfile.as_deref())?;
+        let mut configs = Vec::new();
+        for source in [old, new] {
+            let content = read_config_source(source, &policy, &pins)?;
+            let content = if openwrt::is_openwrt_config(&content) { openwrt::map_kernel_symbols(&content) } else { content };
+            configs.push(KernelConfig::parse(source, content));
+        }
+        let code = diff::run(&configs[0], &configs[1], prefix, *json);
+        std::process::exit(code);
+    }
+
+    if let Some(Command::Merge { base, fragments, output, redundant }) = &cli.command {
+        let policy = fetch_policy(&cli);
+        let pins = remote::Pins::load(&cli.sha256, cli.lockfile.as_deref())?;
+        let base_content = read_config_source(base, &policy, &pins)?;
+        let mut fragment_contents = Vec::new();
+        for fragment in fragments {
+            fragment_contents.push((fragment.clone(), read_config_source(fragment, &policy, &pins)?));
+        }
+        let merged = merge::merge(&base_content, &fragment_contents);
+        for warning in &merged.warnings {
+            if *redundant || matches!(warning, merge::Warning::Redefined { .. }) {
+                eprintln!("⚠️  {}", warning);
+            }
+        }
+        match output {
+            Some(path) => {
+                fs::write(path, &merged.config).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+                eprintln!("✅ Merged {} fragment(s) into {}", fragments.len(), path);
+            }
+            None => print!("{}", merged.config),
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Db { command }) = &cli.command {
+        return match command {
+            DbCommand::Fetch { version, tarball, force } => fetch_kconfig_db(&cli, version, tarball.as_deref(), *force),
+            DbCommand::List => {
+                let versions = KconfigDb::cached_versions();
+                if versions.is_empty() {
+                    println!("No cached Kconfig symbols yet, add them with kcc db fetch <VERSION> or --kernel-src <DIR>");
+                }
+                for version in versions {
+                    println!("{}", version);
+                }
+                Ok(())
+            }
+        };
+    }
+
+    if let Some(Command::Explain { flags, kernel }) = &cli.command {
+        let db = match (&cli.kernel_src, kernel) {
+            (Some(dir), _) => kconfig_db(dir)?,
+            (None, Some(version)) => KconfigDb::cached(version).ok_or_else(|| {
+                anyhow::anyhow!("No cached Kconfig symbols for {}, pass --kernel-src <DIR> with its source tree", version)
+            })?,
+            (None, None) => audit::running_version().and_then(|version| KconfigDb::cached(&version)).ok_or_else(|| {
+                anyhow::anyhow!("No cached Kconfig symbols for the running kernel, pass --kernel-src <DIR> or --kernel <VERSION>")
+            })?,
+        };
+        return explain(&db, flags);
+    }
+
+    if let Some(Command::Wizard { output }) = &cli.command {
+        let spec = wizard::run()?;
+        match output {
+            Some(path) => {
+                fs::write(path, spec).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+                eprintln!("✅ Wrote requirements to {} (check them with kcc -f {})", path, path);
+            }
+            None => print!("{}", spec),
+        }
+        return Ok(());
+    }
+
+    // A device tree alone is enough for a plain check, the subcommands only use explicit flags
+    let dtb_only = cli.check.dtb.is_some() && cli.command.is_none();
+    // Subcommands that only read the config need no flags
+    let reads_config = matches!(
+        cli.command,
+        Some(
+            Command::List { .. }
+                | Command::Search { .. }
+                | Command::Generate { .. }
+                | Command::Snapshot { .. }
+                | Command::Verify { .. }
+        )
+    );
+    if !reads_config && cli.flags.is_empty() && cli.set_flags.is_empty() && cli.profile.is_empty() && !dtb_only {
+        return Err(anyhow::anyhow!("At least one flags file, profile or set flags must be specified with -f/--flags, -p/--profile, --set-flags or --dtb"));
+    }
+
+    // Checking a cross-compiled config must not pick up anything from the build host
+    let host_arch = arch::host_arch();
+    let target_arch = cli.target_arch.as_deref().map(arch::parse).transpose()?;
+    let foreign_arch = target_arch.as_ref().is_some_and(|a| *a != host_arch);
+    let build_tree = match (&cli.yocto, &cli.buildroot) {
+        (Some(dir), _) => Some(buildsys::locate_yocto(dir)?),
+        (_, Some(dir)) => Some(buildsys::locate_buildroot(dir)?),
+        _ => None,
+    };
+    let host_probes = cfg!(all(feature = "linux-runtime", target_os = "linux"))
+        && !cli.no_host_probes
+        && !foreign_arch
+        && build_tree.is_none()
+        && cli.host.is_none()
+        && cli.container.is_none()
+        && cli.root.is_none();
+
+    let policy = fetch_policy(&cli);
+    let pins = remote::Pins::load(&cli.sha256, cli.lockfile.as_deref())?;
+    let (flag_files, metadata) = read_flags_files(&cli.flags, &policy, &pins)?;
+
+    if let Some(Command::AuditInstalled { dpkg, .. }) = &cli.command {
+        let kernels = if *dpkg { audit::dpkg_kernels()? } else { audit::rpm_kernels()? };
+        if kernels.is_empty() {
+            return Err(anyhow::anyhow!("No installed kernel packages found"));
+        }
+        let code = audit_installed_kernels(&kernels, &collect_flags(&cli, &flag_files)?);
+        std::process::exit(code);
+    }
+
+    if let Some(Command::LintSpec) = &cli.command {
+        let code = lint_spec(&cli, &flag_files, host_probes)?;
+        std::process::exit(code);
+    }
+
+    if let Some(Command::Matrix { json }) = &cli.command {
+        if cli.config.is_empty() {
+            return Err(anyhow::anyhow!("matrix checks the configs given with -c/--config, pass at least one"));
+        }
+        let mut configs = Vec::new();
+        for source in &cli.config {
+            let content = read_config_source(source, &policy, &pins)?;
+            let content = if openwrt::is_openwrt_config(&content) { openwrt::map_kernel_symbols(&content) } else { content };
+            configs.push((source.clone(), content));
+        }
+        let specs = collect_specs(&cli, &flag_files)?;
+        let code = matrix::run(&configs, &specs, *json);
+        std::process::exit(code);
+    }
+
+    // Where a config found without --config came from
+    let mut discovered = None;
+    let config = match (cli.config.as_slice(), &build_tree) {
+        (_, _) if cli.host.is_some() && cli.config.len() > 1 => {
+            return Err(anyhow::anyhow!("--host checks a single config, got {}", cli.config.len()))
+        }
+        ([config], _) => config.clone(),
+        ([], None) if cli.host.is_some() || cli.container.is_some() => String::new(),
+        ([], Some(tree)) => tree.config.display().to_string(),
+        ([], None) if host_probes => {
+            let (path, source) = default_config_path(cli.allow_modprobe)?;
+            discovered = Some(source);
+            path
+        }
+        ([], None) if cli.root.is_some() => {
+            let (path, source) = root_config_path()?;
+            discovered = Some(source);
+            path
+        }
+        ([], None) => return Err(anyhow::anyhow!("--config is required when host probes are disabled")),
+        _ => return Err(anyhow::anyhow!("Several configs given, use `kcc matrix` to check them all at once")),
+    };
+
+    if cli.set {
+        if remote::is_remote(&config) || cli.host.is_some() || cli.container.is_some() {
+            return Err(anyhow::anyhow!("kcc set requires a local config file, got: {}", config));
+        }
+        let settings = set_kernel_config_flags(&config, &flag_files, &cli, target_arch.as_deref())?;
+        if cli.set_args.olddefconfig {
+            resolve_with_olddefconfig(&config, cli.kernel_src.as_deref(), &settings, target_arch.as_deref())?;
+        }
+        return Ok(());
+    }
+
+    let container = cli.container.as_deref().map(container::resolve).transpose()?;
+    let (config, mut config_content) = match (&cli.host, &container) {
+        (Some(host), _) => {
+            let path = Some(config.as_str()).filter(|path| !path.is_empty());
+            remote::fetch_ssh_config(host, path, &policy, &pins)?
+        }
+        (_, Some(container)) => match container.read(PROC_CONFIG) {
+            Ok(data) => (format!("{}:{}", container.name, PROC_CONFIG), compress::decode_config(&data, PROC_CONFIG)?),
+            Err(e) => container_host_config(e, cli.allow_modprobe)?,
+        },
+        _ => {
+            let content = read_config_source(&config, &policy, &pins)?;
+            (config, content)
+        }
+    };
+
+    // OpenWrt's top-level .config wraps kernel symbols as CONFIG_KERNEL_*
+    let is_openwrt = openwrt::is_openwrt_config(&config_content);
+    if is_openwrt {
+        config_content = openwrt::map_kernel_symbols(&config_content);
+    }
+
+    if let Some(Command::Snapshot { patterns, output }) = &cli.command {
+        let snapshot = snapshot::Snapshot::capture(&KernelConfig::parse(&config, config_content.as_str()), patterns);
+        snapshot.save(output)?;
+        println!("🔒 Recorded {} options of {} in {}", snapshot.options.len(), config, output);
+        println!("   sha256 {}", snapshot.sha256);
+        return Ok(());
+    }
+    if let Some(Command::Verify { lock }) = &cli.command {
+        let snapshot = snapshot::Snapshot::load(lock)?;
+        let kernel_config = KernelConfig::parse(&config, config_content.as_str());
+        let drift = snapshot.verify(&kernel_config);
+        println!("🔒 Verifying {} against {} ({} options of {})", config, lock, snapshot.options.len(), snapshot.source);
+        if drift.is_empty() {
+            println!("{}", format!("✅ No drift, sha256 {}", snapshot.sha256).green().bold());
+            return Ok(());
+        }
+        println!("{}", format!("❌ {} options drifted, sha256 {}", drift.len(), snapshot.digest_of(&kernel_config)).red().bold());
+        println!();
+        diff::print_changes(&drift, "   ");
+        std::process::exit(1);
+    }
+    if let Some(Command::Generate { patterns, output }) = &cli.command {
+        let patterns: Vec<pattern::Pattern> = patterns.iter().map(|pattern| pattern::Pattern::new(pattern)).collect();
+        let flags = generate::flags_file(&KernelConfig::parse(&config, config_content.as_str()), &patterns);
+        match output {
+            Some(path) => {
+                fs::write(path, &flags).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+                let count = flags.lines().filter(|line| line.starts_with("CONFIG_")).count();
+                eprintln!("✅ Wrote {} flags to {} (check them with kcc -f {})", count, path, path);
+            }
+            None => print!("{}", flags),
+        }
+        return Ok(());
+    }
+    if let Some(Command::List { patterns, regex, state, .. }) = &cli.command {
+        let mut selected: Vec<pattern::Pattern> = patterns.iter().map(|pattern| pattern::Pattern::new(pattern)).collect();
+        for regex in regex {
+            selected.push(pattern::Pattern::regex(regex)?);
+        }
+        let kernel_config = KernelConfig::parse(&config, config_content.as_str());
+        let shown = |value: &ConfigValue| match state.as_slice() {
+            [] => value.tristate() != Some(kcc::Tristate::No),
+            states => states.iter().any(|state| state.matches(value)),
+        };
+        for (name, value) in kernel_config.iter().filter(|(name, value)| pattern::matches_any(&selected, name) && shown(value)) {
+            match value {
+                ConfigValue::NotSet => println!("# {} is not set", name),
+                value => println!("{}={}", name, value),
+            }
+        }
+        return Ok(());
+    }
+
+    // Symbols used to tell invalid flags apart from missing ones: Kconfig declares all of them, while
+    // a config only lists the ones whose dependencies were met. Kconfig also explains missing flags.
+    let kconfig = match &cli.kernel_src {
+        Some(dir) => Some(kconfig_db(dir)?),
+        None => expr::kernel_version(&config_content).and_then(|version| KconfigDb::cached(&version)).filter(|_| !is_openwrt),
+    };
+    if let Some(Command::Search { query, print, limit }) = &cli.command {
+        let candidates = search::candidates(&KernelConfig::parse(&config, config_content.as_str()), kconfig.as_ref());
+        let query = query.join(" ");
+        #[cfg(feature = "tui")]
+        if !print && io::stdin().is_terminal() && io::stdout().is_terminal() {
+            if let Some(name) = kcc::tui::search(&candidates, &query)? {
+                println!("{}", name);
+            }
+            return Ok(());
+        }
+        let matches = search::rank(&candidates, &query);
+        for candidate in matches.iter().take(*limit) {
+            let value = candidate.value.as_deref().unwrap_or("-");
+            match &candidate.prompt {
+                Some(prompt) => println!("{:<40} {:<8} {}", candidate.name, value, prompt.dimmed()),
+                None => println!("{:<40} {}", candidate.name, value),
+            }
+        }
+        if matches.len() > *limit && !print {
+            eprintln!("💡 {} more options match, refine the query or pass --limit", matches.len() - limit);
+        }
+        return Ok(());
+    }
+    let (validation, validation_source): (Option<Box<dyn KnownSymbols>>, Option<String>) = if let Some(dir) = &cli.kernel_src {
+        (kconfig.clone().map(|db| Box::new(db) as Box<dyn KnownSymbols>), Some(format!("the Kconfig files in {}", dir)))
+    } else if let Some(path) = &cli.validate_against {
+        (Some(Box::new(KernelConfig::load(path)?)), None)
+    } else if let Some(db) = &kconfig {
+        let version = db.version.as_deref().unwrap_or("this kernel");
+        let source = match db.version.as_deref().and_then(KconfigDb::cache_path) {
+            Some(path) => format!("the cached Kconfig symbols of {} ({})", version, path.display()),
+            None => format!("the cached Kconfig symbols of {}", version),
+        };
+        (Some(Box::new(db.clone())), Some(source))
+    } else if host_probes && !is_openwrt {
+        match KernelConfig::load(PROC_CONFIG) {
+            Ok(validation) => (Some(Box::new(validation)), None),
+            Err(e) => {
+                eprintln!("⚠️  Skipping flag validation, running kernel config unavailable: {}", e);
+                (None, None)
+            }
+        }
+    } else {
+        (None, None)
+    };
+
+    let mut all_flags = collect_flags(&cli, &flag_files)?;
+    let control_ids = collect_control_ids(&cli, &flag_files)?;
+    let reasons = collect_reasons(&cli, &flag_files);
+    let mut origins = collect_origins(&cli, &flag_files);
+    let device_tree = cli.check.dtb.as_deref().map(dtb::load).transpose()?;
+    if let (Some(tree), Some(path)) = (&device_tree, &cli.check.dtb) {
+        for flag in &tree.flags {
+            if !all_flags.iter().any(|f| controls::result_name(f) == controls::result_name(flag)) {
+                all_flags.push(flag.clone());
+                origins.insert(controls::result_name(flag), report::Origin { source: format!("dtb:{}", path), section: None, value: None });
+            }
+        }
+    }
+
+    if let Some(cmd) = &cli.check.pre_hook {
+        hooks::run_pre_hook(cmd, &[("KCC_CONFIG", config.clone())])?;
+    }
+
+    let kernel_config = KernelConfig::parse(&config, config_content.as_str());
+    say!("🔍 Kernel Config Checker - Checking kernel configuration flags from: {}", config);
+    if let Some(source) = discovered {
+        say!("📍 No --config given, using the {}", source);
+    }
+    if let Some(container) = &container {
+        say!("🐳 Container {} ({}, host PID {})", container.name, container.engine, container.pid);
+    }
+    if let Some(target) = &target_arch {
+        if foreign_arch {
+            say!("🎯 Target architecture: {} (host: {}, host probes disabled)", target, host_arch);
+        } else {
+            say!("🎯 Target architecture: {}", target);
+        }
+        if let Some(detected) = arch::detect_config_arch(&kernel_config) {
+            if detected != *target {
+                say!("{}", format!("⚠️  Config appears to be for {}, not {}", detected, target).yellow());
+            }
+        }
+    }
+    if is_openwrt {
+        say!("📡 OpenWrt config detected, mapping CONFIG_KERNEL_* options to kernel symbols");
+    }
+    if let Some(tree) = &build_tree {
+        say!("🏗️  {} build tree with {} config fragment(s)", tree.system, tree.fragments.len());
+        for fragment in &tree.fragments {
+            say!("   - {}", fragment.display());
+        }
+    }
+    if host_probes && wsl::is_wsl2() {
+        match wsl::custom_kernel_path() {
+            Some(kernel) => say!("🪟 Running under WSL2 with custom kernel: {}", kernel.display()),
+            None => say!("🪟 Running under WSL2 with the Microsoft kernel"),
+        }
+    }
+    if !cli.flags.is_empty() {
+        say!("📋 Reading flags from files: {}", cli.flags.join(", "));
+    }
+    if !cli.profile.is_empty() {
+        say!("📋 Using profiles: {}", cli.profile.join(", "));
+    }
+    if !cli.set_flags.is_empty() {
+        say!("📋 Checking specified flags: {}", cli.set_flags.join(", "));
+    }
+    if let Some(source) = &validation_source {
+        say!("📚 Validating flags against {}", source);
+    }
+    if let Some(tree) = &device_tree {
+        say!(
+            "📟 Device tree: {} ({} driver flag(s) from enabled devices)",
+            tree.model.as_deref().unwrap_or("unknown board"),
+            tree.flags.len()
+        );
+        if !tree.unmapped.is_empty() {
+            say!("💡 No known driver for {} compatible(s): {}", tree.unmapped.len(), tree.unmapped.join(", "));
+        }
+    }
+    say!();
+
+    let mut exit_code = 0;
+    let mut stopped_early = false;
+    let mut results = Vec::new();
+    let mut missing_flags_in_list = Vec::new();
+    let mut disabled_flags_in_list = Vec::new();
+    let mut wrong_value_in_list = Vec::new();
+    // The missing flags as they should be set, `FLAG=value` for value assertions
+    let mut missing_settings = Vec::new();
+    let mut expected_values = HashMap::new();
+    let mut invalid_flags_in_list = Vec::new();
+    let mut failed_rules_in_list = Vec::new();
+    let mut admin_disabled_in_list = Vec::new();
+    let mut forbidden_flags_in_list = Vec::new();
+    // Recommended and optional flags and rules that failed without failing the check
+    let mut advisory_in_list: Vec<(String, Severity)> = Vec::new();
+    let mut fixplan = emit::FixPlan { config: config.clone(), ..Default::default() };
+    // Runtime switches and probes only apply when checking the running kernel
+    let check_running = host_probes && cli.config.is_empty();
+
+    let kernel_version = expr::kernel_version(&config_content);
+    let requirements = collect_requirements(&all_flags, &control_ids, &reasons, &origins, &metadata)?;
+    let mut checker = match validation {
+        Some(validation) => Checker::new().validate_against(validation),
+        None => Checker::new(),
+    };
+    if let Some(target) = &target_arch {
+        checker = checker.arch(target);
+    }
+    let other_arch = requirements
+        .iter()
+        .filter(|requirement| !requirement.applies_to_arch(&kernel_config, target_arch.as_deref()))
+        .count();
+    if other_arch > 0 {
+        say!("💡 Skipping {} requirement(s) for other architectures", other_arch);
+    }
+    let other_kernels = requirements.iter().filter(|requirement| !requirement.applies_to_kernel(&kernel_config)).count();
+    if other_kernels > 0 {
+        say!("💡 Skipping {} requirement(s) for other kernel versions", other_kernels);
+    }
+    // Requirements whose dependency doesn't hold, by the symbols of the dependency
+    let mut dependents: HashMap<String, Vec<&str>> = HashMap::new();
+    let unmet: Vec<&FlagRequirement> = requirements
+        .iter()
+        .filter(|requirement| requirement.applies_to_arch(&kernel_config, target_arch.as_deref()))
+        .filter(|requirement| requirement.applies_to_kernel(&kernel_config))
+        .filter(|requirement| !requirement.dependency_met(&kernel_config))
+        .collect();
+    if !unmet.is_empty() {
+        say!("💡 Skipping {} requirement(s) whose dependencies aren't met:", unmet.len());
+        for requirement in &unmet {
+            let condition = requirement.depends_on.as_deref().unwrap_or_default();
+            say!("   - {} (depends on {})", requirement.name, condition);
+            for symbol in expr::parse(condition).map(|rule| expr::symbols(&rule)).unwrap_or_default() {
+                dependents.entry(symbol).or_default().push(&requirement.name);
+            }
+        }
+        say!();
+    }
+    let mut section = None;
+    for (requirement, mut result) in checker.check(&kernel_config, &requirements) {
+        if requirement.section.is_some() && requirement.section != section {
+            if !results.is_empty() {
+                say!();
+            }
+            section = requirement.section.clone();
+            say!("📂 {}", requirement.section.as_deref().unwrap_or_default().bold());
+        }
+        let ids = requirement.controls.as_slice();
+        let enabled = matches!(result.status, FlagStatus::EnabledInKernel | FlagStatus::EnabledAsModule);
+        let switched_off = if check_running && enabled { probes::administratively_disabled(&result.name) } else { None };
+        if switched_off.is_some() {
+            result.status = FlagStatus::AdminDisabled;
+        }
+        let checked = flag_result(&result, requirement, &origins, cli.check.fail_on)
+            .setting(report::config_setting(&kernel_config, &result.name));
+        let advisory = checked.advisory;
+        let line = format!("{}{}", result_line(&result, &checked), controls::suffix(ids));
+        match &switched_off {
+            Some(reason) => {
+                say!("{}: {}", line, reason);
+                results.push(checked.detail(reason.clone()));
+            }
+            None => {
+                say!("{}", line);
+                results.push(checked);
+            }
+        }
+
+        if advisory {
+            advisory_in_list.push((result.name, requirement.severity));
+        } else if let Some(reason) = switched_off {
+            exit_code = 1;
+            match probes::administrative_fix(&result.name) {
+                Some(fix) => fixplan.add(fix),
+                None => fixplan.note(format!("{}: {}", result.name, reason)),
+            }
+            admin_disabled_in_list.push(format!("{} ({})", result.name, reason));
+        } else if matches!(result.status, FlagStatus::Missing | FlagStatus::Disabled | FlagStatus::WrongValue) {
+            exit_code = 1;
+            missing_settings.push(match requirement.satisfying_value() {
+                Some(value) => format!("{}={}", result.name, value),
+                None => result.name.clone(),
+            });
+            if !requirement.accepted.is_empty() {
+                expected_values.insert(result.name.clone(), requirement.accepted.join(" or "));
+            }
+            match result.status {
+                FlagStatus::Disabled => disabled_flags_in_list.push(result.name),
+                FlagStatus::WrongValue => wrong_value_in_list.push(result.name),
+                _ => missing_flags_in_list.push(result.name),
+            }
+        } else if result.status == FlagStatus::Invalid {
+            exit_code = 1;
+            invalid_flags_in_list.push(result.name);
+        } else if result.status == FlagStatus::RuleFailed {
+            exit_code = 1;
+            failed_rules_in_list.push(result.name);
+        } else if result.status == FlagStatus::Forbidden {
+            exit_code = 1;
+            missing_settings.push(format!("{}=n", result.name));
+            forbidden_flags_in_list.push(result.name);
+        }
+
+        if cli.check.fail_fast && exit_code != 0 {
+            say!();
+            say!("⏹️  Stopping at first failure (--fail-fast)");
+            stopped_early = true;
+            break;
+        }
+    }
+
+    let mut failed_probes = Vec::new();
+    let profile_probes: Vec<(&str, &probes::Probe)> = cli
+        .profile
+        .iter()
+        .filter_map(|name| profiles::find(name).ok())
+        .flat_map(|profile| profile.probes.iter().map(move |probe| (profile.name, probe)))
+        .collect();
+    if !profile_probes.is_empty() && !stopped_early {
+        say!();
+        if check_running {
+            say!("🔎 Runtime probes:");
+            for (profile, probe) in &profile_probes {
+                let label = format!("[{}] {}", profile, probe.name);
+                let ids = probe.control.as_slice();
+                match (probe.run)() {
+                    Ok(()) => {
+                        say!("✅ {}{}", label.green(), controls::suffix(ids));
+                        results.push(report::CheckResult::new(ResultStatus::ProbePass, &label).controls(ids));
+                    }
+                    Err(reason) => {
+                        say!("❌ {}{}: {}", label.red(), controls::suffix(ids), reason);
+                        results.push(report::CheckResult::new(ResultStatus::ProbeFail, &label).detail(reason.clone()).controls(ids));
+                        exit_code = 1;
+                        match probe.fix {
+                            Some(fix) => fixplan.add(fix),
+                            None => fixplan.note(format!("{}: {}", label, reason)),
+                        }
+                        failed_probes.push(format!("{}{}", label, controls::suffix(ids)));
+                    }
+                }
+            }
+        } else {
+            say!("💡 Skipping {} runtime probe(s): they only apply to the running kernel", profile_probes.len());
+        }
+    }
+
+    // What the container's runtime lets it use of the shared kernel
+    let mut failed_container_checks = Vec::new();
+    if let (Some(container), false) = (&container, stopped_early) {
+        say!();
+        say!("🐳 Container runtime:");
+        for check in container.runtime_checks() {
+            let line = format!("{}: {}", check.name, check.detail);
+            let status = match check.status {
+                container::CheckStatus::Pass => ResultStatus::ContainerPass,
+                container::CheckStatus::Warn => ResultStatus::ContainerWarn,
+                container::CheckStatus::Fail => ResultStatus::ContainerFail,
+            };
+            results.push(report::CheckResult::new(status, check.name).detail(check.detail.clone()));
+            match check.status {
+                container::CheckStatus::Pass => {
+                    say!("✅ {}: {}", check.name.green(), check.detail);
+                }
+                container::CheckStatus::Warn => {
+                    say!("⚠️  {}: {}", check.name.yellow(), check.detail);
+                }
+                container::CheckStatus::Fail => {
+                    say!("❌ {}: {}", check.name.red(), check.detail);
+                    exit_code = 1;
+                    fixplan.note(format!("container {}", line));
+                    failed_container_checks.push(line);
+                }
+            }
+        }
+    }
+
+    // Environment facts shared with plugins and policies
+    let mut facts = vec![
+        ("config_source", config.clone()),
+        ("host_arch", host_arch.clone()),
+        ("openwrt", is_openwrt.to_string()),
+    ];
+    if let Some(target) = &target_arch {
+        facts.push(("target_arch", target.clone()));
+    }
+    if host_probes {
+        if let Some(release) = audit::running_version() {
+            facts.push(("kernel_release", release));
+        }
+        facts.push(("wsl2", wsl::is_wsl2().to_string()));
+        if let Ok(abi) = probes::landlock_abi() {
+            facts.push(("landlock_abi", abi.to_string()));
+        }
+    }
+
+    // Let plugins contribute their own checks, unless --fail-fast already stopped us
+    let mut failed_plugin_checks = Vec::new();
+    if !cli.check.plugin.is_empty() && !stopped_early {
+        say!();
+        say!("🔌 Plugin checks:");
+        for path in &cli.check.plugin {
+            for result in plugin::run(path, &config_content, &facts)? {
+                let label = format!("[{}] {}", result.plugin, result.name);
+                let status = match result.status {
+                    plugin::PluginStatus::Pass => ResultStatus::PluginPass,
+                    plugin::PluginStatus::Warn => ResultStatus::PluginWarn,
+                    plugin::PluginStatus::Fail => ResultStatus::PluginFail,
+                };
+                results.push(report::CheckResult::new(status, &label).detail(result.message.clone()));
+                let message = if result.message.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", result.message)
+                };
+                match result.status {
+                    plugin::PluginStatus::Pass => say!("✅ {}{}", label.green(), message),
+                    plugin::PluginStatus::Warn => say!("⚠️  {}{}", label.yellow(), message),
+                    plugin::PluginStatus::Fail => {
+                        say!("❌ {}{}", label.red(), message);
+                        exit_code = 1;
+                        fixplan.note(format!("plugin check {}{}", label, message));
+                        failed_plugin_checks.push(label);
+                    }
+                }
+            }
+        }
+    }
+
+    // Evaluate policy-as-code files against the config, cmdline and facts
+    let mut failed_policies = Vec::new();
+    if !cli.check.policy.is_empty() && !stopped_early {
+        let cmdline = if host_probes { fs::read_to_string("/proc/cmdline").ok() } else { None };
+        let document = policy::document(&kernel_config, cmdline.as_deref().map(str::trim), &facts);
+
+        say!();
+        say!("📜 Policy checks:");
+        for path in &cli.check.policy {
+            for result in policy::evaluate(path, &document)? {
+                let message = if result.message.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", result.message)
+                };
+                let status = match result.status {
+                    policy::PolicyStatus::Pass => ResultStatus::PolicyPass,
+                    policy::PolicyStatus::Warn => ResultStatus::PolicyWarn,
+                    policy::PolicyStatus::Fail => ResultStatus::PolicyFail,
+                };
+                results.push(report::CheckResult::new(status, &result.policy).detail(result.message.clone()));
+                match result.status {
+                    policy::PolicyStatus::Pass => say!("✅ {}{}", result.policy.green(), message),
+                    policy::PolicyStatus::Warn => say!("⚠️  {}{}", result.policy.yellow(), message),
+                    policy::PolicyStatus::Fail => {
+                        say!("❌ {}{}", result.policy.red(), message);
+                        exit_code = 1;
+                        fixplan.note(format!("policy {}{}", result.policy, message));
+                        failed_policies.push(format!("{}{}", result.policy, message));
+                    }
+                }
+            }
+        }
+    }
+
+    // Check for issues with flags in the list
+    if !missing_flags_in_list.is_empty()
+        || !disabled_flags_in_list.is_empty()
+        || !wrong_value_in_list.is_empty()
+        || !invalid_flags_in_list.is_empty()
+        || !failed_rules_in_list.is_empty()
+        || !failed_plugin_checks.is_empty()
+        || !failed_policies.is_empty()
+        || !failed_probes.is_empty()
+        || !failed_container_checks.is_empty()
+        || !admin_disabled_in_list.is_empty()
+        || !forbidden_flags_in_list.is_empty()
+        || !advisory_in_list.is_empty()
+    {
+        say!();
+        if !failed_container_checks.is_empty() {
+            say!("⚠️  Container runtime checks that failed:");
+            for check in &failed_container_checks {
+                say!("   - {}", check.red());
+            }
+        }
+        if !failed_probes.is_empty() {
+            say!("⚠️  Runtime probes that failed:");
+            for probe in &failed_probes {
+                say!("   - {}", probe.red());
+            }
+        }
+        if !failed_policies.is_empty() {
+            say!("⚠️  Policies that failed:");
+            for failure in &failed_policies {
+                say!("   - {}", failure.red());
+            }
+        }
+        if !failed_rules_in_list.is_empty() {
+            say!("⚠️  Rules in your list that are not satisfied:");
+            for rule in &failed_rules_in_list {
+                say!("   - {}{}", rule.red(), control_ids.suffix(rule));
+                if let Some(reason) = reasons.get(rule) {
+                    say!("     💬 {}", reason);
+                }
+                if let Some(url) = metadata.get(rule).and_then(|requirement| requirement.url.as_ref()) {
+                    say!("     📖 {}", url);
+                }
+            }
+        }
+        if !failed_plugin_checks.is_empty() {
+            say!("⚠️  Plugin checks that failed:");
+            for check in &failed_plugin_checks {
+                say!("   - {}", check.red());
+            }
+        }
+        for (severity, heading) in [
+            (Severity::Recommended, "💡 Recommended flags and rules that aren't met, not failing the check:"),
+            (Severity::Optional, "ℹ️  Optional flags and rules that aren't met, not failing the check:"),
+        ] {
+            let names: Vec<&String> = advisory_in_list.iter().filter(|(_, s)| *s == severity).map(|(name, _)| name).collect();
+            if names.is_empty() {
+                continue;
+            }
+            say!("{}", heading);
+            for name in names {
+                say!("   - {}{}", name.yellow(), control_ids.suffix(name));
+                if let Some(reason) = reasons.get(name) {
+                    say!("     💬 {}", reason);
+                }
+                if let Some(url) = metadata.get(name).and_then(|requirement| requirement.url.as_ref()) {
+                    say!("     📖 {}", url);
+                }
+            }
+        }
+        let unmet = [
+            ("⚠️  Flags in your list that are missing from config:", &missing_flags_in_list),
+            ("⚠️  Flags in your list that are not set in config:", &disabled_flags_in_list),
+            ("⚠️  Flags in your list that are set to another value in config:", &wrong_value_in_list),
+        ];
+        for (heading, flags) in unmet.into_iter().filter(|(_, flags)| !flags.is_empty()) {
+            say!("{}", heading);
+            for flag in flags {
+                let actual = kernel_config.get(flag).filter(|_| wrong_value_in_list.contains(flag));
+                match (actual, expected_values.get(flag)) {
+                    (Some(actual), Some(expected)) => {
+                        say!("   - {} (is {}, expected {}){}", flag.red(), actual, expected, control_ids.suffix(flag))
+                    }
+                    (None, Some(expected)) => say!("   - {} (expected {}){}", flag.red(), expected, control_ids.suffix(flag)),
+                    (Some(actual), None) => say!("   - {} (is {}){}", flag.red(), actual, control_ids.suffix(flag)),
+                    (None, None) => say!("   - {}{}", flag.red(), control_ids.suffix(flag)),
+                }
+                if let Some(reason) = reasons.get(flag) {
+                    say!("     💬 {}", reason);
+                }
+                if let Some(url) = metadata.get(flag).and_then(|requirement| requirement.url.as_ref()) {
+                    say!("     📖 {}", url);
+                }
+                if let Some(rename) = renames::find(flag).filter(|rename| rename.applies_to(kernel_version.as_deref())) {
+                    say!("     🔀 {}", rename);
+                }
+                if let Some(names) = dependents.get(flag) {
+                    say!("     ⛓️  needed before these can be checked: {}", names.join(", "));
+                }
+                if let Some(db) = &kconfig {
+                    for unmet in db.dependency_chain(&kernel_config, flag) {
+                        let name = if unmet.name == *flag { "requires" } else { &format!("{} requires", unmet.name) };
+                        say!("     🔗 {} {}", name, unmet.needs.join(" and "));
+                    }
+                    let selected_by = db.selected_by(flag);
+                    if db.get(flag).is_some_and(|symbol| symbol.prompt.is_none()) && !selected_by.is_empty() {
+                        say!("     🔗 has no prompt, enable one of the options selecting it: {}", selected_by.join(", "));
+                    }
+                }
+                if let Some(tree) = &build_tree {
+                    match tree.attribute(flag) {
+                        Some(a) => say!("     🧩 fix in {}:{}: {}", a.fragment.display(), a.line_number, a.line),
+                        None => say!("     🧩 not set by any fragment, add it to one of your {} fragments", tree.system),
+                    }
+                }
+            }
+        }
+        if !forbidden_flags_in_list.is_empty() {
+            say!("⚠️  Flags in your list that must be disabled but are enabled:");
+            for flag in &forbidden_flags_in_list {
+                say!("   - {}{}", flag.red(), control_ids.suffix(flag));
+                if let Some(reason) = reasons.get(flag) {
+                    say!("     💬 {}", reason);
+                }
+                if let Some(url) = metadata.get(flag).and_then(|requirement| requirement.url.as_ref()) {
+                    say!("     📖 {}", url);
+                }
+            }
+        }
+        if !admin_disabled_in_list.is_empty() {
+            say!("⚠️  Flags compiled in but administratively disabled:");
+            for flag in &admin_disabled_in_list {
+                say!("   - {}", flag.red());
+            }
+        }
+        if !invalid_flags_in_list.is_empty() {
+            say!("⚠️  Flags in your list that don't exist in kernel config options:");
+            for flag in &invalid_flags_in_list {
+                say!("   - {}{}", flag.yellow(), control_ids.suffix(flag));
+                if let Some(rename) = renames::find(flag).filter(|rename| rename.applies_to(kernel_version.as_deref())) {
+                    say!("     🔀 {}", rename);
+                } else if let Some(known) = checker.validation() {
+                    let suggestions = kconfig::suggestions(flag, known);
+                    if !suggestions.is_empty() {
+                        say!("     💡 did you mean {}?", suggestions.join(" or "));
+                    }
+                }
+            }
+        }
+        if !missing_settings.is_empty() && (cli.check.emit_nixos || cli.check.emit_gentoo) {
+            if cli.check.emit_nixos {
+                say!();
+                say!("❄️  Add this to your NixOS configuration to enable the missing flags:");
+                say!();
+                say!("{}", emit::nixos(&missing_settings).trim_end());
+            }
+            if cli.check.emit_gentoo {
+                say!();
+                say!("🐧 Save this as /etc/kernel/config.d/kcc.config to persist the missing flags:");
+                say!();
+                say!("{}", emit::gentoo(&missing_settings).trim_end());
+            }
+        } else {
+            if let Some(first) = missing_flags_in_list.iter().chain(&disabled_flags_in_list).next() {
+                say!("📝 Consider using `kcc set` to add missing flags to your config file");
+                if is_openwrt {
+                    say!("📝 In OpenWrt, enable them as {} etc. (Global build settings → Kernel build options)", openwrt::wrapper_symbol(first));
+                }
+            }
+            // `kcc set` only adds flags, it doesn't change the value of one that is set
+            if !wrong_value_in_list.is_empty() {
+                say!("📝 Change the flags set to another value in your config file, or with --fix");
+            }
+        }
+    }
+
+    let sections = section_summaries(&results);
+    if !sections.is_empty() {
+        say!();
+        say!("📂 By section:");
+        for section in &sections {
+            if !section.failed.is_empty() {
+                let failed = section.failed.join(", ");
+                say!("   ❌ {}: {}/{} met, failing: {}", section.name.red(), section.met, section.total, failed);
+            } else if section.met < section.total {
+                say!("   💡 {}: {}/{} met", section.name.yellow(), section.met, section.total);
+            } else {
+                say!("   ✅ {}: {}/{} met", section.name.green(), section.met, section.total);
+            }
+        }
+    }
+
+    if let (Some(path), true) = (&cli.check.emit_fixplan, exit_code != 0) {
+        for flag in &missing_settings {
+            if !fixplan.missing_flags.contains(flag) {
+                fixplan.missing_flags.push(flag.clone());
+            }
+        }
+        for rule in &failed_rules_in_list {
+            fixplan.note(format!("rule not satisfied: {}", rule));
+        }
+        for flag in &invalid_flags_in_list {
+            match renames::find(flag).filter(|rename| rename.applies_to(kernel_version.as_deref())) {
+                Some(rename) => fixplan.note(format!("{} was {}", flag, rename)),
+                None => fixplan.note(format!("{} doesn't exist in this kernel, check the name or kernel version", flag)),
+            }
+        }
+        write_fixplan(path, &emit::fixplan(&fixplan))?;
+        say!();
+        say!("🛠️  Wrote a remediation plan to {}", path);
+    }
+
+    if let Some(path) = &cli.check.report {
+        let (time, host) = history::now_and_host();
+        let mut metadata = html::config_metadata(&config_content);
+        if let Some(target) = &target_arch {
+            metadata.push(("Target architecture", target.clone()));
+        }
+        metadata.push(("Checked on", format!("{} at {} UTC", host, history::format_time(time))));
+        let page = html::render(&report::Report::new(&config, results.clone(), exit_code), &metadata);
+        fs::write(path, page).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+        say!();
+        say!("📄 Wrote an HTML report to {}", path);
+    }
+
+    // Enabled attack surface outranks everything else that failed
+    if !forbidden_flags_in_list.is_empty() {
+        exit_code = 5;
+    }
+
+    say!();
+    if exit_code == 0 && results.is_empty() {
+        // Every entry was skipped for its architecture, kernel version or dependency
+        say!("💡 No requirements apply to this config");
+    } else if exit_code == 0 {
+        say!("✅ All required kernel flags are enabled!");
+        if !advisory_in_list.is_empty() {
+            say!("💡 {} recommended or optional requirement(s) aren't met, --fail-on makes them fail the check", advisory_in_list.len());
+        }
+    } else if !forbidden_flags_in_list.is_empty() {
+        say!("🚫 Some kernel flags that must be disabled are enabled!");
+    } else {
+        say!("❌ Some required kernel flags are missing!");
+    }
+
+    if !cli.check.no_history {
+        let mut spec: Vec<String> = cli.profile.iter().map(|name| format!("profile:{}", name)).collect();
+        spec.extend(cli.flags.iter().cloned());
+        if !cli.set_flags.is_empty() {
+            spec.push(format!("set-flags:{}", cli.set_flags.join(",")));
+        }
+        if let Some(path) = &cli.check.dtb {
+            spec.push(format!("dtb:{}", path));
+        }
+        let (time, host) = history::now_and_host();
+        let run = history::Run {
+            time,
+            host,
+            config: config.clone(),
+            config_sha256: remote::sha256_hex(config_content.as_bytes()),
+            spec: spec.join(" "),
+            spec_sha256: remote::sha256_hex(all_flags.join("\n").as_bytes()),
+            checked: all_flags.len(),
+            missing: missing_flags_in_list.len() + disabled_flags_in_list.len() + wrong_value_in_list.len(),
+            invalid: invalid_flags_in_list.len(),
+            failed: failed_rules_in_list.len()
+                + failed_probes.len()
+                + failed_container_checks.len()
+                + failed_plugin_checks.len()
+                + failed_policies.len()
+                + admin_disabled_in_list.len()
+                + forbidden_flags_in_list.len(),
+            exit_code,
+        };
+        if let Err(e) = history::record(&run) {
+            eprintln!("⚠️  Not recording this run in the history: {}", e);
+        }
+    }
+
+    if let Some(cmd) = &cli.check.post_hook {
+        let env = [
+            ("KCC_CONFIG", config.clone()),
+            ("KCC_EXIT_CODE", exit_code.to_string()),
+            ("KCC_MISSING", missing_flags_in_list.join(",")),
+            ("KCC_DISABLED", disabled_flags_in_list.join(",")),
+            ("KCC_WRONG_VALUE", wrong_value_in_list.join(",")),
+            ("KCC_INVALID", invalid_flags_in_list.join(",")),
+            ("KCC_FAILED_RULES", failed_rules_in_list.join(";")),
+            ("KCC_FAILED_PLUGIN_CHECKS", failed_plugin_checks.join(",")),
+            ("KCC_FAILED_POLICIES", failed_policies.join(";")),
+            ("KCC_FAILED_PROBES", failed_probes.join(";")),
+            ("KCC_FAILED_CONTAINER_CHECKS", failed_container_checks.join(";")),
+            ("KCC_ADMIN_DISABLED", admin_disabled_in_list.join(";")),
+            ("KCC_FORBIDDEN", forbidden_flags_in_list.join(",")),
+        ];
+        let report: String = results.iter().map(|result| result.report_line() + "\n").collect();
+        if let Err(e) = hooks::run_post_hook(cmd, &report, &env) {
+            eprintln!("⚠️  {}", e);
+        }
+    }
+
+    if cli.check.fix && exit_code != 0 {
+        fix_interactively(&config, &results)?;
+    }
+    #[cfg(feature = "tui")]
+    if cli.check.tui {
+        kcc::tui::browse(&report::Report::new(&config, results, exit_code), &kernel_config, kconfig.as_ref())?;
+        std::process::exit(exit_code);
+    }
+    if cli.check.format != report::OutputFormat::Text {
+        print!("{}", report::Report::new(&config, results, exit_code).render(cli.check.format)?);
+    }
+
+    std::process::exit(exit_code);
+}
+
+/// Write the remediation plan as an executable script
+fn write_fixplan(path: &str, script: &str) -> anyhow::Result<()> {
+    fs::write(path, script).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| anyhow::anyhow!("Failed to make {} executable: {}", path, e))?;
+    }
+    Ok(())
+}
+
+/// Lint the flags files and profiles, returning the exit code
+fn lint_spec(cli: &Cli, flag_files: &[(String, String)], host_probes: bool) -> anyhow::Result<i32> {
+    let mut files = flag_files.to_vec();
+    for name in &cli.profile {
+        files.push((format!("profile:{}", name), profiles::find(name)?.flags.to_string()));
+    }
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("lint-spec checks flags files and profiles, pass them with -f/--flags or -p/--profile"));
+    }
+
+    // Unknown symbols can only be spotted with Kconfig or a config that lists every symbol
+    let known_symbols: Option<Box<dyn KnownSymbols>> = if let Some(dir) = &cli.kernel_src {
+        Some(Box::new(kconfig_db(dir)?))
+    } else if let Some(path) = &cli.validate_against {
+        Some(Box::new(KernelConfig::load(path)?))
+    } else if host_probes && Path::new(PROC_CONFIG).exists() {
+        Some(Box::new(KernelConfig::load(PROC_CONFIG)?))
+    } else {
+        println!("💡 Pass --kernel-src <DIR> or --validate-against <CONFIG> to also check for unknown symbols");
+        None
+    };
+
+    let diagnostics = lint::lint(&files, known_symbols.as_deref());
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
 
-    /// Set flags from file (adds missing flags to .config file)
-    #[arg(long)]
-    set: bool,
+    let errors = diagnostics.iter().filter(|d| d.severity == lint::Severity::Error).count();
+    let warnings = diagnostics.len() - errors;
+    if diagnostics.is_empty() {
+        println!("{}", "✅ No problems found".green().bold());
+    } else {
+        println!("\n{} error(s), {} warning(s)", errors, warnings);
+    }
 
-    /// Disable colored output
-    #[arg(short, long)]
-    no_color: bool,
+    Ok(if errors > 0 { 1 } else { 0 })
+}
 
-    /// Check for flags in the list that are missing from config
-    #[arg(long)]
-    check_incomplete: bool,
+/// Print the Kconfig declarations of flags, failing on flags Kconfig doesn't declare
+fn explain(db: &KconfigDb, flags: &[String]) -> anyhow::Result<()> {
+    for (i, flag) in flags.iter().enumerate() {
+        let name = format!("CONFIG_{}", flag.trim().trim_start_matches("CONFIG_"));
+        let version = db.version.as_deref().unwrap_or("this kernel");
+        let symbol = db.get(&name).ok_or_else(|| anyhow::anyhow!("{} is not declared in the Kconfig files of {}", name, version))?;
 
-    /// Show only missing flags
-    #[arg(long)]
-    check_missing: bool,
+        if i > 0 {
+            println!();
+        }
+        match &symbol.prompt {
+            Some(prompt) => println!("📖 {}: {}", name.bold(), prompt),
+            None => println!("📖 {} {}", name.bold(), "(no prompt, only enabled through other options)".dimmed()),
+        }
+        let fields = [
+            ("Type", symbol.kind.clone().unwrap_or_else(|| "unknown".to_string())),
+            ("Depends on", dependencies(&symbol.depends_on)),
+            ("Default", symbol.defaults.join(", ")),
+            ("Selects", symbol.selects.join(", ")),
+            ("Defined in", symbol.files.join(", ")),
+        ];
+        for (label, value) in fields.iter().filter(|(_, value)| !value.is_empty()) {
+            println!("   {:<11} {}", format!("{}:", label), value);
+        }
+        if let Some(help) = &symbol.help {
+            println!();
+            for line in help.lines() {
+                println!("   {}", line);
+            }
+        }
+    }
+    Ok(())
 }
 
-#[derive(Debug, PartialEq)]
-enum FlagStatus {
-    EnabledInKernel,
-    EnabledAsModule,
-    Missing,
-    Invalid, // Flag doesn't exist in kernel config options
+/// `depends on` expressions joined into one, parenthesized where needed
+fn dependencies(conditions: &[String]) -> String {
+    if conditions.len() == 1 {
+        return conditions[0].clone();
+    }
+    let parenthesize = |condition: &String| if condition.contains(' ') { format!("({})", condition) } else { condition.clone() };
+    conditions.iter().map(parenthesize).collect::<Vec<_>>().join(" && ")
 }
 
-struct FlagCheckResult {
-    name: String,
-    status: FlagStatus,
+/// Options with the edit for each, `None` for printing its state
+type OptionEdits = Vec<(String, Option<Edit>)>;
+
+/// The config file and option edits of a scripts/config style subcommand
+fn config_edits(cli: &Cli) -> Option<(&EditTarget, OptionEdits)> {
+    let each = |options: &[String], edit: Option<Edit>| options.iter().map(|option| (option.clone(), edit.clone())).collect();
+    Some(match cli.command.as_ref()? {
+        Command::Enable { options, target } => (target, each(options, Some(Edit::Enable))),
+        Command::Module { options, target } => (target, each(options, Some(Edit::Module))),
+        Command::Disable { options, target } => (target, each(options, Some(Edit::Disable))),
+        Command::Undefine { options, target } => (target, each(options, Some(Edit::Undefine))),
+        Command::State { options, target } => (target, each(options, None)),
+        Command::SetVal { option, value, target } => (target, vec![(option.clone(), Some(Edit::SetVal(value.clone())))]),
+        Command::SetStr { option, value, target } => (target, vec![(option.clone(), Some(Edit::SetStr(value.clone())))]),
+        _ => return None,
+    })
 }
 
-impl FlagCheckResult {
-    fn format_output(&self) -> String {
-        if self.status == FlagStatus::EnabledInKernel {
-            format!("✅ {}", self.name.green())
-        } else if self.status == FlagStatus::EnabledAsModule {
-            format!("✅ {} (as module)", self.name.green())
-        } else if self.status == FlagStatus::Missing {
-            format!("❌ {}", self.name.red())
-        } else {
-            format!("⚠️  {} (invalid flag)", self.name.yellow())
-        }
+/// Timeouts, retries and TLS settings for remote sources, from the command line
+fn fetch_policy(cli: &Cli) -> FetchPolicy {
+    FetchPolicy {
+        connect_timeout: cli.connect_timeout,
+        read_timeout: cli.read_timeout,
+        retries: cli.retries,
+        backoff_ms: cli.retry_backoff,
+        ca_cert: cli.cacert.clone(),
+        insecure: cli.insecure,
     }
 }
 
-fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+/// Build the Kconfig database of a kernel version from its source tarball and cache it
+fn fetch_kconfig_db(cli: &Cli, version: &str, tarball: Option<&str>, force: bool) -> anyhow::Result<()> {
+    let major = version.split('.').next().filter(|major| major.parse::<u32>().is_ok());
+    let major = major.ok_or_else(|| anyhow::anyhow!("Invalid kernel version '{}', expected e.g. 6.8", version))?;
+    // Don't download a tarball only to refuse caching it
+    if let Some(path) = KconfigDb::cache_path(version).filter(|path| path.exists() && !force) {
+        return Err(anyhow::anyhow!("{} is already cached in {}, pass --force to replace it", version, path.display()));
+    }
+    let source = match tarball {
+        Some(tarball) => tarball.to_string(),
+        None => format!("https://cdn.kernel.org/pub/linux/kernel/v{}.x/linux-{}.tar.xz", major, version),
+    };
 
-    // Set up color output
-    if cli.no_color {
-        colored::control::set_override(true);
+    let db = if remote::is_remote(&source) {
+        eprintln!("⬇️  Downloading {}", source);
+        let pins = remote::Pins::load(&cli.sha256, cli.lockfile.as_deref())?;
+        KconfigDb::from_tarball(remote::fetch(&source, &fetch_policy(cli), &pins)?.as_slice(), &source)?
+    } else {
+        let file = fs::File::open(&source).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", source, e))?;
+        KconfigDb::from_tarball(file, &source)?
+    };
+
+    let found = db.version.as_deref().unwrap_or("an unknown version");
+    if expr::compare_versions(found, version) != std::cmp::Ordering::Equal && !version.starts_with(&format!("{}.", found)) {
+        return Err(anyhow::anyhow!("{} is the source of {}, not {}", source, found, version));
     }
+    let path = db.save_to_cache(force)?;
+    println!("✅ Cached {} Kconfig symbols of {} in {}", db.symbols.len(), found, path.display());
+    Ok(())
+}
 
-    if cli.flags.is_empty() && cli.set_flags.is_empty() {
-        return Err(anyhow::anyhow!("At least one flags file or set flags must be specified with -f/--flags or --set-flags"));
+/// Read the Kconfig symbols of a kernel source tree and cache them for configs of its version
+fn kconfig_db(dir: &str) -> anyhow::Result<KconfigDb> {
+    let db = KconfigDb::from_source(dir)?;
+    // A cached database is only replaced by `kcc db fetch --force`
+    if db.is_cached() {
+        return Ok(db);
+    }
+    if let Err(e) = db.save_to_cache(false) {
+        eprintln!("⚠️  Not caching the Kconfig symbols of {}: {}", dir, e);
     }
+    Ok(db)
+}
 
-    if cli.set {
-        return set_kernel_config_flags(&cli.config, &cli.flags, &cli.set_flags);
+/// The text of flags files by path, and the requirements of the YAML and TOML specs among them keyed like check results
+type FlagsFiles = (Vec<(String, String)>, HashMap<String, spec::Requirement>);
+
+/// Read the flags files, YAML and TOML specs as the flags files they convert to, along with the
+/// requirements of those specs, which are checked as written rather than as their converted lines
+fn read_flags_files(paths: &[String], policy: &FetchPolicy, pins: &remote::Pins) -> anyhow::Result<FlagsFiles> {
+    let mut files = Vec::new();
+    let mut metadata = HashMap::new();
+    for path in paths {
+        let content = if remote::is_remote(path) {
+            let data = remote::fetch(path, policy, pins)?;
+            String::from_utf8(data).map_err(|e| anyhow::anyhow!("Failed to read flags file {}: {}", path, e))?
+        } else {
+            fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read flags file {}: {}", path, e))?
+        };
+        let (content, requirements) = spec::as_flags_file(path, &content)?;
+        for requirement in requirements {
+            if let Some(entry) = requirement.entry() {
+                metadata.entry(controls::result_name(entry)).or_insert(requirement);
+            }
+        }
+        files.push((path.clone(), content));
+    }
+    Ok((files, metadata))
+}
+
+/// Each flags file, profile and the --set-flags list as a separate spec, for the matrix
+fn collect_specs(cli: &Cli, flag_files: &[(String, String)]) -> anyhow::Result<Vec<matrix::Spec>> {
+    let mut specs = Vec::new();
+    for (path, content) in flag_files {
+        specs.push(matrix::Spec { name: path.clone(), flags: parse_flags_file(path, content)? });
+    }
+    for name in &cli.profile {
+        specs.push(matrix::Spec { name: format!("profile:{}", name), flags: parse_flags(profiles::find(name)?.flags)? });
+    }
+    let set_flags: Vec<String> = cli
+        .set_flags
+        .iter()
+        .flat_map(|flags| flags.split(','))
+        .map(|flag| flag.trim().to_string())
+        .filter(|flag| !flag.is_empty())
+        .collect();
+    for flag in set_flags.iter().filter(|flag| check::is_rule(flag)) {
+        expr::parse(flag)?;
     }
+    if !set_flags.is_empty() {
+        specs.push(matrix::Spec { name: "set-flags".to_string(), flags: set_flags });
+    }
+    Ok(specs)
+}
 
-    let config_content = read_kernel_config(&cli.config)?;
+/// Gather the flags to check from flags files, profiles and --set-flags
+fn collect_flags(cli: &Cli, flag_files: &[(String, String)]) -> anyhow::Result<Vec<String>> {
     let mut all_flags = Vec::new();
     
     // Read flags from files
-    for flag_file in &cli.flags {
-        let flags = read_flags_file(flag_file)?;
+    for (path, content) in flag_files {
+        let flags = parse_flags_file(path, content)?;
         all_flags.extend(flags);
     }
+
+    // Add flags from built-in profiles
+    for name in &cli.profile {
+        all_flags.extend(parse_flags(profiles::find(name)?.flags)?);
+    }
     
     // Add directly set flags (handle comma-separated values)
     for flags_str in &cli.set_flags {
@@ -93,203 +1906,365 @@ fn main() -> anyhow::Result<()> {
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty())
             .collect();
+        for flag in &flags {
+            if check::is_rule(flag) {
+                expr::parse(flag)?;
+            }
+        }
         all_flags.extend(flags);
     }
 
-    println!("🔍 Kernel Config Checker - Checking kernel configuration flags from: {}", cli.config);
-    if !cli.flags.is_empty() {
-        println!("📋 Reading flags from files: {}", cli.flags.join(", "));
+    Ok(all_flags)
+}
+
+/// Gather the compliance control IDs tagged in flags files and profiles
+fn collect_control_ids(cli: &Cli, flag_files: &[(String, String)]) -> anyhow::Result<controls::ControlIds> {
+    let mut ids = controls::ControlIds::default();
+    for (_, content) in flag_files {
+        ids.add_file(content);
     }
-    if !cli.set_flags.is_empty() {
-        println!("📋 Checking specified flags: {}", cli.set_flags.join(", "));
+    for name in &cli.profile {
+        ids.add_file(profiles::find(name)?.flags);
     }
-    println!();
+    Ok(ids)
+}
 
-    let mut exit_code = 0;
-    let mut missing_flags_in_list = Vec::new();
-    let mut invalid_flags_in_list = Vec::new();
+/// Explanations for entries, taken from the comments directly above them
+fn collect_reasons(cli: &Cli, flag_files: &[(String, String)]) -> HashMap<String, String> {
+    let mut contents: Vec<String> = flag_files.iter().map(|(_, content)| content.clone()).collect();
+    contents.extend(cli.profile.iter().filter_map(|name| profiles::find(name).ok()).map(|p| p.flags.to_string()));
 
-    for flag in &all_flags {
-        let result = check_flag(&config_content, flag);
-        println!("{}", result.format_output());
-        
-        if result.status == FlagStatus::Missing {
-            exit_code = 1;
-            missing_flags_in_list.push(result.name);
-        } else if result.status == FlagStatus::Invalid {
-            exit_code = 1;
-            invalid_flags_in_list.push(result.name);
+    let mut reasons = HashMap::new();
+    for content in contents {
+        // Files that don't parse as a spec are reported when their flags are read
+        let Ok(parsed) = spec::parse(&content, spec::SpecFormat::Plain) else {
+            continue;
+        };
+        for requirement in parsed.requirements {
+            let (Some(reason), Some(entry)) = (requirement.reason, requirement.rule.or(requirement.flag)) else {
+                continue;
+            };
+            reasons.entry(controls::result_name(&entry)).or_insert(reason);
         }
     }
+    reasons
+}
 
-    // Check for issues with flags in the list
-    if !missing_flags_in_list.is_empty() || !invalid_flags_in_list.is_empty() {
-        println!();
-        if !missing_flags_in_list.is_empty() {
-            println!("⚠️  Flags in your list that are missing from config:");
-            for flag in &missing_flags_in_list {
-                println!("   - {}", flag.red());
-            }
+/// Where and under which section each entry was listed, keyed like check results; the first listing wins
+fn collect_origins(cli: &Cli, flag_files: &[(String, String)]) -> HashMap<String, report::Origin> {
+    let mut sources: Vec<(String, String)> = flag_files.to_vec();
+    for name in &cli.profile {
+        if let Ok(profile) = profiles::find(name) {
+            sources.push((format!("profile:{}", profile.name), profile.flags.to_string()));
         }
-        if !invalid_flags_in_list.is_empty() {
-            println!("⚠️  Flags in your list that don't exist in kernel config options:");
-            for flag in &invalid_flags_in_list {
-                println!("   - {}", flag.yellow());
-            }
-        }
-        if !missing_flags_in_list.is_empty() {
-            println!("📝 Consider using --set to add missing flags to your config file");
+    }
+    for flags in &cli.set_flags {
+        sources.push(("--set-flags".to_string(), flags.split(',').collect::<Vec<_>>().join("\n")));
+    }
+
+    let mut origins = HashMap::new();
+    for (source, content) in sources {
+        // Files that don't parse as a spec are reported when their flags are read
+        let Ok(parsed) = spec::parse(&content, spec::SpecFormat::Plain) else {
+            continue;
+        };
+        for requirement in parsed.requirements {
+            let Some(entry) = requirement.rule.or(requirement.flag) else {
+                continue;
+            };
+            origins
+                .entry(controls::result_name(&entry))
+                .or_insert_with(|| report::Origin { source: source.clone(), section: requirement.section, value: requirement.value });
         }
     }
+    origins
+}
 
-    println!();
-    if exit_code == 0 {
-        println!("✅ All required kernel flags are enabled!");
-    } else {
-        println!("❌ Some required kernel flags are missing!");
+/// The requirements behind the checked flags and rules, with their control IDs, reasons, sources,
+/// sections, those of YAML and TOML specs built from their fields, grouped by section
+fn collect_requirements(
+    all_flags: &[String],
+    control_ids: &controls::ControlIds,
+    reasons: &HashMap<String, String>,
+    origins: &HashMap<String, report::Origin>,
+    metadata: &HashMap<String, spec::Requirement>,
+) -> anyhow::Result<Vec<FlagRequirement>> {
+    all_flags
+        .iter()
+        .map(|flag| {
+            let name = controls::result_name(flag);
+            // Requirements of YAML and TOML specs are built from their fields
+            let mut builder = match metadata.get(&name) {
+                Some(requirement) => requirement.builder()?,
+                None => FlagRequirement::builder(flag),
+            };
+            for id in control_ids.get(&name) {
+                builder = builder.control(id);
+            }
+            if let Some(reason) = reasons.get(&name) {
+                builder = builder.reason(reason);
+            }
+            if let Some(origin) = origins.get(&name) {
+                builder = builder.source(&origin.source);
+                if let Some(section) = &origin.section {
+                    builder = builder.section(section);
+                }
+            }
+            builder.build()
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(|mut requirements| {
+            kcc::requirement::group_by_section(&mut requirements);
+            requirements
+        })
+}
+
+/// A section of the checked flags files: how many of its requirements are met, and those that fail the check
+struct SectionSummary<'a> {
+    name: &'a str,
+    met: usize,
+    total: usize,
+    failed: Vec<&'a str>,
+}
+
+/// Each section in the order it was checked; unmet recommended and optional requirements count as
+/// not met without failing
+fn section_summaries(results: &[report::CheckResult]) -> Vec<SectionSummary<'_>> {
+    let mut sections: Vec<SectionSummary> = Vec::new();
+    for result in results {
+        let Some(name) = result.section.as_deref() else {
+            continue;
+        };
+        let index = match sections.iter().position(|section| section.name == name) {
+            Some(index) => index,
+            None => {
+                sections.push(SectionSummary { name, met: 0, total: 0, failed: Vec::new() });
+                sections.len() - 1
+            }
+        };
+        let section = &mut sections[index];
+        section.total += 1;
+        if result.failed() {
+            section.failed.push(&result.name);
+        } else if !result.warned() {
+            section.met += 1;
+        }
     }
+    sections
+}
 
-    std::process::exit(exit_code);
+/// The structured result of a flag or rule, with its control IDs, reason, reference URL, origin and severity
+fn flag_result(
+    result: &FlagCheckResult,
+    requirement: &FlagRequirement,
+    origins: &HashMap<String, report::Origin>,
+    fail_on: Severity,
+) -> report::CheckResult {
+    report::CheckResult::new(result.status, &result.name)
+        .controls(&requirement.controls)
+        .reason(requirement.reason.as_ref())
+        .url(requirement.url.as_ref())
+        .origin(origins.get(&result.name), requirement)
+        .severity(requirement.severity, fail_on)
 }
 
-fn read_kernel_config(path: &str) -> anyhow::Result<String> {
-    let path = Path::new(path);
-    
-    if !path.exists() {
-        return Err(anyhow::anyhow!("Config file not found: {}", path.display()));
+/// The output line of a flag or rule, tagged with its severity unless it's required; a failure
+/// that doesn't fail the check is marked with the severity's icon instead of ❌
+fn result_line(result: &FlagCheckResult, checked: &report::CheckResult) -> String {
+    let line = result.format_output();
+    if checked.severity.is_required() {
+        return line;
     }
+    let line = match line.split_once(' ') {
+        Some((_, rest)) if checked.advisory => format!("{} {}", severity_icon(checked.severity), rest.trim_start()),
+        _ => line,
+    };
+    format!("{} {}", line, format!("[{}]", checked.severity.as_str()).dimmed())
+}
 
-    if path.extension().and_then(|s| s.to_str()) == Some("gz") {
-        // Handle compressed config files
-        use std::process::Command;
-        let output = Command::new("zcat")
-            .arg(path)
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run zcat: {}", e))?;
-        
-        if !output.status.success() {
-            return Err(anyhow::anyhow!("zcat failed: {}", String::from_utf8_lossy(&output.stderr)));
-        }
-        
-        Ok(String::from_utf8(output.stdout)?)
-    } else {
-        // Handle uncompressed config files
-        fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))
+fn severity_icon(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Required => "❌",
+        Severity::Recommended => "💡",
+        Severity::Optional => "ℹ️ ",
     }
 }
 
-fn read_flags_file(path: &str) -> anyhow::Result<Vec<String>> {
-    let content = fs::read_to_string(path)
-        .map_err(|e| anyhow::anyhow!("Failed to read flags file: {}", e))?;
-    
-    let mut flags = Vec::new();
-    
-    for line in content.lines() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') {
+/// Check each installed kernel's config and report which ones would break the workload
+fn audit_installed_kernels(kernels: &[audit::InstalledKernel], all_flags: &[String]) -> i32 {
+    let running = audit::running_version();
+
+    println!("📦 Auditing {} installed kernel(s)", kernels.len());
+    println!();
+
+    let mut broken = 0;
+    for kernel in kernels {
+        let label = if running.as_deref() == Some(kernel.version.as_str()) {
+            format!("{} ({}) [running]", kernel.version, kernel.package)
+        } else {
+            format!("{} ({})", kernel.version, kernel.package)
+        };
+
+        let Some(config) = &kernel.config else {
+            println!("⚠️  {}: no kernel config found", label.yellow());
+            broken += 1;
             continue;
-        }
-        
-        // Handle both "FLAG" and "FLAG=value" formats
-        if trimmed.contains('=') {
-            // Extract the flag name before the equals sign
-            let flag_name = trimmed.split_once('=').unwrap().0;
-            flags.push(flag_name.to_string());
+        };
+
+        let kernel_config = match KernelConfig::load(&config.display().to_string()) {
+            Ok(kernel_config) => kernel_config,
+            Err(e) => {
+                println!("⚠️  {}: {}", label.yellow(), e);
+                broken += 1;
+                continue;
+            }
+        };
+
+        // Installed kernels aren't validated against the running kernel's symbols
+        let requirements: Vec<FlagRequirement> = all_flags.iter().map(|flag| FlagRequirement::new(flag)).collect();
+        let missing: Vec<String> = Checker::new()
+            .check(&kernel_config, &requirements)
+            .into_iter()
+            .map(|(_, result)| result)
+            .filter(|result| {
+                matches!(
+                    result.status,
+                    FlagStatus::Missing | FlagStatus::Disabled | FlagStatus::WrongValue | FlagStatus::RuleFailed | FlagStatus::Forbidden
+                )
+            })
+            .map(|result| result.name)
+            .collect();
+
+        if missing.is_empty() {
+            println!("✅ {}", label.green());
         } else {
-            // It's just a flag name
-            flags.push(trimmed.to_string());
+            println!("❌ {}: {} missing", label.red(), missing.len());
+            for flag in &missing {
+                println!("   - {}", flag.red());
+            }
+            broken += 1;
         }
     }
-    
-    Ok(flags)
+
+    println!();
+    if broken == 0 {
+        println!("✅ All installed kernels satisfy the required flags!");
+        0
+    } else {
+        println!("❌ {} installed kernel(s) would break the workload if booted!", broken);
+        1
+    }
 }
 
-fn check_kernel_config_exists(flag: &str) -> bool {
-    let path = "/proc/config.gz";
-    
-    if let Ok(config_content) = read_kernel_config(path) {
-        for line in config_content.lines() {
-            if line.starts_with(flag) {
-                return true;
-            }
-        }
+/// Pick the kernel config to check when --config isn't given, with a description of where it came from
+fn default_config_path(allow_modprobe: bool) -> anyhow::Result<(String, &'static str)> {
+    if Path::new(PROC_CONFIG).exists() {
+        return Ok((PROC_CONFIG.to_string(), "running kernel's embedded config"));
     }
-    
-    // Fallback: try to read directly with zcat
-    use std::process::Command;
-    let output = Command::new("zcat")
-        .arg(path)
-        .output();
-    
-    if let Ok(output) = output {
-        if output.status.success() {
-            let config_content = String::from_utf8_lossy(&output.stdout);
-            for line in config_content.lines() {
-                if line.starts_with(flag) {
-                    return true;
-                }
-            }
+
+    if wsl::is_wsl2() {
+        // A custom WSL2 kernel without IKCONFIG_PROC may ship its config alongside the image
+        if let Some(config) = wsl::custom_kernel_path().and_then(|k| wsl::custom_kernel_config(&k)) {
+            return Ok((config.display().to_string(), "config shipped with the custom WSL2 kernel"));
         }
     }
-    
-    false
-}
 
-fn check_flag(config_content: &str, flag: &str) -> FlagCheckResult {
-    // Remove CONFIG_ prefix if it already exists in the input
-    let clean_flag = if flag.starts_with("CONFIG_") {
-        &flag[7..]
-    } else {
-        flag
+    let Some(release) = audit::running_version() else {
+        return Err(anyhow::anyhow!("No kernel config found: {} is missing and the running kernel is unknown, pass --config", PROC_CONFIG));
     };
-    
-    let config_flag = format!("CONFIG_{}=", clean_flag);
-    
-    // Check if the flag actually exists in kernel config options
-    if !check_kernel_config_exists(&config_flag) {
-        return FlagCheckResult {
-            name: format!("CONFIG_{}", clean_flag),
-            status: FlagStatus::Invalid,
-        };
+
+    // With CONFIG_IKCONFIG=m, /proc/config.gz only appears once the module is loaded
+    if ikconfig::configs_module_available(&release) && ikconfig::load_configs_module(allow_modprobe) {
+        return Ok((PROC_CONFIG.to_string(), "running kernel's embedded config (configs module)"));
     }
-    
-    for line in config_content.lines() {
-        if line.starts_with(&config_flag) {
-            let value = &line[config_flag.len()..];
-            match value {
-                "y" => return FlagCheckResult {
-                    name: format!("CONFIG_{}", clean_flag),
-                    status: FlagStatus::EnabledInKernel,
-                },
-                "m" => return FlagCheckResult {
-                    name: format!("CONFIG_{}", clean_flag),
-                    status: FlagStatus::EnabledAsModule,
-                },
-                _ => {}
-            }
-        }
+
+    let candidates = installed_config_candidates(&release);
+    if let Some((path, source)) = candidates.iter().find(|(path, _)| Path::new(path).exists()) {
+        return Ok((path.clone(), source));
     }
-    
-    FlagCheckResult {
-        name: format!("CONFIG_{}", clean_flag),
-        status: FlagStatus::Missing,
+
+    let tried: Vec<&str> = std::iter::once(PROC_CONFIG).chain(candidates.iter().map(|(path, _)| path.as_str())).collect();
+    Err(anyhow::anyhow!("No kernel config found, tried {}; pass --config", tried.join(", ")))
+}
+
+/// The host's config for a container whose own view of it can't be read, as the container runs
+/// on the host's kernel and the host's config describes it too
+#[cfg(feature = "linux-runtime")]
+fn container_host_config(e: anyhow::Error, allow_modprobe: bool) -> anyhow::Result<(String, String)> {
+    eprintln!("⚠️  {}, using the host's config of the shared kernel", e);
+    let (path, _) = default_config_path(allow_modprobe)?;
+    let content = read_kernel_config(&path)?;
+    Ok((path, content))
+}
+
+#[cfg(not(feature = "linux-runtime"))]
+fn container_host_config(e: anyhow::Error, _allow_modprobe: bool) -> anyhow::Result<(String, String)> {
+    Err(anyhow::anyhow!("{}; the host's config is only looked up with the linux-runtime feature", e))
+}
+
+/// Pick the config of the newest kernel installed under --root
+fn root_config_path() -> anyhow::Result<(String, &'static str)> {
+    let release = sysroot::installed_release()
+        .ok_or_else(|| anyhow::anyhow!("No kernel installed under the root directory, pass --config"))?;
+    let candidates = installed_config_candidates(&release);
+    if let Some((path, source)) = candidates.iter().find(|(path, _)| Path::new(path).exists()) {
+        return Ok((path.clone(), source));
+    }
+
+    let tried: Vec<&str> = candidates.iter().map(|(path, _)| path.as_str()).collect();
+    Err(anyhow::anyhow!("No config found for {}, tried {}; pass --config", release, tried.join(", ")))
+}
+
+/// Where the config of an installed kernel release may be, in order of how likely
+/// they are to match it exactly
+fn installed_config_candidates(release: &str) -> Vec<(String, &'static str)> {
+    let path = |p: String| sysroot::path(&p).display().to_string();
+    vec![
+        (path(format!("/boot/config-{}", release)), "config installed with the kernel"),
+        (path(format!("/boot/config-{}.xz", release)), "config installed with the kernel"),
+        (path(format!("/boot/config-{}.zst", release)), "config installed with the kernel"),
+        (path(format!("/lib/modules/{}/config", release)), "config installed with the kernel's modules"),
+        (path(format!("/lib/modules/{}/build/.config", release)), "config of the kernel's headers"),
+        (path("/usr/src/linux/.config".to_string()), "config in /usr/src/linux, which may not match the kernel"),
+    ]
+}
+
+/// Read a local config or fetch a remote one, checking it against the pins
+fn read_config_source(source: &str, policy: &FetchPolicy, pins: &remote::Pins) -> anyhow::Result<String> {
+    if remote::is_remote(source) {
+        remote::fetch_config(source, policy, pins)
+    } else {
+        read_kernel_config(source)
     }
 }
 
-fn set_kernel_config_flags(config_path: &str, flag_files: &[String], set_flags: &[String]) -> anyhow::Result<()> {
+/// Add the flags of the flags files, --profile and --set-flags to a config file, returning the
+/// symbols it set and their values
+fn set_kernel_config_flags(
+    config_path: &str,
+    flag_files: &[(String, String)],
+    cli: &Cli,
+    target_arch: Option<&str>,
+) -> anyhow::Result<Vec<(String, String)>> {
     println!("🔧 Adding flags to kernel config file: {}", config_path);
     
     let mut all_flags = Vec::new();
     
     // Read flags from files
-    for flag_file in flag_files {
-        let flags = read_flags_file(flag_file)?;
+    for (flag_file, content) in flag_files {
+        let flags = parse_flags_file(flag_file, content)?;
         all_flags.extend(flags);
         println!("📋 Reading flags from file: {}", flag_file);
     }
+
+    // Add flags from built-in profiles
+    for name in &cli.profile {
+        all_flags.extend(parse_flags(profiles::find(name)?.flags)?);
+        println!("📋 Using profile: {}", name);
+    }
     
     // Add directly set flags (handle comma-separated values)
-    for flags_str in set_flags {
+    for flags_str in &cli.set_flags {
         let flags: Vec<String> = flags_str.split(',')
             .map(|s| s.trim().to_string())
             .filter(|s| !s.is_empty())
@@ -298,40 +2273,115 @@ fn set_kernel_config_flags(config_path: &str, flag_files: &[String], set_flags:
         println!("📋 Adding specified flags: {}", flags_str);
     }
 
+    // Read the current config file
+    let config_content = read_kernel_config(config_path)?;
+    let kernel_config = KernelConfig::parse(config_path, config_content.as_str());
+
+    // Rules describe conditions, not options that can be added, and flags that must be disabled aren't added either
+    all_flags.retain(|flag| {
+        let requirement = FlagRequirement::new(flag);
+        let forbidden = requirement.accepted.iter().any(|value| value == "n");
+        let is_rule = requirement.is_rule();
+        let other_arch = !requirement.applies_to_arch(&kernel_config, target_arch);
+        if other_arch {
+            println!("⚠️  Skipping flag for another architecture: {}", flag.yellow());
+        } else if forbidden {
+            println!("⚠️  Skipping flag that must be disabled, it isn't added: {}", flag.yellow());
+        } else if is_rule {
+            println!("⚠️  Skipping rule, it can't be set: {}", flag.yellow());
+        }
+        !other_arch && !forbidden && !is_rule
+    });
+
+    // Choices and dependencies are looked up in Kconfig when it's at hand, otherwise only the common choices are known
+    let kconfig = match &cli.kernel_src {
+        Some(dir) => Some(kconfig_db(dir)?),
+        None => expr::kernel_version(&config_content).and_then(|version| KconfigDb::cached(&version)),
+    };
+    if let (None, Some(path)) = (&cli.kernel_src, kconfig.as_ref().and_then(|db| KconfigDb::cache_path(db.version.as_deref()?))) {
+        println!("📚 Using the cached Kconfig symbols in {}", path.display());
+    }
+    if cli.set_args.with_deps {
+        let db = kconfig.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("--with-deps needs the Kconfig of the kernel, pass --kernel-src or fetch it with `kcc db fetch`")
+        })?;
+        add_prerequisites(db, &kernel_config, &mut all_flags, cli.set_args.yes)?;
+    }
+
     // Remove duplicates
     all_flags.sort();
     all_flags.dedup();
 
     println!();
     println!("🎯 Adding {} flags to .config file:", all_flags.len());
-
-    // Read the current config file
-    let config_content = read_kernel_config(config_path)?;
     let mut config_lines: Vec<String> = config_content.lines().map(|s| s.to_string()).collect();
-    let mut added_count = 0;
+    let mut added = Vec::new();
     let mut already_exists_count = 0;
+    let mut conflicting_count = 0;
+
+    // OpenWrt only passes CONFIG_KERNEL_* wrapper options through to the kernel
+    let prefix = if openwrt::is_openwrt_config(&config_content) {
+        println!("📡 OpenWrt config detected, adding CONFIG_KERNEL_* options");
+        "CONFIG_KERNEL_"
+    } else {
+        "CONFIG_"
+    };
 
     for flag in &all_flags {
-        let clean_flag = if flag.starts_with("CONFIG_") {
-            &flag[7..]
-        } else {
-            flag
-        };
+        // `FLAG=value` entries are added with their value, `FLAG>=64` with the bound, plain flags as built in
+        let requirement = FlagRequirement::new(flag);
+        let value = requirement.satisfying_value().unwrap_or_else(|| "y".to_string());
+        let clean_flag = requirement.name.strip_prefix("CONFIG_").unwrap_or(&requirement.name);
         
-        let config_flag = format!("CONFIG_{}=", clean_flag);
-        let config_line = format!("CONFIG_{}=y", clean_flag);
+        let config_flag = format!("{}{}=", prefix, clean_flag);
+        let config_line = format!("{}{}={}", prefix, clean_flag, value);
         
         // Check if flag already exists
         let flag_exists = config_lines.iter().any(|line| line.starts_with(&config_flag));
+        let not_set = format!("# {}{} is not set", prefix, clean_flag);
         
         if flag_exists {
             println!("⚠️  {}: already exists", config_flag.yellow());
             already_exists_count += 1;
+            continue;
+        }
+
+        // Only one member of a choice can be selected, the one already selected has to be turned off first
+        let name = format!("CONFIG_{}", clean_flag);
+        let choice = match (prefix, value.as_str()) {
+            ("CONFIG_", "y") => kconfig.as_ref().and_then(|db| db.choice(&name)).or_else(|| kconfig::common_choice(&name)),
+            _ => None,
+        };
+        if let Some(choice) = choice {
+            let selected: Vec<&str> = choice
+                .members
+                .iter()
+                .map(String::as_str)
+                .filter(|member| *member != name && config_lines.contains(&format!("{}=y", member)))
+                .collect();
+            if !selected.is_empty() && !cli.set_args.replace_choice {
+                println!("⚠️  {}: conflicts with {}, selected in the choice '{}'", config_flag.yellow(), selected.join(", "), choice.prompt);
+                conflicting_count += 1;
+                continue;
+            }
+            for member in selected {
+                if let Some(line) = config_lines.iter_mut().find(|line| **line == format!("{}=y", member)) {
+                    *line = format!("# {} is not set", member);
+                }
+                println!("🔀 {}: replaced in the choice '{}'", member.yellow(), choice.prompt);
+            }
+        }
+
+        if let Some(line) = config_lines.iter_mut().find(|line| **line == not_set) {
+            // Turn the flag on where the config turned it off
+            *line = config_line;
+            println!("✅ {}: SET (was not set)", config_flag.green());
+            added.push((format!("{}{}", prefix, clean_flag), value));
         } else {
             // Add the flag to the config content at the end
             config_lines.push(config_line);
             println!("✅ {}: ADDED", config_flag.green());
-            added_count += 1;
+            added.push((format!("{}{}", prefix, clean_flag), value));
         }
     }
 
@@ -340,12 +2390,201 @@ fn set_kernel_config_flags(config_path: &str, flag_files: &[String], set_flags:
     fs::write(config_path, &updated_config)?;
 
     println!();
-    if added_count > 0 {
-        println!("✅ Successfully added {} flags to .config file!", added_count);
+    if !added.is_empty() {
+        println!("✅ Successfully added {} flags to .config file!", added.len());
     }
     if already_exists_count > 0 {
         println!("ℹ️  {} flags already existed and were not modified.", already_exists_count);
     }
+    if conflicting_count > 0 {
+        println!("⚠️  {} flags conflict with another option of their choice and were not added, pass --replace-choice to switch.", conflicting_count);
+    }
+
+    Ok(added)
+}
+
+/// Print the options the flags depend on that the config doesn't enable, and add them to the flags
+/// once confirmed, as setting an option whose dependencies aren't met has no effect
+fn add_prerequisites(db: &KconfigDb, config: &KernelConfig, flags: &mut Vec<String>, confirmed: bool) -> anyhow::Result<()> {
+    let requested: Vec<String> = flags.iter().map(|flag| FlagRequirement::new(flag).name).collect();
+    let mut enable: Vec<(String, String)> = Vec::new();
+    let mut unresolved: Vec<String> = Vec::new();
+    for name in &requested {
+        let prerequisites = db.prerequisites(config, name);
+        for (option, value) in prerequisites.enable {
+            if !requested.contains(&option) && !enable.iter().any(|(planned, _)| *planned == option) {
+                enable.push((option, value));
+            }
+        }
+        for reason in prerequisites.unresolved {
+            if !unresolved.contains(&reason) {
+                unresolved.push(reason);
+            }
+        }
+    }
+    if enable.is_empty() && unresolved.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    println!("🔗 The flags depend on {} options the config doesn't enable:", enable.len());
+    for (option, value) in &enable {
+        println!("   + {}={}", option.green(), value);
+    }
+    for reason in &unresolved {
+        println!("   ⚠️  {}, it isn't enabled automatically", reason.yellow());
+    }
+    if enable.is_empty() {
+        return Ok(());
+    }
+
+    if !confirmed {
+        if !io::stdin().is_terminal() {
+            println!("💡 Pass --yes to enable them without a terminal, adding only the requested flags");
+            return Ok(());
+        }
+        eprint!("❓ Enable these {} options too? [y/N] ", enable.len());
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            println!("ℹ️  Adding only the requested flags");
+            return Ok(());
+        }
+    }
+    flags.extend(enable.into_iter().map(|(option, value)| format!("{}={}", option, value)));
+    Ok(())
+}
+
+/// Walk through the flags a check found missing, disabled or set to another value, asking how to set each in the config,
+/// and write the answers once the changes they make were shown and confirmed
+fn fix_interactively(path: &str, results: &[report::CheckResult]) -> anyhow::Result<()> {
+    let unmet: Vec<&report::CheckResult> =
+        results.iter().filter(|result| matches!(result.status, ResultStatus::Missing | ResultStatus::Disabled | ResultStatus::WrongValue)).collect();
+    println!();
+    if unmet.is_empty() {
+        println!("ℹ️  None of the failures is a missing flag, nothing to fix in the config");
+        return Ok(());
+    }
+    let original = match fs::read(path) {
+        Ok(data) if Path::new(path).is_file() && compress::decompress(&data).is_none() => String::from_utf8_lossy(&data).into_owned(),
+        _ => {
+            println!("⚠️  {} isn't a plain config file that can be edited, fix a copy of it with `kcc set`", path);
+            return Ok(());
+        }
+    };
+
+    println!("🛠️  Fixing {} flags in {}: y built in, m module, n skip, q stop asking", unmet.len(), path);
+    let mut content = original.clone();
+    for result in unmet {
+        let required = result.required.as_deref().unwrap_or("y/m");
+        let accepted: Vec<&str> = required.split(['/', '|']).collect();
+        let tristates: Vec<&str> = accepted.iter().copied().filter(|value| matches!(*value, "y" | "m")).collect();
+        println!();
+        println!("   {} ({}, requires {})", result.name.red(), result.status, required);
+        if let Some(reason) = &result.reason {
+            println!("     💬 {}", reason);
+        }
+        let edit = match (tristates.as_slice(), accepted.as_slice()) {
+            ([], [value]) => match ask(&format!("Set {}={}? [y/n/q]", result.name, value))?.as_str() {
+                "y" => Some(Edit::SetVal(value.to_string())),
+                "q" => break,
+                _ => None,
+            },
+            ([], _) => {
+                println!("     ⚠️  Set it by hand, it accepts {}", required);
+                None
+            }
+            (tristates, _) => {
+                let default = tristates[0];
+                match ask(&format!("Set {} to {}/n/q? [{}]", result.name, tristates.join("/"), default))?.as_str() {
+                    "" if default == "y" => Some(Edit::Enable),
+                    "" => Some(Edit::Module),
+                    answer if answer == "y" && tristates.contains(&"y") => Some(Edit::Enable),
+                    answer if answer == "m" && tristates.contains(&"m") => Some(Edit::Module),
+                    "q" => break,
+                    _ => None,
+                }
+            }
+        };
+        if let Some(edit) = edit {
+            content = edit::apply(&content, &result.name, &edit);
+        }
+    }
+
+    let changes = diff::diff(&KernelConfig::parse(path, original.as_str()), &KernelConfig::parse(path, content.as_str()));
+    println!();
+    if changes.is_empty() {
+        println!("ℹ️  No changes to {}", path);
+        return Ok(());
+    }
+    println!("📝 Changes to {}:", path);
+    diff::print_changes(&changes, "   ");
+    if ask(&format!("Write them to {}? [y/N]", path))? != "y" {
+        println!("ℹ️  Left {} unchanged", path);
+        return Ok(());
+    }
+    fs::write(path, content).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?;
+    println!("{}", format!("✅ Updated {}", path).green().bold());
+    println!("💡 Run `make olddefconfig` in the kernel tree so the new flags' dependencies are resolved");
+    Ok(())
+}
+
+/// Ask a question on stderr and read the lower-cased answer; `q` at the end of the input
+fn ask(question: &str) -> anyhow::Result<String> {
+    eprint!("❓ {} ", question);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Ok("q".to_string());
+    }
+    Ok(answer.trim().to_lowercase())
+}
+
+/// Run `make olddefconfig` on a config that flags were just added to, so they stick or are
+/// dropped now rather than at the next build, and explain the ones that were dropped
+fn resolve_with_olddefconfig(
+    config_path: &str,
+    kernel_src: Option<&str>,
+    settings: &[(String, String)],
+    target_arch: Option<&str>,
+) -> anyhow::Result<()> {
+    let config_dir = Path::new(config_path).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let tree = match kernel_src {
+        Some(dir) => Path::new(dir),
+        None if config_dir.join("Kconfig").is_file() && config_dir.join("Makefile").is_file() => config_dir,
+        None => return Err(anyhow::anyhow!("{} isn't inside a kernel source tree, pass it with --kernel-src", config_path)),
+    };
+    if settings.iter().any(|(name, _)| name.starts_with("CONFIG_KERNEL_")) && openwrt::is_openwrt_config(&read_kernel_config(config_path)?) {
+        println!("⚠️  Not running make olddefconfig on an OpenWrt config, the OpenWrt build resolves it");
+        return Ok(());
+    }
+
+    println!();
+    println!("🔄 Running make olddefconfig in {}", tree.display());
+    kconfig::olddefconfig(tree, Path::new(config_path), target_arch)?;
+
+    let resolved = KernelConfig::load(config_path)?;
+    let dropped: Vec<&(String, String)> = settings
+        .iter()
+        .filter(|(name, value)| resolved.get(name) != Some(&ConfigValue::parse(value)))
+        .collect();
+    if dropped.is_empty() {
+        println!("✅ All {} added flags survived make olddefconfig", settings.len());
+        return Ok(());
+    }
 
+    println!("⚠️  make olddefconfig dropped {} of the added flags:", dropped.len());
+    let db = KconfigDb::from_source(tree).ok();
+    for (name, value) in dropped {
+        match resolved.get(name).filter(|current| **current != ConfigValue::NotSet) {
+            Some(current) => println!("   - {}={} (now {})", name.red(), value, current),
+            None => println!("   - {}={} (now not set)", name.red(), value),
+        }
+        for unmet in db.iter().flat_map(|db| db.dependency_chain(&resolved, name)) {
+            let name = if unmet.name == *name { "requires" } else { &format!("{} requires", unmet.name) };
+            println!("     🔗 {} {}", name, unmet.needs.join(" and "));
+        }
+    }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,103 @@
+//! Check several kernel configs against several specs at once and report
+//! which kernels satisfy which profile.
+
+use colored::*;
+use serde::Serialize;
+
+use crate::{Checker, FlagRequirement, FlagStatus, KernelConfig};
+
+/// A flags file, profile or --set-flags list checked as one column of the matrix
+pub struct Spec {
+    pub name: String,
+    pub flags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Cell {
+    config: String,
+    spec: String,
+    satisfied: bool,
+    /// Flags and rules the config doesn't satisfy
+    missing: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    configs: Vec<&'a str>,
+    specs: Vec<&'a str>,
+    results: Vec<Cell>,
+}
+
+/// Check every config against every spec and print the matrix; returns the exit code
+pub fn run(configs: &[(String, String)], specs: &[Spec], json: bool) -> i32 {
+    let mut results = Vec::new();
+    for (source, content) in configs {
+        let kernel_config = KernelConfig::parse(source, content.as_str());
+        for spec in specs {
+            // Configs from other kernels aren't validated against the running kernel's symbols
+            // Requirements for other architectures than the config's are left out
+            let requirements: Vec<FlagRequirement> = spec.flags.iter().map(|flag| FlagRequirement::new(flag)).collect();
+            let missing: Vec<String> = Checker::new()
+                .check(&kernel_config, &requirements)
+                .into_iter()
+                .map(|(_, result)| result)
+                .filter(|result| {
+                    matches!(
+                        result.status,
+                        FlagStatus::Missing | FlagStatus::Disabled | FlagStatus::WrongValue | FlagStatus::RuleFailed | FlagStatus::Forbidden
+                    )
+                })
+                .map(|result| result.name)
+                .collect();
+            results.push(Cell { config: source.clone(), spec: spec.name.clone(), satisfied: missing.is_empty(), missing });
+        }
+    }
+    let code = if results.iter().all(|cell| cell.satisfied) { 0 } else { 1 };
+
+    if json {
+        let report = Report {
+            configs: configs.iter().map(|(source, _)| source.as_str()).collect(),
+            specs: specs.iter().map(|spec| spec.name.as_str()).collect(),
+            results,
+        };
+        println!("{}", serde_json::to_string_pretty(&report).expect("matrix report serializes"));
+        return code;
+    }
+
+    println!("🧮 Checking {} config(s) against {} spec(s)", configs.len(), specs.len());
+    println!();
+
+    let config_width = configs.iter().map(|(source, _)| source.len()).max().unwrap_or(0).max("config".len());
+    let widths: Vec<usize> = specs.iter().map(|spec| spec.name.len().max(8)).collect();
+
+    let mut header = format!("{:<width$}", "config", width = config_width);
+    for (spec, width) in specs.iter().zip(&widths) {
+        header.push_str(&format!("  {:<width$}", spec.name, width = width));
+    }
+    println!("{}", header.bold());
+
+    for (row, (source, _)) in results.chunks(specs.len().max(1)).zip(configs) {
+        let mut line = format!("{:<width$}", source, width = config_width);
+        for (cell, width) in row.iter().zip(&widths) {
+            // Pad before coloring, escape codes would throw the alignment off
+            let text = if cell.satisfied { "✅".to_string() } else { format!("❌ {}", cell.missing.len()) };
+            let padded = format!("{:<width$}", text, width = width.saturating_sub(1));
+            line.push_str("  ");
+            line.push_str(&if cell.satisfied { padded.green() } else { padded.red() }.to_string());
+        }
+        println!("{}", line);
+    }
+
+    let failed: Vec<&Cell> = results.iter().filter(|cell| !cell.satisfied).collect();
+    println!();
+    if failed.is_empty() {
+        println!("{}", "✅ Every config satisfies every spec!".green().bold());
+    } else {
+        println!("{}", format!("❌ {} of {} combination(s) not satisfied:", failed.len(), results.len()).red().bold());
+        for cell in failed {
+            println!("   {} × {}: {}", cell.config, cell.spec, cell.missing.join(", "));
+        }
+    }
+
+    code
+}
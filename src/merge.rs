@@ -0,0 +1,88 @@
+//! Merging config fragments into a base config, as the kernel's
+//! `scripts/kconfig/merge_config.sh -m` does.
+//!
+//! Fragments are applied in order and later ones win: an option a fragment sets
+//! replaces the earlier line, which moves to the end of the config, and setting an
+//! option to another value than an earlier file did is reported as a redefinition.
+//!
+//! ```no_run
+//! let base = std::fs::read_to_string("arch/arm64/configs/defconfig")?;
+//! let fragment = std::fs::read_to_string("kvm.cfg")?;
+//! let merged = kcc::merge::merge(&base, &[("kvm.cfg".to_string(), fragment)]);
+//! for warning in &merged.warnings {
+//!     eprintln!("{}", warning);
+//! }
+//! std::fs::write(".config", merged.config)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fmt;
+
+/// Something a fragment did that merge_config.sh warns about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// The fragment set an option to another value than an earlier file
+    Redefined { option: String, fragment: String, previous: String, new: String },
+    /// The fragment set an option to the value it already had
+    Redundant { option: String, fragment: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::Redefined { option, fragment, previous, new } => {
+                write!(f, "{} is redefined by {}: {} → {}", option, fragment, previous, new)
+            }
+            Warning::Redundant { option, fragment } => write!(f, "{} is redundant in {}", option, fragment),
+        }
+    }
+}
+
+/// A merged config and the warnings of merging it
+#[derive(Debug, Clone, Default)]
+pub struct Merged {
+    pub config: String,
+    pub warnings: Vec<Warning>,
+}
+
+/// The option a config line assigns or unsets
+fn option(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let name = match line.strip_prefix("# ").and_then(|rest| rest.strip_suffix(" is not set")) {
+        Some(name) => name,
+        None => line.split_once('=').filter(|_| !line.starts_with('#'))?.0,
+    };
+    name.starts_with("CONFIG_").then_some(name)
+}
+
+/// Merge fragments, given as their names and text, into the text of a base config
+pub fn merge(base: &str, fragments: &[(String, String)]) -> Merged {
+    let mut lines: Vec<String> = base.lines().map(str::to_string).collect();
+    let mut warnings = Vec::new();
+    for (fragment, content) in fragments {
+        for line in content.lines().map(str::trim) {
+            let Some(name) = option(line) else { continue };
+            if let Some(position) = lines.iter().rposition(|existing| option(existing) == Some(name)) {
+                let previous = lines.remove(position);
+                warnings.push(if previous.trim() == line {
+                    Warning::Redundant { option: name.to_string(), fragment: fragment.clone() }
+                } else {
+                    Warning::Redefined {
+                        option: name.to_string(),
+                        fragment: fragment.clone(),
+                        previous: previous.trim().to_string(),
+                        new: line.to_string(),
+                    }
+                });
+                lines.retain(|existing| option(existing) != Some(name));
+            }
+            lines.push(line.to_string());
+        }
+    }
+
+    let mut config = lines.join("\n");
+    if !config.is_empty() {
+        config.push('\n');
+    }
+    Merged { config, warnings }
+}
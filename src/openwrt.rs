@@ -0,0 +1,35 @@
+/// Prefix OpenWrt uses for symbols that are passed through to the kernel config
+const WRAPPER_PREFIX: &str = "CONFIG_KERNEL_";
+
+/// Returns true if the content is an OpenWrt top-level .config rather than a kernel config
+pub fn is_openwrt_config(content: &str) -> bool {
+    content.lines().any(|line| line.starts_with("CONFIG_TARGET_BOARD="))
+}
+
+/// Add the underlying kernel symbol for every `CONFIG_KERNEL_*` wrapper,
+/// e.g. `CONFIG_KERNEL_CGROUPS=y` also yields `CONFIG_CGROUPS=y`
+pub fn map_kernel_symbols(content: &str) -> String {
+    let mut mapped = String::with_capacity(content.len());
+
+    for line in content.lines() {
+        mapped.push_str(line);
+        mapped.push('\n');
+
+        if let Some(rest) = line.strip_prefix(WRAPPER_PREFIX) {
+            mapped.push_str("CONFIG_");
+            mapped.push_str(rest);
+            mapped.push('\n');
+        } else if let Some(rest) = line.strip_prefix("# ").and_then(|l| l.strip_prefix(WRAPPER_PREFIX)) {
+            mapped.push_str("# CONFIG_");
+            mapped.push_str(rest);
+            mapped.push('\n');
+        }
+    }
+
+    mapped
+}
+
+/// OpenWrt wrapper symbol for a kernel flag (CONFIG_CGROUPS -> CONFIG_KERNEL_CGROUPS)
+pub fn wrapper_symbol(flag: &str) -> String {
+    format!("{}{}", WRAPPER_PREFIX, flag.strip_prefix("CONFIG_").unwrap_or(flag))
+}
@@ -0,0 +1,113 @@
+//! Selecting config options by name and state, for the subcommands that list or
+//! capture parts of a config.
+//!
+//! A pattern with `*` or `?` is a shell-style glob that must match the whole name,
+//! anything else is a prefix. Both are matched against the name without its
+//! `CONFIG_` prefix and ignore case, so `nf_*`, `NF_` and `CONFIG_NF_*` select the
+//! same options. Regular expressions are searched for in the name without its
+//! `CONFIG_` prefix too, and are case-sensitive unless they start with `(?i)`.
+
+use regex::Regex;
+
+use crate::{ConfigValue, Tristate};
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Prefix,
+    Glob,
+    Regex(Regex),
+}
+
+/// A prefix, glob or regular expression selecting config options
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    text: String,
+    kind: Kind,
+}
+
+impl Pattern {
+    /// A prefix, or a glob when it contains `*` or `?`
+    pub fn new(pattern: &str) -> Self {
+        let pattern = pattern.trim().to_uppercase();
+        let pattern = pattern.strip_prefix("CONFIG_").unwrap_or(&pattern);
+        let kind = if pattern.contains(['*', '?']) { Kind::Glob } else { Kind::Prefix };
+        Pattern { text: pattern.to_string(), kind }
+    }
+
+    /// A regular expression
+    pub fn regex(pattern: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(pattern).map_err(|e| anyhow::anyhow!("Invalid regular expression {}: {}", pattern, e))?;
+        Ok(Pattern { text: pattern.to_string(), kind: Kind::Regex(regex) })
+    }
+
+    /// Whether an option, with or without its `CONFIG_` prefix, is selected
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+        match &self.kind {
+            Kind::Prefix => name.to_uppercase().starts_with(&self.text),
+            Kind::Glob => glob(self.text.as_bytes(), name.to_uppercase().as_bytes()),
+            Kind::Regex(regex) => regex.is_match(name),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Whether an option is selected by any of the patterns; every option is when there are none
+pub fn matches_any(patterns: &[Pattern], name: &str) -> bool {
+    patterns.is_empty() || patterns.iter().any(|pattern| pattern.matches(name))
+}
+
+/// Match a glob against a whole name, backtracking to the last `*` on a mismatch
+pub(crate) fn glob(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// The state of an option, as selected by `kcc list --state`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum State {
+    /// Built in
+    Y,
+    /// A module
+    M,
+    /// Not set
+    N,
+    /// A number or string
+    Value,
+}
+
+impl State {
+    pub fn matches(&self, value: &ConfigValue) -> bool {
+        matches!(
+            (self, value.tristate()),
+            (State::Y, Some(Tristate::Yes))
+                | (State::M, Some(Tristate::Module))
+                | (State::N, Some(Tristate::No))
+                | (State::Value, None)
+        )
+    }
+}
@@ -0,0 +1,117 @@
+//! Dynamically loaded check plugins.
+//!
+//! A plugin is a shared library exporting the following C functions:
+//!
+//! ```c
+//! unsigned int kcc_plugin_api_version(void);            /* must return 1 */
+//! char *kcc_plugin_check(const char *config, const char *facts);
+//! void kcc_plugin_free(char *results);
+//! ```
+//!
+//! `config` is the kernel config text and `facts` holds `key=value` lines
+//! describing the environment (config source, architectures, kernel release).
+//! The returned string contains one result per line in the form
+//! `pass|fail|warn<TAB>name<TAB>message` and is released with `kcc_plugin_free`.
+
+use std::ffi::{c_char, c_uint, CStr, CString};
+use std::path::Path;
+
+use libloading::{Library, Symbol};
+
+pub const API_VERSION: c_uint = 1;
+
+type ApiVersionFn = unsafe extern "C" fn() -> c_uint;
+type CheckFn = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+#[derive(Debug, PartialEq)]
+pub enum PluginStatus {
+    Pass,
+    Fail,
+    Warn,
+}
+
+/// A single result contributed to the report by a plugin
+pub struct PluginResult {
+    pub plugin: String,
+    pub status: PluginStatus,
+    pub name: String,
+    pub message: String,
+}
+
+/// Load a plugin and run its checks against the config and environment facts
+pub fn run(path: &str, config: &str, facts: &[(&str, String)]) -> anyhow::Result<Vec<PluginResult>> {
+    let plugin_name = Path::new(path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().trim_start_matches("lib").to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    let config = CString::new(config).map_err(|_| anyhow::anyhow!("Config contains a NUL byte"))?;
+    let facts = facts
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", key, value))
+        .collect::<String>();
+    let facts = CString::new(facts).map_err(|_| anyhow::anyhow!("Environment facts contain a NUL byte"))?;
+
+    // SAFETY: loading a plugin runs its initializers; plugins are trusted code
+    // explicitly requested by the user with --plugin.
+    let library = unsafe { Library::new(path) }
+        .map_err(|e| anyhow::anyhow!("Failed to load plugin {}: {}", path, e))?;
+
+    // SAFETY: the symbol types match the documented plugin ABI above.
+    let output = unsafe {
+        let api_version: Symbol<ApiVersionFn> = library
+            .get(b"kcc_plugin_api_version")
+            .map_err(|e| anyhow::anyhow!("Plugin {} is missing kcc_plugin_api_version: {}", path, e))?;
+        let check: Symbol<CheckFn> = library
+            .get(b"kcc_plugin_check")
+            .map_err(|e| anyhow::anyhow!("Plugin {} is missing kcc_plugin_check: {}", path, e))?;
+        let free: Symbol<FreeFn> = library
+            .get(b"kcc_plugin_free")
+            .map_err(|e| anyhow::anyhow!("Plugin {} is missing kcc_plugin_free: {}", path, e))?;
+
+        let version = api_version();
+        if version != API_VERSION {
+            return Err(anyhow::anyhow!(
+                "Plugin {} uses API version {}, kcc supports {}",
+                path,
+                version,
+                API_VERSION
+            ));
+        }
+
+        let results = check(config.as_ptr(), facts.as_ptr());
+        if results.is_null() {
+            return Err(anyhow::anyhow!("Plugin {} returned no results", path));
+        }
+        let output = CStr::from_ptr(results).to_string_lossy().into_owned();
+        free(results);
+        output
+    };
+
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| parse_result(&plugin_name, line))
+        .collect()
+}
+
+fn parse_result(plugin: &str, line: &str) -> anyhow::Result<PluginResult> {
+    let mut fields = line.splitn(3, '\t');
+    let status = match fields.next().unwrap_or_default() {
+        "pass" => PluginStatus::Pass,
+        "fail" => PluginStatus::Fail,
+        "warn" => PluginStatus::Warn,
+        other => return Err(anyhow::anyhow!("Plugin {} returned unknown status: {}", plugin, other)),
+    };
+    let name = fields
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Plugin {} returned a result without a name: {}", plugin, line))?;
+
+    Ok(PluginResult {
+        plugin: plugin.to_string(),
+        status,
+        name: name.to_string(),
+        message: fields.next().unwrap_or_default().to_string(),
+    })
+}
@@ -0,0 +1,169 @@
+//! Policy-as-code evaluation.
+//!
+//! The parsed config and environment are exposed to policies as a document:
+//!
+//! ```json
+//! {
+//!   "config": { "CONFIG_BPF": "y", "CONFIG_HZ": "1000", "CONFIG_KPROBES": "n", ... },
+//!   "kernel_version": "6.8.0",
+//!   "arch": "x86_64",
+//!   "cmdline": "BOOT_IMAGE=... quiet",
+//!   "facts": { "config_source": "/proc/config.gz", "host_arch": "x86_64", ... }
+//! }
+//! ```
+//!
+//! CEL policies (`.cel`) are a single boolean expression over these variables.
+//! Rego policies (`.rego`) are evaluated with the document as `input`; every
+//! message in the package's `deny` set is a failure and every message in its
+//! `warn` set a warning.
+
+use std::fs;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+use crate::config::{ConfigValue, KernelConfig};
+use crate::{arch, expr};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(any(feature = "cel", feature = "rego")), allow(dead_code))]
+pub enum PolicyStatus {
+    Pass,
+    Fail,
+    Warn,
+}
+
+/// A single result produced by evaluating a policy
+pub struct PolicyResult {
+    pub policy: String,
+    pub status: PolicyStatus,
+    pub message: String,
+}
+
+/// Build the document policies are evaluated against
+pub fn document(kernel_config: &KernelConfig, cmdline: Option<&str>, facts: &[(&str, String)]) -> Value {
+    let mut config = Map::new();
+    for (symbol, value) in kernel_config.iter() {
+        let value = match value {
+            ConfigValue::String(string) => string.clone(),
+            ConfigValue::NotSet => "n".to_string(),
+            value => value.to_string(),
+        };
+        config.insert(symbol.to_string(), Value::String(value));
+    }
+
+    let facts: Map<String, Value> = facts
+        .iter()
+        .map(|(key, value)| (key.to_string(), Value::String(value.clone())))
+        .collect();
+
+    json!({
+        "config": config,
+        "kernel_version": expr::kernel_version(kernel_config.text()),
+        "arch": arch::detect_config_arch(kernel_config),
+        "cmdline": cmdline,
+        "facts": facts,
+    })
+}
+
+/// Evaluate a CEL or Rego policy file, chosen by its extension
+pub fn evaluate(path: &str, document: &Value) -> anyhow::Result<Vec<PolicyResult>> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read policy file {}: {}", path, e))?;
+    let name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("cel") => evaluate_cel(&name, &source, document),
+        Some("rego") => evaluate_rego(&name, &source, document),
+        _ => Err(anyhow::anyhow!("Unknown policy language for {} (expected .cel or .rego)", path)),
+    }
+}
+
+#[cfg(feature = "cel")]
+fn evaluate_cel(name: &str, source: &str, document: &Value) -> anyhow::Result<Vec<PolicyResult>> {
+    use cel_interpreter::{to_value, Context, Program};
+
+    let program = Program::compile(source).map_err(|e| anyhow::anyhow!("Invalid CEL policy {}: {}", name, e))?;
+
+    let mut context = Context::default();
+    if let Value::Object(fields) = document {
+        for (key, value) in fields {
+            let value = to_value(value).map_err(|e| anyhow::anyhow!("Failed to convert {}: {}", key, e))?;
+            context.add_variable_from_value(key.as_str(), value);
+        }
+    }
+
+    let status = match program.execute(&context) {
+        Ok(cel_interpreter::Value::Bool(true)) => PolicyStatus::Pass,
+        Ok(cel_interpreter::Value::Bool(false)) => PolicyStatus::Fail,
+        Ok(other) => return Err(anyhow::anyhow!("CEL policy {} returned {:?} instead of a boolean", name, other)),
+        Err(e) => return Err(anyhow::anyhow!("Failed to evaluate CEL policy {}: {}", name, e)),
+    };
+
+    Ok(vec![PolicyResult {
+        policy: name.to_string(),
+        status,
+        message: String::new(),
+    }])
+}
+
+#[cfg(not(feature = "cel"))]
+fn evaluate_cel(name: &str, _source: &str, _document: &Value) -> anyhow::Result<Vec<PolicyResult>> {
+    Err(anyhow::anyhow!("Can't evaluate {}: kcc was built without the `cel` feature", name))
+}
+
+#[cfg(feature = "rego")]
+fn evaluate_rego(name: &str, source: &str, document: &Value) -> anyhow::Result<Vec<PolicyResult>> {
+    let mut engine = regorus::Engine::new();
+    let package = engine
+        .add_policy(name.to_string(), source.to_string())
+        .map_err(|e| anyhow::anyhow!("Invalid Rego policy {}: {}", name, e))?;
+    engine
+        .set_input_json(&document.to_string())
+        .map_err(|e| anyhow::anyhow!("Failed to set Rego input: {}", e))?;
+
+    let mut results = Vec::new();
+    for (rule, status) in [("deny", PolicyStatus::Fail), ("warn", PolicyStatus::Warn)] {
+        let value = engine
+            .eval_rule(format!("{}.{}", package, rule))
+            .map_err(|e| anyhow::anyhow!("Failed to evaluate {}.{} in {}: {}", package, rule, name, e))?;
+        if value == regorus::Value::Undefined {
+            continue;
+        }
+
+        let value: Value = serde_json::from_str(&value.to_json_str()?)?;
+        let messages = match value {
+            Value::Array(messages) => messages,
+            Value::Bool(false) => Vec::new(),
+            other => vec![other],
+        };
+        for message in messages {
+            results.push(PolicyResult {
+                policy: name.to_string(),
+                status,
+                message: match message {
+                    Value::String(text) => text,
+                    other => other.to_string(),
+                },
+            });
+        }
+    }
+
+    if results.is_empty() {
+        results.push(PolicyResult {
+            policy: name.to_string(),
+            status: PolicyStatus::Pass,
+            message: String::new(),
+        });
+    }
+
+    Ok(results)
+}
+
+#[cfg(not(feature = "rego"))]
+fn evaluate_rego(name: &str, _source: &str, _document: &Value) -> anyhow::Result<Vec<PolicyResult>> {
+    Err(anyhow::anyhow!("Can't evaluate {}: kcc was built without the `rego` feature", name))
+}
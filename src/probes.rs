@@ -0,0 +1,879 @@
+//! Runtime probes of the running kernel.
+//!
+//! Some requirements depend on more than the config: sysctls, LSM policy or
+//! features that were built but are unusable. Profiles can carry probes that
+//! are run when checking the running kernel, next to the flag checks.
+
+use std::fs;
+use std::path::Path;
+
+use crate::backend::{CheckSource, Cmdline, Modules, SourceKind, Sysctl, Sysfs};
+use crate::check::FlagStatus;
+use crate::config::{ConfigValue, KernelConfig};
+use crate::requirement::{Checker, FlagRequirement};
+
+/// A single check against the running system; an error explains why it failed
+pub struct Probe {
+    pub name: &'static str,
+    pub run: fn() -> Result<(), String>,
+    /// Compliance control the probe verifies, reported with its result
+    pub control: Option<&'static str>,
+    /// What makes the probe pass, for the remediation plan
+    pub fix: Option<Fix>,
+}
+
+/// A change to the running system that resolves a failed check
+#[derive(Clone, Copy)]
+pub enum Fix {
+    /// Set a sysctl, e.g. `kernel.kptr_restrict=1`
+    Sysctl(&'static str, &'static str),
+    /// Add a parameter to the kernel command line; takes effect on the next boot
+    BootParam(&'static str),
+    /// Load a module
+    Module(&'static str),
+}
+
+/// What `criu check` verifies beyond the kernel config
+pub const CRIU: &[Probe] = &[
+    Probe { name: "ns_last_pid sysctl", run: criu_ns_last_pid, control: None, fix: None },
+    Probe { name: "/proc/<pid>/map_files", run: criu_map_files, control: None, fix: None },
+    Probe { name: "soft-dirty memory tracking", run: criu_soft_dirty, control: None, fix: None },
+    Probe { name: "userfaultfd for lazy restore", run: criu_userfaultfd, control: None, fix: None },
+    Probe { name: "namespaces exposed in /proc/<pid>/ns", run: criu_namespaces, control: None, fix: None },
+    Probe { name: "mnt_id in /proc/<pid>/fdinfo", run: criu_fdinfo_mnt_id, control: None, fix: None },
+    Probe { name: "ptrace not disabled by Yama", run: criu_ptrace_scope, control: None, fix: None },
+];
+
+/// Confirms the running kernel can accept live patches
+pub const LIVEPATCH: &[Probe] = &[
+    Probe { name: "/sys/kernel/livepatch exists", run: livepatch_sysfs, control: None, fix: None },
+    Probe { name: "ftrace enabled", run: livepatch_ftrace_enabled, control: None, fix: Some(Fix::Sysctl("kernel.ftrace_enabled", "1")) },
+];
+
+/// Confirms the running system can actually suspend and hibernate
+pub const POWER: &[Probe] = &[
+    Probe { name: "suspend to RAM offered in /sys/power/state", run: power_suspend, control: None, fix: None },
+    Probe { name: "hibernation offered in /sys/power/state", run: power_hibernate, control: None, fix: None },
+    Probe { name: "swap at least as large as RAM for hibernation", run: power_swap_size, control: None, fix: None },
+    Probe { name: "resume device configured", run: power_resume_device, control: None, fix: None },
+];
+
+/// Confirms the running kernel really is a PREEMPT_RT build
+pub const REALTIME: &[Probe] = &[
+    Probe { name: "/sys/kernel/realtime reports 1", run: realtime_kernel, control: None, fix: None },
+];
+
+/// Confirms the running kernel is a Xen control domain
+pub const XEN_DOM0: &[Probe] = &[
+    Probe { name: "running on Xen", run: xen_hypervisor, control: None, fix: None },
+    Probe { name: "control domain capabilities", run: xen_control_domain, control: None, fix: None },
+];
+
+/// Confirms the running kernel is a Xen guest
+pub const XEN_DOMU: &[Probe] = &[
+    Probe { name: "running on Xen", run: xen_hypervisor, control: None, fix: None },
+];
+
+/// Confirms modules can be built against the running kernel
+pub const DKMS: &[Probe] = &[
+    Probe { name: "headers installed for the running kernel", run: dkms_headers, control: None, fix: None },
+];
+
+/// Reasons an out-of-tree GPU driver would be refused at load time
+pub const GPU: &[Probe] = &[
+    Probe { name: "module signatures not enforced, or the driver is signed", run: gpu_sig_enforce, control: None, fix: None },
+    Probe { name: "kernel lockdown allows unsigned modules", run: gpu_lockdown, control: None, fix: None },
+    Probe { name: "nouveau not holding the GPU", run: gpu_nouveau, control: None, fix: Some(Fix::BootParam("modprobe.blacklist=nouveau")) },
+];
+
+/// Cross-checks module signing in the config with what the running kernel enforces
+pub const MODULE_SIGNING: &[Probe] = &[
+    Probe { name: "unsigned modules are rejected at runtime", run: signing_enforced, control: None, fix: Some(Fix::BootParam("module.sig_enforce=1")) },
+    Probe { name: "config and runtime enforcement agree", run: signing_config_matches_runtime, control: None, fix: None },
+    Probe { name: "lockdown can verify module signatures", run: signing_lockdown_consistent, control: None, fix: None },
+];
+
+/// Confirms the storage modules are built in or can be loaded
+pub const STORAGE: &[Probe] = &[
+    Probe { name: "nfs module loadable", run: storage_nfs, control: None, fix: Some(Fix::Module("nfs")) },
+    Probe { name: "nfsv4 module loadable", run: storage_nfsv4, control: None, fix: Some(Fix::Module("nfsv4")) },
+    Probe { name: "nfsd module loadable", run: storage_nfsd, control: None, fix: Some(Fix::Module("nfsd")) },
+    Probe { name: "iscsi_tcp module loadable", run: storage_iscsi_tcp, control: None, fix: Some(Fix::Module("iscsi_tcp")) },
+    Probe { name: "dm-multipath module loadable", run: storage_dm_multipath, control: None, fix: Some(Fix::Module("dm_multipath")) },
+    Probe { name: "dm-crypt module loadable", run: storage_dm_crypt, control: None, fix: Some(Fix::Module("dm_crypt")) },
+];
+
+/// Confirms cgroup v2 is actually mounted and its controllers are usable
+pub const CGROUP_V2: &[Probe] = &[
+    Probe { name: "unified hierarchy mounted at /sys/fs/cgroup", run: cgroup_unified_mounted, control: None, fix: Some(Fix::BootParam("systemd.unified_cgroup_hierarchy=1")) },
+    Probe { name: "cpu, memory, io, pids and cpuset in cgroup.controllers", run: cgroup_controllers, control: None, fix: None },
+];
+
+/// Confirms IMA is active, has a policy and a TPM to extend measurements into
+pub const IMA: &[Probe] = &[
+    Probe { name: "/sys/kernel/security/ima exists", run: ima_securityfs, control: None, fix: None },
+    Probe { name: "ima_policy= or a custom policy loaded", run: ima_policy, control: None, fix: Some(Fix::BootParam("ima_policy=tcb")) },
+    Probe { name: "TPM present for measurements", run: ima_tpm, control: None, fix: None },
+];
+
+/// Asks the running kernel whether the sandboxing primitives really work
+pub const SANDBOX: &[Probe] = &[
+    Probe { name: "Landlock enabled (landlock_create_ruleset)", run: sandbox_landlock, control: None, fix: None },
+    Probe { name: "seccomp available (PR_GET_SECCOMP)", run: sandbox_seccomp, control: None, fix: None },
+    Probe { name: "seccomp filter mode available", run: sandbox_seccomp_filter, control: None, fix: None },
+];
+
+/// Sysctl and boot parameter controls of the CIS benchmark
+pub const CIS: &[Probe] = &[
+    Probe { name: "kernel.randomize_va_space is 2", run: aslr_full, control: Some("CIS 1.5.1"), fix: Some(Fix::Sysctl("kernel.randomize_va_space", "2")) },
+    Probe { name: "fs.suid_dumpable is 0", run: cis_suid_dumpable, control: Some("CIS 1.5.4"), fix: Some(Fix::Sysctl("fs.suid_dumpable", "0")) },
+    Probe { name: "AppArmor is an active LSM", run: cis_apparmor_active, control: Some("CIS 1.6.1.2"), fix: None },
+    Probe { name: "net.ipv4.conf.all.accept_source_route is 0", run: cis_source_route, control: Some("CIS 3.3.1"), fix: Some(Fix::Sysctl("net.ipv4.conf.all.accept_source_route", "0")) },
+    Probe { name: "net.ipv4.conf.all.accept_redirects is 0", run: cis_icmp_redirects, control: Some("CIS 3.3.2"), fix: Some(Fix::Sysctl("net.ipv4.conf.all.accept_redirects", "0")) },
+    Probe { name: "net.ipv4.conf.all.rp_filter is 1", run: cis_rp_filter, control: Some("CIS 3.3.7"), fix: Some(Fix::Sysctl("net.ipv4.conf.all.rp_filter", "1")) },
+    Probe { name: "net.ipv4.tcp_syncookies is 1", run: cis_syncookies, control: Some("CIS 3.3.8"), fix: Some(Fix::Sysctl("net.ipv4.tcp_syncookies", "1")) },
+    Probe { name: "audit=1 on the kernel command line", run: audit_at_boot, control: Some("CIS 4.1.1.3"), fix: Some(Fix::BootParam("audit=1")) },
+    Probe { name: "audit_backlog_limit is at least 8192", run: audit_backlog_limit, control: Some("CIS 4.1.1.4"), fix: Some(Fix::BootParam("audit_backlog_limit=8192")) },
+];
+
+/// Sysctl and boot parameter rules of the DISA RHEL 8 STIG
+pub const STIG: &[Probe] = &[
+    Probe { name: "crypto.fips_enabled is 1", run: stig_fips, control: Some("V-230223"), fix: Some(Fix::BootParam("fips=1")) },
+    Probe { name: "kernel.kexec_load_disabled is 1", run: stig_kexec_load_disabled, control: Some("V-230266"), fix: Some(Fix::Sysctl("kernel.kexec_load_disabled", "1")) },
+    Probe { name: "fs.protected_symlinks is 1", run: stig_protected_symlinks, control: Some("V-230267"), fix: Some(Fix::Sysctl("fs.protected_symlinks", "1")) },
+    Probe { name: "fs.protected_hardlinks is 1", run: stig_protected_hardlinks, control: Some("V-230268"), fix: Some(Fix::Sysctl("fs.protected_hardlinks", "1")) },
+    Probe { name: "kernel.dmesg_restrict is 1", run: stig_dmesg_restrict, control: Some("V-230269"), fix: Some(Fix::Sysctl("kernel.dmesg_restrict", "1")) },
+    Probe { name: "kernel.perf_event_paranoid is 2", run: stig_perf_event_paranoid, control: Some("V-230270"), fix: Some(Fix::Sysctl("kernel.perf_event_paranoid", "2")) },
+    Probe { name: "page_poison=1 on the kernel command line", run: stig_page_poison, control: Some("V-230277"), fix: Some(Fix::BootParam("page_poison=1")) },
+    Probe { name: "vsyscall=none on the kernel command line", run: stig_vsyscall, control: Some("V-230278"), fix: Some(Fix::BootParam("vsyscall=none")) },
+    Probe { name: "slub_debug=P on the kernel command line", run: stig_slub_debug, control: Some("V-230279"), fix: Some(Fix::BootParam("slub_debug=P")) },
+    Probe { name: "kernel.randomize_va_space is 2", run: aslr_full, control: Some("V-230280"), fix: Some(Fix::Sysctl("kernel.randomize_va_space", "2")) },
+    Probe { name: "kernel.core_pattern is |/bin/false", run: stig_core_pattern, control: Some("V-230311"), fix: Some(Fix::Sysctl("kernel.core_pattern", "|/bin/false")) },
+    Probe { name: "audit=1 on the kernel command line", run: audit_at_boot, control: Some("V-230468"), fix: Some(Fix::BootParam("audit=1")) },
+    Probe { name: "audit_backlog_limit is at least 8192", run: audit_backlog_limit, control: Some("V-230469"), fix: Some(Fix::BootParam("audit_backlog_limit=8192")) },
+    Probe { name: "pti=on on the kernel command line", run: stig_pti, control: Some("V-230491"), fix: Some(Fix::BootParam("pti=on")) },
+    Probe { name: "kernel.unprivileged_bpf_disabled is 1", run: stig_unprivileged_bpf, control: Some("V-230545"), fix: Some(Fix::Sysctl("kernel.unprivileged_bpf_disabled", "1")) },
+    Probe { name: "kernel.yama.ptrace_scope is 1", run: stig_ptrace_scope, control: Some("V-230546"), fix: Some(Fix::Sysctl("kernel.yama.ptrace_scope", "1")) },
+    Probe { name: "kernel.kptr_restrict is 1", run: stig_kptr_restrict, control: Some("V-230547"), fix: Some(Fix::Sysctl("kernel.kptr_restrict", "1")) },
+    Probe { name: "user.max_user_namespaces is 0", run: stig_user_namespaces, control: Some("V-230548"), fix: Some(Fix::Sysctl("user.max_user_namespaces", "0")) },
+];
+
+/// Why a feature the config enables is switched off on the running system, if it is
+pub fn administratively_disabled(flag: &str) -> Option<String> {
+    match flag {
+        "CONFIG_IO_URING" => match Sysctl::new().get("kernel.io_uring_disabled")?.trim() {
+            "1" => Some("kernel.io_uring_disabled is 1, only root and kernel.io_uring_group may create rings".to_string()),
+            "2" => Some("kernel.io_uring_disabled is 2, io_uring is disabled for everyone".to_string()),
+            _ => None,
+        },
+        "CONFIG_USER_NS" => match Sysctl::new().get("user.max_user_namespaces")?.trim() {
+            "0" => Some("user.max_user_namespaces is 0".to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// How to switch an administratively disabled feature back on
+pub fn administrative_fix(flag: &str) -> Option<Fix> {
+    match flag {
+        "CONFIG_IO_URING" => Some(Fix::Sysctl("kernel.io_uring_disabled", "0")),
+        "CONFIG_USER_NS" => Some(Fix::Sysctl("user.max_user_namespaces", "65536")),
+        _ => None,
+    }
+}
+
+fn require_path(path: &str) -> Result<(), String> {
+    if Path::new(path).exists() {
+        Ok(())
+    } else {
+        Err(format!("{} does not exist", path))
+    }
+}
+
+/// Check a runtime requirement through the checker, with the source of its kind registered
+fn check_running(requirement: &FlagRequirement, source: impl CheckSource + 'static) -> FlagStatus {
+    // Runtime requirements don't look at the config, an empty one stands in for it
+    let config = KernelConfig::parse("", "");
+    Checker::new().source(source).check_one(&config, requirement).map_or(FlagStatus::Invalid, |result| result.status)
+}
+
+/// A runtime setting that must have exactly the value a control requires
+fn requiring(kind: SourceKind, name: &str, expected: &str) -> FlagRequirement {
+    FlagRequirement { accepted: vec![expected.to_string()], ..FlagRequirement::for_source(kind, name) }
+}
+
+/// The running kernel's modules, including the ones installed for it
+fn running_modules() -> Result<Modules, String> {
+    let release = running_release()?;
+    let modules_dep = fs::read_to_string(format!("/lib/modules/{}/modules.dep", release)).unwrap_or_default();
+    Ok(Modules::running().installed(&modules_dep))
+}
+
+/// Whether a module is loaded, built in, or installed for the running kernel
+fn module_present(name: &str) -> Result<bool, String> {
+    let requirement = FlagRequirement::for_source(SourceKind::Module, name);
+    Ok(matches!(check_running(&requirement, running_modules()?), FlagStatus::EnabledInKernel | FlagStatus::EnabledAsModule))
+}
+
+/// A module is usable if it is loaded, built in, or installed for the running kernel
+fn require_module(name: &str) -> Result<(), String> {
+    if module_present(name)? {
+        Ok(())
+    } else {
+        Err(format!("{} is neither built in nor installed in /lib/modules/{}", name, running_release()?))
+    }
+}
+
+fn running_release() -> Result<String, String> {
+    crate::audit::running_version().ok_or_else(|| "can't determine the running kernel".to_string())
+}
+
+/// Path, relative to /lib/modules/<release>, of a module in modules.builtin or modules.dep
+fn module_entry(release: &str, list: &str, name: &str) -> Option<String> {
+    let normalized = module_name(name);
+    let content = fs::read_to_string(format!("/lib/modules/{}/{}", release, list)).ok()?;
+    content.lines().find_map(|line| {
+        let path = line.split(':').next().unwrap_or(line);
+        (module_name(path) == normalized).then(|| path.to_string())
+    })
+}
+
+/// The name of a module as listed in /sys/module, from its name or the path of its file
+pub(crate) fn module_name(entry: &str) -> String {
+    let file = entry.rsplit('/').next().unwrap_or(entry);
+    let stem = file.split(".ko").next().unwrap_or(file);
+    stem.replace('-', "_")
+}
+
+/// Compare a sysctl, given in dotted form, with the value a control requires
+fn require_sysctl(name: &str, expected: &str) -> Result<(), String> {
+    let sysctl = Sysctl::new();
+    let value = sysctl.get(name);
+    match (check_running(&requiring(SourceKind::Sysctl, name, expected), sysctl), value) {
+        (FlagStatus::EnabledInKernel, _) => Ok(()),
+        (_, Some(value)) => Err(format!("{} is {}", name, value.trim())),
+        (_, None) => Err(format!("can't read {}", name)),
+    }
+}
+
+fn running_cmdline() -> Result<Cmdline, String> {
+    Cmdline::running().map_err(|e| e.to_string())
+}
+
+/// Value of a parameter on the running kernel's command line, empty for a bare `name`
+fn cmdline_param(name: &str) -> Result<Option<String>, String> {
+    Ok(running_cmdline()?.get(name).map(str::to_string))
+}
+
+/// Compare a parameter on the kernel command line with the value a control requires
+fn require_cmdline(name: &str, expected: &str) -> Result<(), String> {
+    let cmdline = running_cmdline()?;
+    let value = cmdline.get(name).map(str::to_string);
+    match (check_running(&requiring(SourceKind::Cmdline, name, expected), cmdline), value) {
+        (FlagStatus::EnabledInKernel, _) => Ok(()),
+        (_, Some(value)) => Err(format!("{}={} on the kernel command line", name, value)),
+        (_, None) => Err(format!("{}={} is not on the kernel command line", name, expected)),
+    }
+}
+
+fn cgroup_unified_mounted() -> Result<(), String> {
+    let mounts = fs::read_to_string("/proc/self/mounts").map_err(|e| format!("can't read mounts: {}", e))?;
+    let cgroup2: Vec<&str> = mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?;
+            (fields.next()? == "cgroup2").then_some(mount_point)
+        })
+        .collect();
+
+    if cgroup2.contains(&"/sys/fs/cgroup") {
+        Ok(())
+    } else if let Some(mount_point) = cgroup2.first() {
+        Err(format!(
+            "hybrid hierarchy, cgroup2 is only mounted at {} and controllers stay on v1: boot with systemd.unified_cgroup_hierarchy=1",
+            mount_point
+        ))
+    } else {
+        Err("no cgroup2 filesystem is mounted, only the legacy v1 hierarchy".to_string())
+    }
+}
+
+fn cgroup_controllers() -> Result<(), String> {
+    let controllers = fs::read_to_string("/sys/fs/cgroup/cgroup.controllers")
+        .map_err(|_| "/sys/fs/cgroup/cgroup.controllers does not exist, cgroup v2 is not mounted there".to_string())?;
+    let available: Vec<&str> = controllers.split_whitespace().collect();
+    let missing: Vec<&str> = ["cpu", "memory", "io", "pids", "cpuset"]
+        .into_iter()
+        .filter(|controller| !available.contains(controller))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        // Compiled-in controllers are missing when bound to a v1 hierarchy or disabled on the command line
+        Err(format!(
+            "not available: {} (still bound to a v1 hierarchy, or disabled with cgroup_disable=)",
+            missing.join(", ")
+        ))
+    }
+}
+
+fn aslr_full() -> Result<(), String> {
+    require_sysctl("kernel.randomize_va_space", "2")
+}
+
+fn audit_at_boot() -> Result<(), String> {
+    require_cmdline("audit", "1")
+}
+
+fn audit_backlog_limit() -> Result<(), String> {
+    match cmdline_param("audit_backlog_limit")? {
+        Some(value) if value.parse::<u64>().is_ok_and(|limit| limit >= 8192) => Ok(()),
+        Some(value) => Err(format!("audit_backlog_limit is {}", value)),
+        None => Err("audit_backlog_limit is not on the kernel command line".to_string()),
+    }
+}
+
+fn criu_ns_last_pid() -> Result<(), String> {
+    require_path("/proc/sys/kernel/ns_last_pid")
+}
+
+fn criu_map_files() -> Result<(), String> {
+    fs::read_dir("/proc/self/map_files")
+        .map(|_| ())
+        .map_err(|e| format!("can't read /proc/self/map_files: {}", e))
+}
+
+fn criu_soft_dirty() -> Result<(), String> {
+    // Fresh mappings are flagged soft-dirty ("sd"), but only when the kernel tracks it
+    let smaps = fs::read_to_string("/proc/self/smaps").map_err(|e| format!("can't read smaps: {}", e))?;
+    let tracked = smaps
+        .lines()
+        .filter_map(|line| line.strip_prefix("VmFlags:"))
+        .any(|flags| flags.split_whitespace().any(|flag| flag == "sd"));
+    if tracked {
+        Ok(())
+    } else {
+        Err("no mapping is flagged soft-dirty in /proc/self/smaps".to_string())
+    }
+}
+
+fn criu_userfaultfd() -> Result<(), String> {
+    if Path::new("/dev/userfaultfd").exists() {
+        return Ok(());
+    }
+    require_path("/proc/sys/vm/unprivileged_userfaultfd")
+}
+
+fn criu_namespaces() -> Result<(), String> {
+    let missing: Vec<&str> = ["mnt", "net", "pid", "ipc", "uts", "user", "cgroup"]
+        .into_iter()
+        .filter(|ns| !Path::new(&format!("/proc/self/ns/{}", ns)).exists())
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("missing namespaces: {}", missing.join(", ")))
+    }
+}
+
+fn criu_fdinfo_mnt_id() -> Result<(), String> {
+    let fdinfo = fs::read_to_string("/proc/self/fdinfo/0").map_err(|e| format!("can't read fdinfo: {}", e))?;
+    if fdinfo.lines().any(|line| line.starts_with("mnt_id:")) {
+        Ok(())
+    } else {
+        Err("fdinfo has no mnt_id field".to_string())
+    }
+}
+
+fn criu_ptrace_scope() -> Result<(), String> {
+    match Sysctl::new().get("kernel.yama.ptrace_scope") {
+        Some(scope) if scope.trim() == "3" => Err("kernel.yama.ptrace_scope is 3, ptrace is disabled".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn livepatch_sysfs() -> Result<(), String> {
+    require_path("/sys/kernel/livepatch")
+}
+
+fn livepatch_ftrace_enabled() -> Result<(), String> {
+    // Patches can't be applied while ftrace is switched off at runtime
+    match Sysctl::new().get("kernel.ftrace_enabled") {
+        Some(value) if value.trim() == "0" => Err("kernel.ftrace_enabled is 0".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn realtime_kernel() -> Result<(), String> {
+    match fs::read_to_string("/sys/kernel/realtime") {
+        Ok(value) if value.trim() == "1" => Ok(()),
+        Ok(value) => Err(format!("/sys/kernel/realtime is {}", value.trim())),
+        Err(_) => Err("/sys/kernel/realtime does not exist, not a PREEMPT_RT kernel".to_string()),
+    }
+}
+
+fn xen_hypervisor() -> Result<(), String> {
+    match fs::read_to_string("/sys/hypervisor/type") {
+        Ok(kind) if kind.trim() == "xen" => Ok(()),
+        Ok(kind) => Err(format!("hypervisor is {}", kind.trim())),
+        Err(_) => Err("/sys/hypervisor/type does not exist, not running on Xen".to_string()),
+    }
+}
+
+fn xen_control_domain() -> Result<(), String> {
+    let capabilities = fs::read_to_string("/proc/xen/capabilities")
+        .map_err(|_| "/proc/xen/capabilities is unavailable, is xenfs mounted?".to_string())?;
+    if capabilities.contains("control_d") {
+        Ok(())
+    } else {
+        Err("this domain is not the control domain".to_string())
+    }
+}
+
+fn storage_nfs() -> Result<(), String> {
+    require_module("nfs")
+}
+
+fn storage_nfsv4() -> Result<(), String> {
+    require_module("nfsv4")
+}
+
+fn storage_nfsd() -> Result<(), String> {
+    require_module("nfsd")
+}
+
+fn storage_iscsi_tcp() -> Result<(), String> {
+    require_module("iscsi_tcp")
+}
+
+fn storage_dm_multipath() -> Result<(), String> {
+    require_module("dm-multipath")
+}
+
+fn storage_dm_crypt() -> Result<(), String> {
+    require_module("dm-crypt")
+}
+
+fn cis_suid_dumpable() -> Result<(), String> {
+    require_sysctl("fs.suid_dumpable", "0")
+}
+
+fn cis_apparmor_active() -> Result<(), String> {
+    let lsms = fs::read_to_string("/sys/kernel/security/lsm").map_err(|e| format!("can't read active LSMs: {}", e))?;
+    if lsms.trim().split(',').any(|lsm| lsm == "apparmor") {
+        Ok(())
+    } else {
+        Err(format!("active LSMs are {}", lsms.trim()))
+    }
+}
+
+fn cis_source_route() -> Result<(), String> {
+    require_sysctl("net.ipv4.conf.all.accept_source_route", "0")
+}
+
+fn cis_icmp_redirects() -> Result<(), String> {
+    require_sysctl("net.ipv4.conf.all.accept_redirects", "0")
+}
+
+fn cis_rp_filter() -> Result<(), String> {
+    require_sysctl("net.ipv4.conf.all.rp_filter", "1")
+}
+
+fn cis_syncookies() -> Result<(), String> {
+    require_sysctl("net.ipv4.tcp_syncookies", "1")
+}
+
+fn stig_fips() -> Result<(), String> {
+    require_sysctl("crypto.fips_enabled", "1")
+}
+
+fn stig_kexec_load_disabled() -> Result<(), String> {
+    require_sysctl("kernel.kexec_load_disabled", "1")
+}
+
+fn stig_protected_symlinks() -> Result<(), String> {
+    require_sysctl("fs.protected_symlinks", "1")
+}
+
+fn stig_protected_hardlinks() -> Result<(), String> {
+    require_sysctl("fs.protected_hardlinks", "1")
+}
+
+fn stig_dmesg_restrict() -> Result<(), String> {
+    require_sysctl("kernel.dmesg_restrict", "1")
+}
+
+fn stig_perf_event_paranoid() -> Result<(), String> {
+    require_sysctl("kernel.perf_event_paranoid", "2")
+}
+
+fn stig_page_poison() -> Result<(), String> {
+    require_cmdline("page_poison", "1")
+}
+
+fn stig_vsyscall() -> Result<(), String> {
+    require_cmdline("vsyscall", "none")
+}
+
+fn stig_slub_debug() -> Result<(), String> {
+    require_cmdline("slub_debug", "P")
+}
+
+fn stig_core_pattern() -> Result<(), String> {
+    require_sysctl("kernel.core_pattern", "|/bin/false")
+}
+
+fn stig_pti() -> Result<(), String> {
+    require_cmdline("pti", "on")
+}
+
+fn stig_unprivileged_bpf() -> Result<(), String> {
+    require_sysctl("kernel.unprivileged_bpf_disabled", "1")
+}
+
+fn stig_ptrace_scope() -> Result<(), String> {
+    require_sysctl("kernel.yama.ptrace_scope", "1")
+}
+
+fn stig_kptr_restrict() -> Result<(), String> {
+    require_sysctl("kernel.kptr_restrict", "1")
+}
+
+fn stig_user_namespaces() -> Result<(), String> {
+    require_sysctl("user.max_user_namespaces", "0")
+}
+
+fn dkms_headers() -> Result<(), String> {
+    let release = running_release()?;
+    let build = crate::dkms::headers_dir(&release);
+    if !build.join("Makefile").exists() {
+        return Err(format!("{} is missing, install the headers package", build.display()));
+    }
+    match crate::dkms::headers_release(&build) {
+        Some(found) if found != release => Err(format!("headers were prepared for {}", found)),
+        _ => Ok(()),
+    }
+}
+
+/// Appended to every signed module, in front of which the signature itself sits
+const MODULE_SIGNATURE_MAGIC: &[u8] = b"~Module signature appended~\n";
+
+fn gpu_sig_enforce() -> Result<(), String> {
+    if !sig_enforce() {
+        return Ok(());
+    }
+
+    let release = running_release()?;
+    let Some(entry) = module_entry(&release, "modules.dep", "nvidia") else {
+        return Err("the kernel only loads signed modules (module.sig_enforce=Y or MODULE_SIG_FORCE) and no nvidia \
+                    module is installed yet: make sure DKMS signs it with a key enrolled through mokutil"
+            .to_string());
+    };
+    // Compressed modules can't be inspected without decompressing them
+    if !entry.ends_with(".ko") {
+        return Ok(());
+    }
+    let path = format!("/lib/modules/{}/{}", release, entry);
+    let data = fs::read(&path).map_err(|e| format!("can't read {}: {}", path, e))?;
+    if data.ends_with(MODULE_SIGNATURE_MAGIC) {
+        Ok(())
+    } else {
+        Err(format!(
+            "the kernel only loads signed modules (module.sig_enforce=Y or MODULE_SIG_FORCE) and {} is unsigned, \
+             loading it fails with \"Key was rejected by service\": sign it with a key enrolled through mokutil",
+            path
+        ))
+    }
+}
+
+fn gpu_lockdown() -> Result<(), String> {
+    match lockdown_mode() {
+        None => Ok(()),
+        Some(mode) => Err(format!(
+            "kernel lockdown is in {} mode, usually because Secure Boot is on: unsigned modules are rejected, \
+             sign the driver with an enrolled MOK key or disable Secure Boot",
+            mode
+        )),
+    }
+}
+
+fn gpu_nouveau() -> Result<(), String> {
+    let nouveau = FlagRequirement::for_source(SourceKind::Module, "nouveau");
+    if check_running(&nouveau, Modules::running()) != FlagStatus::Missing {
+        Err("nouveau is loaded and binds the GPU before nvidia can: blacklist it with modprobe.blacklist=nouveau".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Landlock ABI version of the running kernel, or why Landlock is unusable
+pub fn landlock_abi() -> Result<i64, String> {
+    match sys::landlock_abi_version() {
+        Ok(version) => Ok(version),
+        Err(e) if e.raw_os_error() == Some(sys::ENOSYS) => {
+            Err("landlock_create_ruleset is not implemented, the kernel lacks CONFIG_SECURITY_LANDLOCK".to_string())
+        }
+        Err(e) if e.raw_os_error() == Some(sys::EOPNOTSUPP) => {
+            Err("Landlock is compiled in but not enabled, add landlock to CONFIG_LSM or the lsm= boot parameter".to_string())
+        }
+        Err(e) => Err(format!("landlock_create_ruleset failed: {}", e)),
+    }
+}
+
+fn sandbox_landlock() -> Result<(), String> {
+    landlock_abi().map(|_| ())
+}
+
+fn sandbox_seccomp() -> Result<(), String> {
+    sys::seccomp_mode()
+        .map(|_| ())
+        .map_err(|e| format!("PR_GET_SECCOMP failed ({}), the kernel lacks CONFIG_SECCOMP", e))
+}
+
+fn sandbox_seccomp_filter() -> Result<(), String> {
+    // Installing a NULL filter fails with EFAULT when filter mode exists and EINVAL when it doesn't
+    let error = sys::install_null_seccomp_filter();
+    match error.raw_os_error() {
+        Some(sys::EFAULT) => Ok(()),
+        Some(sys::EINVAL) => Err("seccomp filter mode is unavailable, the kernel lacks CONFIG_SECCOMP_FILTER".to_string()),
+        _ => Err(format!("unexpected result probing seccomp filter mode: {}", error)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::ffi::{c_int, c_long, c_ulong};
+    use std::io;
+    use std::ptr;
+
+    pub const EFAULT: i32 = 14;
+    pub const EINVAL: i32 = 22;
+    pub const ENOSYS: i32 = 38;
+    pub const EOPNOTSUPP: i32 = 95;
+
+    // Same number on every architecture, it was allocated after the syscall tables were unified
+    const SYS_LANDLOCK_CREATE_RULESET: c_long = 444;
+    const LANDLOCK_CREATE_RULESET_VERSION: c_ulong = 1;
+    const PR_GET_SECCOMP: c_int = 21;
+    const PR_SET_SECCOMP: c_int = 22;
+    const SECCOMP_MODE_FILTER: c_ulong = 2;
+
+    extern "C" {
+        fn syscall(number: c_long, ...) -> c_long;
+        fn prctl(option: c_int, ...) -> c_int;
+    }
+
+    pub fn landlock_abi_version() -> io::Result<i64> {
+        // SAFETY: a NULL attribute with size 0 and the VERSION flag only queries the ABI
+        let version = unsafe {
+            syscall(SYS_LANDLOCK_CREATE_RULESET, ptr::null::<u8>(), 0 as c_ulong, LANDLOCK_CREATE_RULESET_VERSION)
+        };
+        if version < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(version as i64)
+        }
+    }
+
+    pub fn seccomp_mode() -> io::Result<i32> {
+        // SAFETY: PR_GET_SECCOMP takes no arguments and only reads the calling thread's mode
+        let mode = unsafe { prctl(PR_GET_SECCOMP) };
+        if mode < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(mode)
+        }
+    }
+
+    /// Always fails; the error tells whether filter mode is supported
+    pub fn install_null_seccomp_filter() -> io::Error {
+        // SAFETY: the kernel rejects the NULL program before changing any state
+        unsafe { prctl(PR_SET_SECCOMP, SECCOMP_MODE_FILTER, ptr::null::<u8>()) };
+        io::Error::last_os_error()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::io;
+
+    pub const EFAULT: i32 = -1;
+    pub const EINVAL: i32 = -2;
+    pub const ENOSYS: i32 = -3;
+    pub const EOPNOTSUPP: i32 = -4;
+
+    pub fn landlock_abi_version() -> io::Result<i64> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Landlock is Linux only"))
+    }
+
+    pub fn seccomp_mode() -> io::Result<i32> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "seccomp is Linux only"))
+    }
+
+    pub fn install_null_seccomp_filter() -> io::Error {
+        io::Error::new(io::ErrorKind::Unsupported, "seccomp is Linux only")
+    }
+}
+
+fn ima_securityfs() -> Result<(), String> {
+    if Path::new("/sys/kernel/security/ima").exists() {
+        return Ok(());
+    }
+    if Path::new("/sys/kernel/security").read_dir().is_ok_and(|mut entries| entries.next().is_none()) {
+        return Err("securityfs is not mounted at /sys/kernel/security".to_string());
+    }
+    Err("/sys/kernel/security/ima does not exist, IMA is not enabled".to_string())
+}
+
+fn ima_policy() -> Result<(), String> {
+    if cmdline_param("ima_policy")?.is_some() || cmdline_param("ima_appraise_tcb").is_ok_and(|p| p.is_some()) {
+        return Ok(());
+    }
+    // Without a boot policy IMA measures nothing until a custom policy is written
+    match fs::read_to_string("/sys/kernel/security/ima/policy") {
+        Ok(policy) if !policy.trim().is_empty() => Ok(()),
+        Ok(_) => Err("no ima_policy= boot parameter and the loaded policy is empty, nothing is measured".to_string()),
+        Err(_) => Err("no ima_policy= boot parameter (e.g. ima_policy=tcb) and the policy can't be read".to_string()),
+    }
+}
+
+fn ima_tpm() -> Result<(), String> {
+    if Path::new("/sys/class/tpm/tpm0").exists() {
+        Ok(())
+    } else {
+        Err("no TPM in /sys/class/tpm, IMA runs in TPM-bypass mode and measurements can't be attested".to_string())
+    }
+}
+
+fn power_states() -> Result<Vec<String>, String> {
+    let states = fs::read_to_string("/sys/power/state").map_err(|e| format!("can't read /sys/power/state: {}", e))?;
+    Ok(states.split_whitespace().map(String::from).collect())
+}
+
+fn power_suspend() -> Result<(), String> {
+    if power_states()?.iter().any(|state| state == "mem" || state == "freeze") {
+        Ok(())
+    } else {
+        Err("the kernel offers no suspend state, check the firmware sleep settings and `dmesg | grep -i acpi`".to_string())
+    }
+}
+
+fn power_hibernate() -> Result<(), String> {
+    if power_states()?.iter().any(|state| state == "disk") {
+        return Ok(());
+    }
+    // Lockdown (e.g. under Secure Boot) removes "disk" since the image can't be verified
+    let locked_down = fs::read_to_string("/sys/kernel/security/lockdown")
+        .is_ok_and(|lockdown| !lockdown.contains("[none]"));
+    if locked_down {
+        Err("hibernation is disabled by kernel lockdown, usually because Secure Boot is on".to_string())
+    } else {
+        Err("the kernel does not offer hibernation (\"disk\" missing from /sys/power/state), is nohibernate on the command line?".to_string())
+    }
+}
+
+/// Size in kB of a `Name:   1234 kB` line of /proc/meminfo
+fn meminfo_kb(name: &str) -> Result<u64, String> {
+    let meminfo = fs::read_to_string("/proc/meminfo").map_err(|e| format!("can't read /proc/meminfo: {}", e))?;
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+        .and_then(|value| value.split_whitespace().next()?.parse().ok())
+        .ok_or_else(|| format!("{} is missing from /proc/meminfo", name))
+}
+
+fn power_swap_size() -> Result<(), String> {
+    let ram = meminfo_kb("MemTotal")?;
+    let swap = meminfo_kb("SwapTotal")?;
+    let gib = |kb: u64| kb as f64 / (1024.0 * 1024.0);
+    if swap == 0 {
+        Err(format!("no swap is configured: add a swap partition or file of at least {:.1} GiB and set resume=", gib(ram)))
+    } else if swap < ram {
+        Err(format!(
+            "swap is {:.1} GiB but RAM is {:.1} GiB, hibernating with memory in use can fail: grow swap to at least {:.1} GiB",
+            gib(swap),
+            gib(ram),
+            gib(ram)
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn power_resume_device() -> Result<(), String> {
+    if cmdline_param("resume")?.is_some() {
+        return Ok(());
+    }
+    match fs::read_to_string("/sys/power/resume") {
+        Ok(device) if device.trim() != "0:0" => Ok(()),
+        _ => Err("no resume= boot parameter and /sys/power/resume is unset, the image would be written but never restored".to_string()),
+    }
+}
+
+fn running_config() -> Result<KernelConfig, String> {
+    if let Ok(config) = KernelConfig::load(crate::PROC_CONFIG) {
+        return Ok(config);
+    }
+    let boot_config = format!("/boot/config-{}", running_release()?);
+    KernelConfig::load(&boot_config).map_err(|_| "the running kernel's config is unavailable".to_string())
+}
+
+fn sig_enforce() -> bool {
+    Sysfs::new().get("/sys/module/module/parameters/sig_enforce").is_some_and(|value| value == "Y")
+}
+
+/// The active lockdown mode, None when lockdown is off or unavailable
+fn lockdown_mode() -> Option<String> {
+    // e.g. "none [integrity] confidentiality", with the active mode in brackets
+    let lockdown = fs::read_to_string("/sys/kernel/security/lockdown").ok()?;
+    let mode = lockdown.split_whitespace().find(|mode| mode.starts_with('['))?;
+    let mode = mode.trim_matches(|c| c == '[' || c == ']');
+    (mode != "none").then(|| mode.to_string())
+}
+
+fn signing_enforced() -> Result<(), String> {
+    let modules_disabled = Sysctl::new().get("kernel.modules_disabled").is_some_and(|value| value.trim() == "1");
+    if modules_disabled || sig_enforce() || lockdown_mode().is_some() {
+        Ok(())
+    } else {
+        Err("unsigned modules load with only a taint: set CONFIG_MODULE_SIG_FORCE or boot with module.sig_enforce=1".to_string())
+    }
+}
+
+fn signing_config_matches_runtime() -> Result<(), String> {
+    let config = running_config()?;
+    let forced = config.get("CONFIG_MODULE_SIG_FORCE") == Some(&ConfigValue::Bool(true));
+    let enforced = sig_enforce();
+    if forced && !enforced {
+        return Err("CONFIG_MODULE_SIG_FORCE is set but sig_enforce reports N".to_string());
+    }
+    if !forced && enforced {
+        let cause = match cmdline_param("module.sig_enforce") {
+            Ok(Some(_)) => "module.sig_enforce= on the command line".to_string(),
+            _ => "a boot parameter or the distribution's Secure Boot handling".to_string(),
+        };
+        return Err(format!(
+            "the config leaves enforcement off but the running kernel enforces it because of {}: \
+             booting the same kernel without it accepts unsigned modules",
+            cause
+        ));
+    }
+    Ok(())
+}
+
+fn signing_lockdown_consistent() -> Result<(), String> {
+    let Some(mode) = lockdown_mode() else {
+        return Ok(());
+    };
+    let config = running_config()?;
+    if config.get("CONFIG_MODULE_SIG") == Some(&ConfigValue::Bool(true)) {
+        Ok(())
+    } else {
+        Err(format!(
+            "lockdown is in {} mode but CONFIG_MODULE_SIG is unset, so no module can be verified and every module load is refused",
+            mode
+        ))
+    }
+}
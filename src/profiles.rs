@@ -0,0 +1,159 @@
+use crate::probes::{self, Probe};
+
+/// A built-in set of kernel flags for a common workload
+pub struct Profile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub flags: &'static str,
+    /// Checks of the running kernel that go beyond its config
+    pub probes: &'static [Probe],
+}
+
+pub const PROFILES: &[Profile] = &[
+    Profile {
+        name: "cgroup-v2",
+        description: "cgroup v2 unified hierarchy with the cpu, memory, io, pids and cpuset controllers",
+        flags: include_str!("../profiles/cgroup-v2.flags"),
+        probes: probes::CGROUP_V2,
+    },
+    Profile {
+        name: "cis",
+        description: "Kernel controls of the CIS Ubuntu 22.04 benchmark, reported with control IDs",
+        flags: include_str!("../profiles/cis.flags"),
+        probes: probes::CIS,
+    },
+    Profile {
+        name: "containers",
+        description: "Namespaces, cgroups and networking for container runtimes",
+        flags: include_str!("../profiles/containers.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "criu",
+        description: "Checkpoint/restore support needed by CRIU and container live migration",
+        flags: include_str!("../profiles/criu.flags"),
+        probes: probes::CRIU,
+    },
+    Profile {
+        name: "dkms",
+        description: "Prerequisites for building out-of-tree modules with DKMS (ZFS, VirtualBox, ...)",
+        flags: include_str!("../profiles/dkms.flags"),
+        probes: probes::DKMS,
+    },
+    Profile {
+        name: "ebpf",
+        description: "BPF, BTF and tracing support for eBPF tooling",
+        flags: include_str!("../profiles/ebpf.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "gpu",
+        description: "Out-of-tree GPU drivers (NVIDIA): module support, DRM/KMS and module signing conflicts",
+        flags: include_str!("../profiles/gpu.flags"),
+        probes: probes::GPU,
+    },
+    Profile {
+        name: "hardening",
+        description: "Baseline kernel self-protection options",
+        flags: include_str!("../profiles/hardening.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "hardening-strict",
+        description: "Stricter self-protection on top of the hardening profile",
+        flags: include_str!("../profiles/hardening-strict.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "ima",
+        description: "Measured boot and IMA/EVM integrity with TPM 2.0 for remote attestation",
+        flags: include_str!("../profiles/ima.flags"),
+        probes: probes::IMA,
+    },
+    Profile {
+        name: "kvm",
+        description: "KVM, vhost and VFIO for running virtual machines",
+        flags: include_str!("../profiles/kvm.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "livepatch",
+        description: "Kernel live patching (kpatch, klp, Canonical Livepatch): ftrace and reliable stack traces",
+        flags: include_str!("../profiles/livepatch.flags"),
+        probes: probes::LIVEPATCH,
+    },
+    Profile {
+        name: "module-signing",
+        description: "Module signature enforcement, cross-checked with runtime enforcement and lockdown",
+        flags: include_str!("../profiles/module-signing.flags"),
+        probes: probes::MODULE_SIGNING,
+    },
+    Profile {
+        name: "power",
+        description: "Suspend to RAM and hibernation, with runtime checks of sleep states, swap and resume device",
+        flags: include_str!("../profiles/power.flags"),
+        probes: probes::POWER,
+    },
+    Profile {
+        name: "realtime",
+        description: "PREEMPT_RT, timer frequency and tickless options for low-latency workloads",
+        flags: include_str!("../profiles/realtime.flags"),
+        probes: probes::REALTIME,
+    },
+    Profile {
+        name: "rpi",
+        description: "Raspberry Pi SoC drivers: firmware, VC4/KMS, GPIO/I2C/SPI controllers, SD and Ethernet",
+        flags: include_str!("../profiles/rpi.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "sandbox",
+        description: "Landlock and seccomp-bpf for sandboxing tools, verified against the running kernel",
+        flags: include_str!("../profiles/sandbox.flags"),
+        probes: probes::SANDBOX,
+    },
+    Profile {
+        name: "sbc",
+        description: "Generic ARM single-board computer support: device tree overlays, GPIO, I2C, SPI, PWM",
+        flags: include_str!("../profiles/sbc.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "storage",
+        description: "NFS client/server, iSCSI initiator, dm-multipath and dm-crypt",
+        flags: include_str!("../profiles/storage.flags"),
+        probes: probes::STORAGE,
+    },
+    Profile {
+        name: "stig",
+        description: "Kernel, sysctl and boot parameter rules of the DISA RHEL 8 STIG, reported with rule IDs",
+        flags: include_str!("../profiles/stig.flags"),
+        probes: probes::STIG,
+    },
+    Profile {
+        name: "wsl2-docker",
+        description: "Flags most often missing when running Docker under WSL2",
+        flags: include_str!("../profiles/wsl2-docker.flags"),
+        probes: &[],
+    },
+    Profile {
+        name: "xen-dom0",
+        description: "Xen control domain support and backend drivers",
+        flags: include_str!("../profiles/xen-dom0.flags"),
+        probes: probes::XEN_DOM0,
+    },
+    Profile {
+        name: "xen-domU",
+        description: "Xen guest support and frontend drivers",
+        flags: include_str!("../profiles/xen-domU.flags"),
+        probes: probes::XEN_DOMU,
+    },
+];
+
+/// Look up a built-in profile by name
+pub fn find(name: &str) -> anyhow::Result<&'static Profile> {
+    PROFILES.iter().find(|p| p.name == name).ok_or_else(|| {
+        let available: Vec<&str> = PROFILES.iter().map(|p| p.name).collect();
+        anyhow::anyhow!("Unknown profile: {} (available: {})", name, available.join(", "))
+    })
+}
@@ -0,0 +1,124 @@
+//! The `kcc` Python module, for validation tooling that would otherwise shell out to
+//! the binary and parse its text output.
+//!
+//! ```python
+//! import kcc
+//!
+//! config = kcc.KernelConfig.load("/boot/config-6.12.9")
+//! config.get("CONFIG_HZ")          # 1000
+//! config.check("CONFIG_BPF")       # "enabled"
+//! report = kcc.check(config, open("requirements.txt").read())
+//! report["summary"]["missing"]
+//! ```
+//!
+//! Built with maturin, which picks up the features from `pyproject.toml`.
+
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::IntoPyObjectExt;
+
+use crate::config::{ConfigValue, KernelConfig, Tristate};
+use crate::report::Report;
+
+/// A kernel config parsed into its symbols
+#[pyclass(name = "KernelConfig", module = "kcc", frozen)]
+struct PyKernelConfig(KernelConfig);
+
+#[pymethods]
+impl PyKernelConfig {
+    /// Read a config file: plain, gzip, xz or zstd, or a kernel image with an embedded config
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        KernelConfig::load(path).map(PyKernelConfig).map_err(|e| PyOSError::new_err(e.to_string()))
+    }
+
+    /// Parse the text of a config
+    #[staticmethod]
+    #[pyo3(signature = (text, source = "<string>"))]
+    fn parse(text: &str, source: &str) -> Self {
+        PyKernelConfig(KernelConfig::parse(source, text))
+    }
+
+    /// Parse a config from bytes, decompressing it or extracting it from a kernel image as needed
+    #[staticmethod]
+    #[pyo3(signature = (data, source = "<bytes>"))]
+    fn from_bytes(data: &[u8], source: &str) -> PyResult<Self> {
+        KernelConfig::from_bytes(source, data).map(PyKernelConfig).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn source(&self) -> &str {
+        self.0.source()
+    }
+
+    /// The text of the config, after decompression
+    #[getter]
+    fn text(&self) -> &str {
+        self.0.text()
+    }
+
+    /// The value of a symbol, with or without its `CONFIG_` prefix: True or False for
+    /// `y` and `n` (or not set), "m" for modules, an int or a string; None when absent
+    fn get(&self, py: Python<'_>, name: &str) -> PyResult<Option<Py<PyAny>>> {
+        self.0.get(name).map(|value| to_python(py, value)).transpose()
+    }
+
+    /// The symbols and their values, in the order they appear in the config
+    fn items(&self, py: Python<'_>) -> PyResult<Vec<(String, Py<PyAny>)>> {
+        self.0.iter().map(|(name, value)| Ok((name.to_string(), to_python(py, value)?))).collect()
+    }
+
+    /// Check a flag or rule, returning the status as written in reports, e.g. "enabled" or "missing"
+    fn check(&self, flag: &str) -> &'static str {
+        self.0.check(flag).status.as_str()
+    }
+
+    fn __contains__(&self, name: &str) -> bool {
+        self.0.contains(name)
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<kcc.KernelConfig {:?} with {} symbols>", self.0.source(), self.0.len())
+    }
+}
+
+fn to_python(py: Python<'_>, value: &ConfigValue) -> PyResult<Py<PyAny>> {
+    match value {
+        ConfigValue::Bool(enabled) => enabled.into_py_any(py),
+        ConfigValue::Tristate(Tristate::Module) => "m".into_py_any(py),
+        ConfigValue::Tristate(state) => (*state == Tristate::Yes).into_py_any(py),
+        ConfigValue::Int(int) => int.into_py_any(py),
+        ConfigValue::Hex(hex) => hex.into_py_any(py),
+        ConfigValue::String(string) => string.into_py_any(py),
+        ConfigValue::NotSet => false.into_py_any(py),
+    }
+}
+
+/// Check a config against the text of a flags file and return the report as a dict, the
+/// document `kcc --format json` prints
+#[pyfunction]
+#[pyo3(signature = (config, flags, source = "flags"))]
+fn check(py: Python<'_>, config: &PyKernelConfig, flags: &str, source: &str) -> PyResult<Py<PyAny>> {
+    let report = Report::from_flags(&config.0, source, flags).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let json = serde_json::to_string(&report).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// The entries of a flags file: flag names and rule expressions
+#[pyfunction]
+fn parse_flags(flags: &str) -> PyResult<Vec<String>> {
+    crate::check::parse_flags(flags).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+#[pymodule]
+fn kcc(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<PyKernelConfig>()?;
+    m.add_function(wrap_pyfunction!(check, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_flags, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::process::{Command, Output};
+use std::thread;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::compress;
+
+/// Timeout, retry and TLS settings applied to every remote source
+pub struct FetchPolicy {
+    /// Seconds allowed for establishing a connection
+    pub connect_timeout: u64,
+    /// Seconds the transfer may stall before it is aborted
+    pub read_timeout: u64,
+    /// Number of retries after the first failed attempt
+    pub retries: u32,
+    /// Initial delay between attempts in milliseconds, doubled after each failure
+    pub backoff_ms: u64,
+    /// CA certificates to verify the server with instead of the system store
+    pub ca_cert: Option<String>,
+    /// Skip TLS certificate verification
+    pub insecure: bool,
+}
+
+/// Returns true if the config path points to a remote source
+pub fn is_remote(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Expected SHA-256 digests of remote sources, from --sha256 and --lockfile
+#[derive(Default)]
+pub struct Pins {
+    /// Digests any remote source may match
+    digests: Vec<String>,
+    /// Digests for specific sources, from a lockfile
+    by_source: HashMap<String, String>,
+}
+
+impl Pins {
+    /// Collect the digests given on the command line and the entries of a
+    /// lockfile in sha256sum format (`<digest>  <source>` per line)
+    pub fn load(digests: &[String], lockfile: Option<&str>) -> anyhow::Result<Self> {
+        let mut pins = Pins::default();
+        for digest in digests {
+            pins.digests.push(parse_digest(digest)?);
+        }
+
+        if let Some(path) = lockfile {
+            let content = fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read lockfile {}: {}", path, e))?;
+            for (line_number, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (digest, source) = line
+                    .split_once(char::is_whitespace)
+                    .ok_or_else(|| anyhow::anyhow!("{}: line {}: expected <sha256> <source>", path, line_number + 1))?;
+                // sha256sum marks binary mode with a '*' before the name
+                let source = source.trim_start().trim_start_matches('*');
+                let digest = parse_digest(digest).map_err(|e| anyhow::anyhow!("{}: line {}: {}", path, line_number + 1, e))?;
+                pins.by_source.insert(source.to_string(), digest);
+            }
+        }
+
+        Ok(pins)
+    }
+
+    /// Refuse data whose digest doesn't match the pin for its source; once
+    /// anything is pinned, unpinned remote sources are refused as well
+    pub fn verify(&self, source: &str, data: &[u8]) -> anyhow::Result<()> {
+        if self.digests.is_empty() && self.by_source.is_empty() {
+            return Ok(());
+        }
+
+        let actual = sha256_hex(data);
+        let matches = match self.by_source.get(source) {
+            Some(expected) => *expected == actual,
+            None if self.digests.is_empty() => {
+                return Err(anyhow::anyhow!("{} is not pinned in the lockfile (sha256 {})", source, actual))
+            }
+            None => self.digests.contains(&actual),
+        };
+
+        if matches {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Refusing {}: sha256 {} doesn't match the pinned digest", source, actual))
+        }
+    }
+}
+
+fn parse_digest(digest: &str) -> anyhow::Result<String> {
+    let digest = digest.trim().to_ascii_lowercase();
+    let digest = digest.strip_prefix("sha256:").unwrap_or(&digest).to_string();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("Invalid sha256 digest: {}", digest));
+    }
+    Ok(digest)
+}
+
+/// Hex SHA-256 digest of `data`
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Fetch a remote source, retrying transient failures, and check it against the pins
+pub fn fetch(source: &str, policy: &FetchPolicy, pins: &Pins) -> anyhow::Result<Vec<u8>> {
+    let data = with_retries(source, policy, || fetch_http(source, policy))?;
+    pins.verify(source, &data)?;
+    Ok(data)
+}
+
+/// Fetch a kernel config from a remote source, retrying transient failures
+pub fn fetch_config(source: &str, policy: &FetchPolicy, pins: &Pins) -> anyhow::Result<String> {
+    let data = fetch(source, policy, pins)?;
+    compress::decode_config(&data, source)
+}
+
+/// Finds the running kernel's config on a remote host, in the same order as locally
+pub(crate) const FIND_CONFIG: &str = r#"r=$(uname -r); for f in /proc/config.gz /boot/config-$r /boot/config-$r.xz /boot/config-$r.zst /lib/modules/$r/build/.config; do if [ -r "$f" ]; then echo "$f"; exit 0; fi; done"#;
+
+/// Fetch a kernel config from `host` over SSH, either `path` or the running
+/// kernel's config; returns the `host:path` it was read from and the content
+pub fn fetch_ssh_config(
+    host: &str,
+    path: Option<&str>,
+    policy: &FetchPolicy,
+    pins: &Pins,
+) -> anyhow::Result<(String, String)> {
+    let path = match path {
+        Some(path) => path.to_string(),
+        None => {
+            let found = with_retries(host, policy, || run_ssh(host, FIND_CONFIG, policy))?;
+            let found = String::from_utf8_lossy(&found).trim().to_string();
+            if found.is_empty() {
+                return Err(anyhow::anyhow!("No kernel config found on {}, pass its path with --config", host));
+            }
+            found
+        }
+    };
+
+    let source = format!("{}:{}", host, path);
+    let command = cat_command(&path);
+    let data = with_retries(&source, policy, || run_ssh(host, &command, policy))?;
+    pins.verify(&source, &data)?;
+    let content = compress::decode_config(&data, &path)?;
+    Ok((source, content))
+}
+
+fn run_ssh(host: &str, command: &str, policy: &FetchPolicy) -> anyhow::Result<Vec<u8>> {
+    fetch_output("ssh", ssh_command(host, command, policy).output())
+}
+
+/// The ssh invocation running `command` on `host`
+pub(crate) fn ssh_command(host: &str, command: &str, policy: &FetchPolicy) -> Command {
+    // Never prompt for a password, and give up on a connection that stops answering
+    let mut ssh = Command::new("ssh");
+    ssh.args(["-o", "BatchMode=yes"])
+        .args(["-o", &format!("ConnectTimeout={}", policy.connect_timeout)])
+        .args(["-o", &format!("ServerAliveInterval={}", policy.read_timeout)])
+        .args(["-o", "ServerAliveCountMax=1"])
+        .arg("--")
+        .arg(host)
+        .arg(command);
+    ssh
+}
+
+/// The shell command printing a file on a remote host
+pub(crate) fn cat_command(path: &str) -> String {
+    format!("cat -- '{}'", path.replace('\'', "'\\''"))
+}
+
+/// The stdout of a finished `tool`, or its stderr as the error
+pub(crate) fn command_output(tool: &str, output: io::Result<Output>) -> anyhow::Result<Vec<u8>> {
+    let output = output.map_err(|e| anyhow::anyhow!("Failed to run {}: {}", tool, e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} failed: {}",
+            tool,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(output.stdout)
+}
+
+/// A failed fetch that another attempt may get past, such as a timeout or a refused connection
+#[derive(Debug)]
+pub(crate) struct Transient(anyhow::Error);
+
+impl fmt::Display for Transient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Transient {}
+
+/// Errors ssh reports with exit code 255 when the host can't be reached, rather than refusing the login
+const SSH_CONNECTION_ERRORS: &[&str] = &[
+    "Could not resolve hostname",
+    "Connection refused",
+    "Connection reset",
+    "Connection closed",
+    "Connection timed out",
+    "Operation timed out",
+    "No route to host",
+    "Network is unreachable",
+    "Timeout, server",
+];
+
+/// Like `command_output` for curl and ssh, marking the failures worth retrying as [`Transient`]
+pub(crate) fn fetch_output(tool: &str, output: io::Result<Output>) -> anyhow::Result<Vec<u8>> {
+    let transient = output.as_ref().is_ok_and(|output| !output.status.success() && is_transient(tool, output));
+    command_output(tool, output).map_err(|e| if transient { Transient(e).into() } else { e })
+}
+
+/// Whether curl failed to resolve, connect or finish the transfer or got a 5xx or 429 response,
+/// or ssh failed to reach the host; a 404, a rejected key or a missing file won't go away
+fn is_transient(tool: &str, output: &Output) -> bool {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match (tool, output.status.code()) {
+        ("curl", Some(6 | 7 | 28 | 35 | 52 | 56)) => true,
+        // --fail turns HTTP errors into exit code 22, naming the status in the message
+        ("curl", Some(22)) => http_status(&stderr).is_some_and(|status| status == 429 || (500..600).contains(&status)),
+        ("ssh", Some(255)) => SSH_CONNECTION_ERRORS.iter().any(|error| stderr.contains(error)),
+        _ => false,
+    }
+}
+
+/// The status in curl's "The requested URL returned error: 503" message
+fn http_status(stderr: &str) -> Option<u16> {
+    let (_, rest) = stderr.split_once("returned error: ")?;
+    rest.get(..3)?.parse().ok()
+}
+
+/// Run `attempt` until it succeeds, fails for good or the policy's retries are exhausted,
+/// printing a diagnostic for every failed attempt that is retried
+fn with_retries<T>(
+    source: &str,
+    policy: &FetchPolicy,
+    mut attempt: impl FnMut() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let attempts = policy.retries + 1;
+    let mut delay = Duration::from_millis(policy.backoff_ms);
+
+    for n in 1..=attempts {
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(e) if n < attempts && e.is::<Transient>() => {
+                retrying(source, n, attempts, delay, &e);
+                thread::sleep(delay);
+                delay *= 2;
+            }
+            Err(e) => return Err(gave_up(source, n, e)),
+        }
+    }
+
+    unreachable!("at least one attempt is always made")
+}
+
+/// Print the diagnostic for a failed attempt that will be retried after `delay`
+pub(crate) fn retrying(source: &str, n: u32, attempts: u32, delay: Duration, e: &anyhow::Error) {
+    eprintln!("⚠️  Attempt {}/{} to fetch {} failed: {}", n, attempts, source, e);
+    eprintln!("   Retrying in {} ms...", delay.as_millis());
+}
+
+/// The error for a source that failed after `attempts` attempts
+pub(crate) fn gave_up(source: &str, attempts: u32, e: anyhow::Error) -> anyhow::Error {
+    match attempts {
+        1 => anyhow::anyhow!("Failed to fetch {}: {}", source, e),
+        _ => anyhow::anyhow!("Failed to fetch {} after {} attempts: {}", source, attempts, e),
+    }
+}
+
+fn fetch_http(url: &str, policy: &FetchPolicy) -> anyhow::Result<Vec<u8>> {
+    fetch_output("curl", curl_command(url, policy).output())
+}
+
+/// The curl invocation downloading `url`
+pub(crate) fn curl_command(url: &str, policy: &FetchPolicy) -> Command {
+    // curl has no plain read timeout, so abort when the transfer stalls
+    // below 1 byte/s for the configured number of seconds instead
+    let mut curl = Command::new("curl");
+    curl.args(["--silent", "--show-error", "--fail", "--location"])
+        .args(["--connect-timeout", &policy.connect_timeout.to_string()])
+        .args(["--speed-limit", "1"])
+        .args(["--speed-time", &policy.read_timeout.to_string()])
+        // Ask for and transparently decode gzip Content-Encoding
+        .arg("--compressed")
+        .args(policy.ca_cert.iter().flat_map(|path| ["--cacert", path.as_str()]))
+        .args(policy.insecure.then_some("--insecure"))
+        .arg(url);
+    curl
+}
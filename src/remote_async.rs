@@ -0,0 +1,164 @@
+//! Async variants of the remote config sources, on tokio, for library callers that
+//! fetch configs from many hosts at once.
+//!
+//! The sources and settings are the same as for the CLI's `--config <url>`, `--host`
+//! and `--container`: downloads and SSH sessions run as `curl` and `ssh` child
+//! processes, with the [`FetchPolicy`]'s timeouts and retries and the [`Pins`]
+//! checked. Any tokio runtime with I/O and time enabled will do:
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use kcc::remote::{FetchPolicy, Pins};
+//! use kcc::remote_async::{self, ConfigSource};
+//! use kcc::{Checker, FlagRequirement};
+//!
+//! async fn check_fleet(hosts: Vec<String>) -> anyhow::Result<()> {
+//!     let policy = Arc::new(FetchPolicy { connect_timeout: 10, read_timeout: 30, retries: 2, backoff_ms: 500, ca_cert: None, insecure: false });
+//!     let pins = Arc::new(Pins::default());
+//!     let requirements = [FlagRequirement::builder("CONFIG_BPF_SYSCALL").build()?];
+//!
+//!     let mut tasks = tokio::task::JoinSet::new();
+//!     for host in hosts {
+//!         let (policy, pins) = (policy.clone(), pins.clone());
+//!         tasks.spawn(async move {
+//!             let source = ConfigSource::Ssh { host, path: None };
+//!             remote_async::load(&source, &policy, &pins).await
+//!         });
+//!     }
+//!     while let Some(config) = tasks.join_next().await {
+//!         let config = config??;
+//!         for (_, result) in Checker::new().check(&config, &requirements) {
+//!             println!("{}: {} {}", config.source(), result.name, result.status.as_str());
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use std::fmt;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use crate::config::{KernelConfig, PROC_CONFIG};
+use crate::remote::{self, FetchPolicy, Pins};
+use crate::{compress, container};
+
+/// Where to load a kernel config from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// An `http://` or `https://` URL
+    Http(String),
+    /// A config on a host reached over SSH, or its running kernel's config when `path` is `None`
+    Ssh { host: String, path: Option<String> },
+    /// The running kernel's config as seen inside a docker or podman container
+    Container(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigSource::Http(url) => write!(f, "{}", url),
+            ConfigSource::Ssh { host, path: Some(path) } => write!(f, "{}:{}", host, path),
+            ConfigSource::Ssh { host, path: None } => write!(f, "{}", host),
+            ConfigSource::Container(name) => write!(f, "{}:{}", name, PROC_CONFIG),
+        }
+    }
+}
+
+/// Load and parse a config from any remote source
+pub async fn load(source: &ConfigSource, policy: &FetchPolicy, pins: &Pins) -> anyhow::Result<KernelConfig> {
+    let (source, content) = match source {
+        ConfigSource::Http(url) => (url.clone(), fetch_config(url, policy, pins).await?),
+        ConfigSource::Ssh { host, path } => fetch_ssh_config(host, path.as_deref(), policy, pins).await?,
+        ConfigSource::Container(name) => fetch_container_config(name).await?,
+    };
+    Ok(KernelConfig::parse(&source, content))
+}
+
+/// Fetch a remote source, retrying transient failures, and check it against the pins
+pub async fn fetch(source: &str, policy: &FetchPolicy, pins: &Pins) -> anyhow::Result<Vec<u8>> {
+    let data = with_retries(source, policy, || run(Command::from(remote::curl_command(source, policy)), "curl")).await?;
+    pins.verify(source, &data)?;
+    Ok(data)
+}
+
+/// Fetch a kernel config from a remote source, retrying transient failures
+pub async fn fetch_config(source: &str, policy: &FetchPolicy, pins: &Pins) -> anyhow::Result<String> {
+    let data = fetch(source, policy, pins).await?;
+    compress::decode_config(&data, source)
+}
+
+/// Fetch a kernel config from `host` over SSH, either `path` or the running
+/// kernel's config; returns the `host:path` it was read from and the content
+pub async fn fetch_ssh_config(
+    host: &str,
+    path: Option<&str>,
+    policy: &FetchPolicy,
+    pins: &Pins,
+) -> anyhow::Result<(String, String)> {
+    let ssh = |command: &str| run(Command::from(remote::ssh_command(host, command, policy)), "ssh");
+    let path = match path {
+        Some(path) => path.to_string(),
+        None => {
+            let found = with_retries(host, policy, || ssh(remote::FIND_CONFIG)).await?;
+            let found = String::from_utf8_lossy(&found).trim().to_string();
+            if found.is_empty() {
+                return Err(anyhow::anyhow!("No kernel config found on {}, pass its path explicitly", host));
+            }
+            found
+        }
+    };
+
+    let source = format!("{}:{}", host, path);
+    let command = remote::cat_command(&path);
+    let data = with_retries(&source, policy, || ssh(&command)).await?;
+    pins.verify(&source, &data)?;
+    let content = compress::decode_config(&data, &path)?;
+    Ok((source, content))
+}
+
+/// Read the running kernel's config inside a container; returns the `container:path`
+/// it was read from and the content
+pub async fn fetch_container_config(name: &str) -> anyhow::Result<(String, String)> {
+    // Resolving the engine takes a couple of short inspect/exec calls, so they run on the blocking pool
+    let name = name.to_string();
+    let data = tokio::task::spawn_blocking({
+        let name = name.clone();
+        move || container::resolve(&name)?.read(PROC_CONFIG)
+    })
+    .await??;
+    Ok((format!("{}:{}", name, PROC_CONFIG), compress::decode_config(&data, PROC_CONFIG)?))
+}
+
+async fn run(mut command: Command, tool: &str) -> anyhow::Result<Vec<u8>> {
+    // Don't leave curl or ssh running when the caller drops the future
+    command.kill_on_drop(true);
+    remote::fetch_output(tool, command.output().await)
+}
+
+/// Run `attempt` until it succeeds, fails for good or the policy's retries are exhausted,
+/// printing a diagnostic for every failed attempt that is retried
+async fn with_retries<T, F, Fut>(source: &str, policy: &FetchPolicy, mut attempt: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let attempts = policy.retries + 1;
+    let mut delay = Duration::from_millis(policy.backoff_ms);
+
+    for n in 1..=attempts {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) if n < attempts && e.is::<remote::Transient>() => {
+                remote::retrying(source, n, attempts, delay, &e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => return Err(remote::gave_up(source, n, e)),
+        }
+    }
+
+    unreachable!("at least one attempt is always made")
+}
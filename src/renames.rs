@@ -0,0 +1,106 @@
+//! Config symbols that were renamed, folded into others or removed, so flag lists
+//! written for older kernels get a pointer to the current option instead of a bare
+//! invalid or missing result.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::expr;
+
+/// What became of a symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// The same option under a new name
+    Renamed(&'static str),
+    /// Merged into a broader option
+    FoldedInto(&'static str),
+    /// Dropped without a successor
+    Removed,
+}
+
+/// A symbol that no longer exists in current kernels
+#[derive(Debug, Clone, Copy)]
+pub struct Rename {
+    /// The old symbol, without its `CONFIG_` prefix
+    pub old: &'static str,
+    pub change: Change,
+    /// The first kernel version without the old symbol
+    pub version: &'static str,
+    /// What to do instead, when there's more to it than the new name
+    pub note: Option<&'static str>,
+}
+
+const fn renamed(old: &'static str, new: &'static str, version: &'static str) -> Rename {
+    Rename { old, change: Change::Renamed(new), version, note: None }
+}
+
+const fn folded(old: &'static str, new: &'static str, version: &'static str) -> Rename {
+    Rename { old, change: Change::FoldedInto(new), version, note: None }
+}
+
+const fn removed(old: &'static str, version: &'static str, note: &'static str) -> Rename {
+    Rename { old, change: Change::Removed, version, note: Some(note) }
+}
+
+pub const RENAMES: &[Rename] = &[
+    renamed("CGROUP_MEM_RES_CTLR", "MEMCG", "3.6"),
+    renamed("CGROUP_MEM_RES_CTLR_SWAP", "MEMCG_SWAP", "3.6"),
+    renamed("CGROUP_MEM_RES_CTLR_KMEM", "MEMCG_KMEM", "3.6"),
+    renamed("NETPRIO_CGROUP", "CGROUP_NET_PRIO", "3.14"),
+    removed("RESOURCE_COUNTERS", "3.19", "memory cgroups use page counters instead"),
+    folded("EXT3_FS", "EXT4_FS", "4.3"),
+    removed("MEMCG_KMEM", "4.6", "kernel memory is always accounted with CONFIG_MEMCG"),
+    removed("DEVPTS_MULTIPLE_INSTANCES", "4.7", "every devpts mount is a separate instance"),
+    renamed("DEBUG_RODATA", "STRICT_KERNEL_RWX", "4.11"),
+    renamed("DEBUG_SET_MODULE_RONX", "STRICT_MODULE_RWX", "4.11"),
+    renamed("CC_STACKPROTECTOR", "STACKPROTECTOR", "4.18"),
+    renamed("CC_STACKPROTECTOR_REGULAR", "STACKPROTECTOR", "4.18"),
+    renamed("CC_STACKPROTECTOR_STRONG", "STACKPROTECTOR_STRONG", "4.18"),
+    folded("NF_CONNTRACK_IPV4", "NF_CONNTRACK", "4.19"),
+    folded("NF_CONNTRACK_IPV6", "NF_CONNTRACK", "4.19"),
+    removed("IOSCHED_CFQ", "5.0", "the legacy block layer is gone, use CONFIG_IOSCHED_BFQ"),
+    removed("IOSCHED_DEADLINE", "5.0", "the legacy block layer is gone, use CONFIG_MQ_IOSCHED_DEADLINE"),
+    folded("NF_NAT_IPV4", "NF_NAT", "5.1"),
+    folded("NF_NAT_IPV6", "NF_NAT", "5.1"),
+    folded("NF_NAT_MASQUERADE_IPV4", "NF_NAT_MASQUERADE", "5.1"),
+    folded("NF_NAT_MASQUERADE_IPV6", "NF_NAT_MASQUERADE", "5.1"),
+    removed("MEMCG_SWAP_ENABLED", "5.8", "swap is accounted by default, swapaccount=0 turns it off"),
+    folded("MEMCG_SWAP", "MEMCG", "6.1"),
+    removed("NET_SCH_ATM", "6.3", "the qdisc was retired"),
+    removed("NET_SCH_CBQ", "6.3", "the qdisc was retired"),
+    removed("NET_SCH_DSMARK", "6.3", "the qdisc was retired"),
+    removed("NET_CLS_RSVP", "6.3", "the classifier was retired"),
+    removed("NET_CLS_RSVP6", "6.3", "the classifier was retired"),
+    removed("NET_CLS_TCINDEX", "6.3", "the classifier was retired"),
+    removed("SLOB", "6.4", "use CONFIG_SLUB"),
+    removed("SECURITY_SELINUX_DISABLE", "6.4", "disable SELinux on the kernel command line instead"),
+    removed("SLAB", "6.8", "use CONFIG_SLUB"),
+];
+
+impl Rename {
+    /// Whether a kernel of this version no longer has the old symbol; unknown versions are assumed current
+    pub fn applies_to(&self, kernel_version: Option<&str>) -> bool {
+        kernel_version.is_none_or(|version| expr::compare_versions(version, self.version) != Ordering::Less)
+    }
+}
+
+impl fmt::Display for Rename {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.change {
+            Change::Renamed(new) => write!(f, "renamed to CONFIG_{} in {}", new, self.version)?,
+            Change::FoldedInto(new) => write!(f, "folded into CONFIG_{} in {}", new, self.version)?,
+            Change::Removed => write!(f, "removed in {}", self.version)?,
+        }
+        match self.note {
+            Some(note) => write!(f, ": {}", note),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What became of a symbol, with or without its `CONFIG_` prefix, if it was renamed or removed
+pub fn find(name: &str) -> Option<&'static Rename> {
+    let name = name.trim();
+    let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+    RENAMES.iter().find(|rename| rename.old == name)
+}
@@ -0,0 +1,833 @@
+//! Structured check results, for the post-hook report and the machine-readable
+//! output formats selected with `--format`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::check::{self, FlagStatus};
+use crate::config::{ConfigValue, KernelConfig};
+use crate::controls::{self, ControlIds};
+use crate::requirement::{self, Checker, FlagRequirement, Severity};
+use crate::spec;
+
+/// How the results of a check are printed
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable output with colors and emoji
+    Text,
+    /// A single JSON document
+    Json,
+    /// A single YAML document, with the same fields as the JSON one
+    Yaml,
+    /// One comma-separated row per result, for spreadsheets
+    Csv,
+    /// One tab-separated row per result
+    Tsv,
+    /// JUnit XML with a test case per result, for CI test reports
+    Junit,
+    /// SARIF 2.1.0 with a finding per failure or warning, for code scanning
+    Sarif,
+    /// Metrics for the node_exporter textfile collector
+    Prometheus,
+    /// Version-stable `status<TAB>name<TAB>value` lines for scripts, same as --porcelain
+    Porcelain,
+}
+
+static HUMAN: AtomicBool = AtomicBool::new(true);
+
+/// Select the output format; anything but text silences the human-readable output
+pub fn set_format(format: OutputFormat) {
+    HUMAN.store(format == OutputFormat::Text, Ordering::Relaxed);
+}
+
+/// Silence the human-readable output, while the results are shown another way like in the terminal UI
+pub fn silence() {
+    HUMAN.store(false, Ordering::Relaxed);
+}
+
+/// Whether the human-readable output is printed
+pub fn human() -> bool {
+    HUMAN.load(Ordering::Relaxed)
+}
+
+/// Status of a result as in the post-hook report: a flag or rule's [`FlagStatus`], or the
+/// outcome of a runtime probe, container, plugin or policy check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResultStatus {
+    Enabled,
+    Module,
+    Missing,
+    Disabled,
+    WrongValue,
+    Invalid,
+    RulePass,
+    RuleFail,
+    AdminDisabled,
+    Off,
+    Forbidden,
+    ProbePass,
+    ProbeFail,
+    ContainerPass,
+    ContainerWarn,
+    ContainerFail,
+    PluginPass,
+    PluginWarn,
+    PluginFail,
+    PolicyPass,
+    PolicyWarn,
+    PolicyFail,
+}
+
+impl ResultStatus {
+    /// The status as written in reports, e.g. `enabled` or `probe-fail`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResultStatus::Enabled => "enabled",
+            ResultStatus::Module => "module",
+            ResultStatus::Missing => "missing",
+            ResultStatus::Disabled => "disabled",
+            ResultStatus::WrongValue => "wrong-value",
+            ResultStatus::Invalid => "invalid",
+            ResultStatus::RulePass => "rule-pass",
+            ResultStatus::RuleFail => "rule-fail",
+            ResultStatus::AdminDisabled => "admin-disabled",
+            ResultStatus::Off => "off",
+            ResultStatus::Forbidden => "forbidden",
+            ResultStatus::ProbePass => "probe-pass",
+            ResultStatus::ProbeFail => "probe-fail",
+            ResultStatus::ContainerPass => "container-pass",
+            ResultStatus::ContainerWarn => "container-warn",
+            ResultStatus::ContainerFail => "container-fail",
+            ResultStatus::PluginPass => "plugin-pass",
+            ResultStatus::PluginWarn => "plugin-warn",
+            ResultStatus::PluginFail => "plugin-fail",
+            ResultStatus::PolicyPass => "policy-pass",
+            ResultStatus::PolicyWarn => "policy-warn",
+            ResultStatus::PolicyFail => "policy-fail",
+        }
+    }
+
+    /// The kind of entry the status comes from: `flags` for flags and rules, else `probes`,
+    /// `container`, `plugins` or `policies`
+    pub fn group(&self) -> &'static str {
+        match self {
+            ResultStatus::Enabled
+            | ResultStatus::Module
+            | ResultStatus::Missing
+            | ResultStatus::Disabled
+            | ResultStatus::WrongValue
+            | ResultStatus::Invalid
+            | ResultStatus::RulePass
+            | ResultStatus::RuleFail
+            | ResultStatus::AdminDisabled
+            | ResultStatus::Off
+            | ResultStatus::Forbidden => "flags",
+            ResultStatus::ProbePass | ResultStatus::ProbeFail => "probes",
+            ResultStatus::ContainerPass | ResultStatus::ContainerWarn | ResultStatus::ContainerFail => "container",
+            ResultStatus::PluginPass | ResultStatus::PluginWarn | ResultStatus::PluginFail => "plugins",
+            ResultStatus::PolicyPass | ResultStatus::PolicyWarn | ResultStatus::PolicyFail => "policies",
+        }
+    }
+
+    /// Whether the status is that of a flag or rule rather than of a probe, container, plugin or policy check
+    pub fn is_flag(&self) -> bool {
+        self.group() == "flags"
+    }
+
+    /// Whether the status is a pass
+    pub fn is_pass(&self) -> bool {
+        match self {
+            ResultStatus::Enabled
+            | ResultStatus::Module
+            | ResultStatus::Off
+            | ResultStatus::RulePass
+            | ResultStatus::ProbePass
+            | ResultStatus::ContainerPass
+            | ResultStatus::PluginPass
+            | ResultStatus::PolicyPass => true,
+            ResultStatus::Missing
+            | ResultStatus::Disabled
+            | ResultStatus::WrongValue
+            | ResultStatus::Invalid
+            | ResultStatus::RuleFail
+            | ResultStatus::AdminDisabled
+            | ResultStatus::Forbidden
+            | ResultStatus::ProbeFail
+            | ResultStatus::ContainerWarn
+            | ResultStatus::ContainerFail
+            | ResultStatus::PluginWarn
+            | ResultStatus::PluginFail
+            | ResultStatus::PolicyWarn
+            | ResultStatus::PolicyFail => false,
+        }
+    }
+
+    /// Whether the status is a warning, which doesn't fail the check
+    pub fn is_warning(&self) -> bool {
+        matches!(self, ResultStatus::ContainerWarn | ResultStatus::PluginWarn | ResultStatus::PolicyWarn)
+    }
+
+    /// Whether the status is a failure
+    pub fn is_failure(&self) -> bool {
+        !self.is_pass() && !self.is_warning()
+    }
+}
+
+impl fmt::Display for ResultStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<FlagStatus> for ResultStatus {
+    fn from(status: FlagStatus) -> Self {
+        match status {
+            FlagStatus::EnabledInKernel => ResultStatus::Enabled,
+            FlagStatus::EnabledAsModule => ResultStatus::Module,
+            FlagStatus::Missing => ResultStatus::Missing,
+            FlagStatus::Disabled => ResultStatus::Disabled,
+            FlagStatus::WrongValue => ResultStatus::WrongValue,
+            FlagStatus::Invalid => ResultStatus::Invalid,
+            FlagStatus::RulePassed => ResultStatus::RulePass,
+            FlagStatus::RuleFailed => ResultStatus::RuleFail,
+            FlagStatus::AdminDisabled => ResultStatus::AdminDisabled,
+            FlagStatus::Off => ResultStatus::Off,
+            FlagStatus::Forbidden => ResultStatus::Forbidden,
+        }
+    }
+}
+
+/// The outcome of a single flag, rule, probe, plugin or policy check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub status: ResultStatus,
+    pub name: String,
+    /// Why a check failed or what a plugin or policy reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Compliance control IDs the check is tagged with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controls: Vec<String>,
+    /// The explanation from the comment above the entry in its flags file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Where to read more about the requirement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The value the spec asks for, `y/m` for a flag listed without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<String>,
+    /// The flags file, `profile:<name>`, `--set-flags` or `dtb:<file>` the entry came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// The flag's value in the config, `n` when it is explicitly unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The `[Section]` of the flags file the entry was listed under
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// How much the flag or rule matters, when less than required
+    #[serde(default, skip_serializing_if = "Severity::is_required")]
+    pub severity: Severity,
+    /// A failure that doesn't fail the check, its severity being below `--fail-on`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub advisory: bool,
+    /// Line of the config that sets the flag, where findings are reported
+    #[serde(skip)]
+    pub line: Option<usize>,
+}
+
+/// Where a checked entry was listed, under which section and what value it was listed with
+pub struct Origin {
+    pub source: String,
+    pub section: Option<String>,
+    pub value: Option<String>,
+}
+
+impl CheckResult {
+    pub fn new(status: impl Into<ResultStatus>, name: &str) -> Self {
+        CheckResult {
+            status: status.into(),
+            name: name.to_string(),
+            detail: None,
+            controls: Vec::new(),
+            reason: None,
+            url: None,
+            required: None,
+            source: None,
+            value: None,
+            section: None,
+            severity: Severity::Required,
+            advisory: false,
+            line: None,
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        self.detail = (!detail.is_empty()).then_some(detail);
+        self
+    }
+
+    pub fn controls<S: AsRef<str>>(mut self, ids: &[S]) -> Self {
+        self.controls = ids.iter().map(|id| id.as_ref().to_string()).collect();
+        self
+    }
+
+    pub fn reason(mut self, reason: Option<&String>) -> Self {
+        self.reason = reason.cloned();
+        self
+    }
+
+    pub fn url(mut self, url: Option<&String>) -> Self {
+        self.url = url.cloned();
+        self
+    }
+
+    /// Record where a flag or rule was listed; rules have no separate required value
+    pub fn origin(mut self, origin: Option<&Origin>, requirement: &FlagRequirement) -> Self {
+        if let Some(origin) = origin {
+            self.source = Some(origin.source.clone());
+            self.section = origin.section.clone();
+            if !requirement.is_rule() {
+                // `!FLAG` is listed without a value, but accepts `n` like `FLAG=n`
+                self.required = Some(match (&origin.value, requirement.accepted.as_slice()) {
+                    (Some(value), _) => value.clone(),
+                    (None, []) => "y/m".to_string(),
+                    (None, accepted) => accepted.join("|"),
+                });
+            }
+        }
+        self
+    }
+
+    /// Record the severity of a flag or rule, and whether its failure is only advisory because
+    /// the severity is below the one that fails the check
+    pub fn severity(mut self, severity: Severity, fail_on: Severity) -> Self {
+        self.severity = severity;
+        self.advisory = severity > fail_on && self.failing_status();
+        self
+    }
+
+    /// Record the line and value of the config entry for the flag
+    pub fn setting(mut self, setting: Option<(usize, String)>) -> Self {
+        if let Some((line, value)) = setting {
+            self.line = Some(line);
+            self.value = Some(value);
+        }
+        self
+    }
+
+    /// Whether the result fails the check (warnings and advisory failures don't)
+    pub fn failed(&self) -> bool {
+        self.failing_status() && !self.advisory
+    }
+
+    /// Whether the result is a warning: a `-warn` status or an advisory failure
+    pub fn warned(&self) -> bool {
+        self.advisory || self.status.is_warning()
+    }
+
+    fn failing_status(&self) -> bool {
+        self.status.is_failure()
+    }
+
+    /// What kind of check produced the result: its source for flags and rules, else the section
+    fn group(&self) -> &str {
+        if let Some(source) = &self.source {
+            return source;
+        }
+        self.status.group()
+    }
+
+    /// A tab-separated post-hook report line, with the control IDs as a third column when there are any
+    pub fn report_line(&self) -> String {
+        let mut line = format!("{}\t{}", self.status, self.name);
+        if let Some(detail) = &self.detail {
+            line.push_str(&format!(": {}", detail));
+        }
+        if !self.controls.is_empty() {
+            line.push_str(&format!("\t{}", self.controls.join(",")));
+        }
+        line
+    }
+}
+
+/// 1-based line and value of the config entry that sets or unsets a flag
+pub fn config_setting(config: &KernelConfig, name: &str) -> Option<(usize, String)> {
+    let value = match config.get(name)? {
+        ConfigValue::NotSet => "n".to_string(),
+        value => value.to_string(),
+    };
+    Some((config.line(name)?, value))
+}
+
+/// Counts of the results by outcome
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Summary {
+    pub checked: usize,
+    pub passed: usize,
+    pub missing: usize,
+    /// Flags that are in the config but not set
+    #[serde(default)]
+    pub disabled: usize,
+    /// Flags that are set to a value that isn't accepted
+    #[serde(default)]
+    pub wrong_value: usize,
+    pub invalid: usize,
+    pub failed_rules: usize,
+    pub admin_disabled: usize,
+    /// Flags that must be disabled but are enabled
+    #[serde(default)]
+    pub forbidden: usize,
+    pub failed_probes: usize,
+    pub failed_container_checks: usize,
+    pub failed_plugin_checks: usize,
+    pub failed_policies: usize,
+    /// Flags and rules that failed with a severity below the one that fails the check
+    #[serde(default)]
+    pub advisory: usize,
+}
+
+/// Everything a check found, as printed by the machine-readable formats and read back with
+/// `serde_json::from_str` or `serde_yaml::from_str`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    /// The config source that was checked, after discovery
+    pub config: String,
+    pub results: Vec<CheckResult>,
+    pub summary: Summary,
+    pub exit_code: i32,
+}
+
+impl Report {
+    pub fn new(config: &str, results: Vec<CheckResult>, exit_code: i32) -> Self {
+        let mut summary = Summary { checked: results.len(), ..Default::default() };
+        for result in &results {
+            if result.advisory {
+                summary.advisory += 1;
+                continue;
+            }
+            match result.status {
+                ResultStatus::Enabled
+                | ResultStatus::Module
+                | ResultStatus::Off
+                | ResultStatus::RulePass
+                | ResultStatus::ProbePass
+                | ResultStatus::ContainerPass
+                | ResultStatus::PluginPass
+                | ResultStatus::PolicyPass => summary.passed += 1,
+                ResultStatus::Missing => summary.missing += 1,
+                ResultStatus::Disabled => summary.disabled += 1,
+                ResultStatus::WrongValue => summary.wrong_value += 1,
+                ResultStatus::Invalid => summary.invalid += 1,
+                ResultStatus::RuleFail => summary.failed_rules += 1,
+                ResultStatus::AdminDisabled => summary.admin_disabled += 1,
+                ResultStatus::Forbidden => summary.forbidden += 1,
+                ResultStatus::ProbeFail => summary.failed_probes += 1,
+                ResultStatus::ContainerFail => summary.failed_container_checks += 1,
+                ResultStatus::PluginFail => summary.failed_plugin_checks += 1,
+                ResultStatus::PolicyFail => summary.failed_policies += 1,
+                // Warnings are neither passes nor failures
+                ResultStatus::ContainerWarn | ResultStatus::PluginWarn | ResultStatus::PolicyWarn => {}
+            }
+        }
+        Report { config: config.to_string(), results, summary, exit_code }
+    }
+
+    /// Check a config against the text of a flags file, or of a YAML or TOML spec when the source
+    /// ends in `.yaml`, `.yml` or `.toml`, and collect the results as the CLI does for a single `--flags-file`, with control IDs,
+    /// reasons and the config values
+    pub fn from_flags(kernel_config: &KernelConfig, source: &str, flags: &str) -> anyhow::Result<Report> {
+        let (flags, metadata) = spec::as_flags_file(source, flags)?;
+        let flags = flags.as_str();
+        let metadata: HashMap<String, spec::Requirement> = metadata
+            .into_iter()
+            .filter_map(|requirement| Some((controls::result_name(requirement.entry()?), requirement)))
+            .collect();
+        let entries = check::parse_flags_file(source, flags)?;
+
+        let mut control_ids = ControlIds::default();
+        control_ids.add_file(flags);
+        let mut reasons = HashMap::new();
+        let mut origins = HashMap::new();
+        if let Ok(parsed) = spec::parse(flags, spec::SpecFormat::Plain) {
+            for requirement in parsed.requirements {
+                let Some(entry) = requirement.rule.or(requirement.flag) else {
+                    continue;
+                };
+                let name = controls::result_name(&entry);
+                if let Some(reason) = requirement.reason {
+                    reasons.entry(name.clone()).or_insert(reason);
+                }
+                let origin = Origin { source: source.to_string(), section: requirement.section, value: requirement.value };
+                origins.entry(name).or_insert(origin);
+            }
+        }
+
+        let mut requirements = entries
+            .iter()
+            .map(|entry| {
+                let name = controls::result_name(entry);
+                let mut builder = match metadata.get(&name) {
+                    Some(requirement) => requirement.builder()?,
+                    None => FlagRequirement::builder(entry),
+                }
+                .source(source);
+                for id in control_ids.get(&name) {
+                    builder = builder.control(id);
+                }
+                if let Some(reason) = reasons.get(&name) {
+                    builder = builder.reason(reason);
+                }
+                if let Some(section) = origins.get(&name).and_then(|origin| origin.section.as_deref()) {
+                    builder = builder.section(section);
+                }
+                builder.build()
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        requirement::group_by_section(&mut requirements);
+
+        let mut exit_code = 0;
+        let mut results = Vec::new();
+        for (requirement, result) in Checker::new().check(kernel_config, &requirements) {
+            let checked = CheckResult::new(result.status, &result.name)
+                .controls(&requirement.controls)
+                .reason(requirement.reason.as_ref())
+                .url(requirement.url.as_ref())
+                .origin(origins.get(&result.name), requirement)
+                .setting(config_setting(kernel_config, &result.name))
+                .severity(requirement.severity, Severity::Required);
+            // Recommended and optional requirements don't fail the check
+            if result.status == FlagStatus::Forbidden && !checked.advisory {
+                exit_code = 5;
+            } else if exit_code == 0 && checked.failed() {
+                exit_code = 1;
+            }
+            results.push(checked);
+        }
+        Ok(Self::new(kernel_config.source(), results, exit_code))
+    }
+
+    /// Render the report in a machine-readable format
+    pub fn render(&self, format: OutputFormat) -> anyhow::Result<String> {
+        match format {
+            OutputFormat::Text => Ok(self.results.iter().map(|result| result.report_line() + "\n").collect()),
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(self)? + "\n"),
+            OutputFormat::Yaml => Ok(serde_yaml::to_string(self)?),
+            OutputFormat::Csv => Ok(self.table(',')),
+            OutputFormat::Tsv => Ok(self.table('\t')),
+            OutputFormat::Junit => Ok(self.junit()),
+            OutputFormat::Sarif => Ok(serde_json::to_string_pretty(&self.sarif())? + "\n"),
+            OutputFormat::Prometheus => Ok(self.prometheus()),
+            OutputFormat::Porcelain => Ok(self.porcelain()),
+        }
+    }
+
+    /// name, status, required, source, detail, controls and reason columns with a header row
+    fn table(&self, separator: char) -> String {
+        let header = ["name", "status", "required", "source", "detail", "controls", "reason"];
+        let mut out = header.join(&separator.to_string()) + "\n";
+        for result in &self.results {
+            let fields = [
+                result.name.as_str(),
+                result.status.as_str(),
+                result.required.as_deref().unwrap_or(""),
+                result.source.as_deref().unwrap_or(""),
+                result.detail.as_deref().unwrap_or(""),
+                &result.controls.join(";"),
+                result.reason.as_deref().unwrap_or(""),
+            ];
+            let row: Vec<String> = fields
+                .iter()
+                .map(|field| if separator == '\t' { tsv_field(field) } else { csv_field(field) })
+                .collect();
+            out.push_str(&row.join(&separator.to_string()));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A single test suite with a test case per result, failing ones carrying a <failure>
+    fn junit(&self) -> String {
+        let failures = self.results.iter().filter(|result| result.failed()).count();
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str(&format!(
+            "<testsuites name=\"kcc\" tests=\"{}\" failures=\"{}\" errors=\"0\">\n",
+            self.results.len(),
+            failures
+        ));
+        out.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+            xml_escape(&self.config),
+            self.results.len(),
+            failures
+        ));
+        for result in &self.results {
+            // Test report viewers nest dotted class names, putting each section under its file
+            let group = match &result.section {
+                Some(section) => format!("{}.{}", result.group(), section),
+                None => result.group().to_string(),
+            };
+            out.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                xml_escape(&group),
+                xml_escape(&result.name)
+            ));
+
+            let mut details = Vec::new();
+            if let Some(required) = &result.required {
+                details.push(format!("required: {}", required));
+            }
+            if let Some(detail) = &result.detail {
+                details.push(detail.clone());
+            }
+            if !result.controls.is_empty() {
+                details.push(format!("controls: {}", result.controls.join(", ")));
+            }
+            if let Some(reason) = &result.reason {
+                details.push(format!("reason: {}", reason));
+            }
+            if let Some(url) = &result.url {
+                details.push(format!("see: {}", url));
+            }
+            if !result.severity.is_required() {
+                details.push(format!("severity: {}", result.severity.as_str()));
+            }
+            if !result.failed() && details.is_empty() {
+                out.push_str("/>\n");
+                continue;
+            }
+
+            out.push_str(">\n");
+            if result.failed() {
+                let message = match &result.detail {
+                    Some(detail) => format!("{}: {}", result.status, detail),
+                    None => result.status.to_string(),
+                };
+                out.push_str(&format!(
+                    "      <failure message=\"{}\" type=\"{}\">{}</failure>\n",
+                    xml_escape(&message),
+                    xml_escape(result.status.as_str()),
+                    xml_escape(&details.join("\n"))
+                ));
+            } else {
+                out.push_str(&format!("      <system-out>{}</system-out>\n", xml_escape(&details.join("\n"))));
+            }
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n</testsuites>\n");
+        out
+    }
+
+    /// Porcelain v1: one `status<TAB>name<TAB>value` line per result, where value is the flag's
+    /// value in the config or the detail of other checks, empty when there is none
+    fn porcelain(&self) -> String {
+        let mut out = String::new();
+        for result in &self.results {
+            let value = result.value.as_deref().or(result.detail.as_deref()).unwrap_or("");
+            out.push_str(&format!("{}\t{}\t{}\n", result.status, tsv_field(&result.name), tsv_field(value)));
+        }
+        out
+    }
+
+    /// Gauges in the Prometheus text format: the verdict, counts by outcome, and one series per result
+    fn prometheus(&self) -> String {
+        let mut out = String::new();
+        let time = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+        out.push_str("# HELP kcc_info The checked config and the kcc version.\n# TYPE kcc_info gauge\n");
+        out.push_str(&format!(
+            "kcc_info{{config=\"{}\",version=\"{}\"}} 1\n",
+            label_value(&self.config),
+            env!("CARGO_PKG_VERSION")
+        ));
+        out.push_str("# HELP kcc_check_success Whether every requirement was met.\n# TYPE kcc_check_success gauge\n");
+        out.push_str(&format!("kcc_check_success {}\n", u8::from(self.exit_code == 0)));
+        out.push_str("# HELP kcc_last_run_timestamp_seconds When the check ran.\n# TYPE kcc_last_run_timestamp_seconds gauge\n");
+        out.push_str(&format!("kcc_last_run_timestamp_seconds {}\n", time));
+
+        out.push_str(
+            "# HELP kcc_results Number of results by outcome; failures of recommended or optional entries \
+             are only counted as advisory, not as missing, disabled or any other failure.\n# TYPE kcc_results gauge\n",
+        );
+        let summary = &self.summary;
+        for (outcome, count) in [
+            ("checked", summary.checked),
+            ("passed", summary.passed),
+            ("missing", summary.missing),
+            ("disabled", summary.disabled),
+            ("wrong_value", summary.wrong_value),
+            ("invalid", summary.invalid),
+            ("failed_rules", summary.failed_rules),
+            ("admin_disabled", summary.admin_disabled),
+            ("forbidden", summary.forbidden),
+            ("failed_probes", summary.failed_probes),
+            ("failed_container_checks", summary.failed_container_checks),
+            ("failed_plugin_checks", summary.failed_plugin_checks),
+            ("failed_policies", summary.failed_policies),
+            ("advisory", summary.advisory),
+        ] {
+            out.push_str(&format!("kcc_results{{outcome=\"{}\"}} {}\n", outcome, count));
+        }
+
+        // Flags and rules are labeled by name, probes, container, plugin and policy checks by check;
+        // an entry listed twice is only exported once, duplicate series break the collector
+        let mut seen = Vec::new();
+        let unique: Vec<&CheckResult> = self
+            .results
+            .iter()
+            .filter(|result| {
+                let first = !seen.contains(&result.name.as_str());
+                seen.push(result.name.as_str());
+                first
+            })
+            .collect();
+        let (flags, checks): (Vec<&CheckResult>, Vec<&CheckResult>) = unique.into_iter().partition(|result| result.status.is_flag());
+        if !flags.is_empty() {
+            out.push_str(
+                "# HELP kcc_flag_status Status of each required flag or rule, 1 for the status it has: builtin, module, off, \
+                 missing, disabled, wrong-value, invalid, admin-disabled, forbidden, rule-pass or rule-fail.\n",
+            );
+            out.push_str("# TYPE kcc_flag_status gauge\n");
+            for result in flags {
+                out.push_str(&format!(
+                    "kcc_flag_status{{flag=\"{}\",status=\"{}\"}} 1\n",
+                    label_value(&result.name),
+                    flag_status_label(result.status)
+                ));
+            }
+        }
+        if !checks.is_empty() {
+            out.push_str("# HELP kcc_check_status Status of each runtime, container, plugin or policy check.\n");
+            out.push_str("# TYPE kcc_check_status gauge\n");
+            for result in checks {
+                out.push_str(&format!(
+                    "kcc_check_status{{check=\"{}\",status=\"{}\"}} 1\n",
+                    label_value(&result.name),
+                    result.status
+                ));
+            }
+        }
+        out
+    }
+
+    /// A SARIF log with a rule per failed or warning entry and a result per finding, located in the config
+    fn sarif(&self) -> serde_json::Value {
+        let findings: Vec<&CheckResult> =
+            self.results.iter().filter(|result| result.failed() || result.warned()).collect();
+
+        let mut rules: Vec<serde_json::Value> = Vec::new();
+        let mut rule_ids: Vec<&str> = Vec::new();
+        for result in &findings {
+            if rule_ids.contains(&result.name.as_str()) {
+                continue;
+            }
+            rule_ids.push(&result.name);
+            let description = match (&result.reason, &result.required) {
+                (Some(reason), _) => reason.clone(),
+                (None, Some(required)) => format!("{} must be set to {}", result.name, required),
+                (None, None) => result.name.clone(),
+            };
+            let mut rule = serde_json::json!({
+                "id": result.name,
+                "shortDescription": { "text": description },
+                "properties": { "tags": result.controls },
+            });
+            if let Some(source) = &result.source {
+                rule["properties"]["source"] = source.as_str().into();
+            }
+            if let Some(url) = &result.url {
+                rule["helpUri"] = url.as_str().into();
+            }
+            rules.push(rule);
+        }
+
+        let results: Vec<serde_json::Value> = findings
+            .iter()
+            .map(|result| {
+                let level = match result.status {
+                    ResultStatus::Invalid => "warning",
+                    _ if result.warned() => "warning",
+                    _ => "error",
+                };
+                let mut message = format!("{} is {}", result.name, result.status);
+                if let Some(detail) = &result.detail {
+                    message.push_str(&format!(": {}", detail));
+                }
+                if let Some(required) = &result.required {
+                    message.push_str(&format!(" (required: {})", required));
+                }
+
+                let mut location = serde_json::json!({ "artifactLocation": { "uri": self.config } });
+                if let Some(line) = result.line {
+                    location["region"] = serde_json::json!({ "startLine": line });
+                }
+                serde_json::json!({
+                    "ruleId": result.name,
+                    "ruleIndex": rule_ids.iter().position(|id| *id == result.name),
+                    "level": level,
+                    "message": { "text": message },
+                    "locations": [{ "physicalLocation": location }],
+                    "properties": { "status": result.status, "controls": result.controls },
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "kcc",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    }
+                },
+                "artifacts": [{ "location": { "uri": self.config } }],
+                "results": results,
+            }]
+        })
+    }
+}
+
+/// The `status` label of `kcc_flag_status`: `builtin` for built-in options, other statuses as in the results
+fn flag_status_label(status: ResultStatus) -> &'static str {
+    match status {
+        ResultStatus::Enabled => "builtin",
+        status => status.as_str(),
+    }
+}
+
+/// Escape a Prometheus label value
+fn label_value(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Quote a field that contains a separator, quote or line break (RFC 4180)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// TSV has no quoting, so tabs and line breaks become spaces
+fn tsv_field(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
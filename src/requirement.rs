@@ -0,0 +1,378 @@
+//! Requirements a kernel config is checked against, and the checker that applies them.
+//!
+//! The CLI turns flags files, profiles and `--set-flags` into [`FlagRequirement`]s
+//! and checks them with a [`Checker`]; other tools can build requirements directly:
+//!
+//! ```
+//! use kcc::{Checker, FlagRequirement, KernelConfig, Severity};
+//!
+//! let config = KernelConfig::parse("inline", "CONFIG_BPF=y\nCONFIG_HZ=250\n");
+//! let requirements = [
+//!     FlagRequirement::builder("CONFIG_BPF").reason("eBPF programs").build()?,
+//!     FlagRequirement::builder("CONFIG_HZ").accepts("1000").severity(Severity::Recommended).build()?,
+//! ];
+//! for (requirement, result) in Checker::new().check(&config, &requirements) {
+//!     println!("{} ({:?}): {}", result.name, requirement.severity, result.status.as_str());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::backend::{CheckSource, ConfigCheck, SourceKind};
+use crate::check::{self, FlagCheckResult};
+use crate::config::KernelConfig;
+use crate::kconfig::KnownSymbols;
+use crate::{arch, expr};
+
+/// How much a requirement matters, from most to least; marked in flags files with a trailing
+/// `!recommended` or `!optional`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Fails the check when not met
+    #[default]
+    Required,
+    /// Only fails the check with `--fail-on recommended` or `optional`
+    Recommended,
+    /// Only fails the check with `--fail-on optional`
+    Optional,
+}
+
+impl Severity {
+    /// The severity as written in flags files and reports, e.g. `recommended`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Required => "required",
+            Severity::Recommended => "recommended",
+            Severity::Optional => "optional",
+        }
+    }
+
+    /// The severity of a flags file marker like `!recommended`
+    pub fn from_marker(marker: &str) -> Option<Self> {
+        match marker.strip_prefix('!')? {
+            "required" => Some(Severity::Required),
+            "recommended" => Some(Severity::Recommended),
+            "optional" => Some(Severity::Optional),
+            _ => None,
+        }
+    }
+
+    pub fn is_required(&self) -> bool {
+        *self == Severity::Required
+    }
+}
+
+/// A flag or rule a config is checked against, with what is known about it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlagRequirement {
+    /// `CONFIG_FOO` or a rule expression as written; for runtime requirements the
+    /// parameter, sysctl, sysfs path or module name
+    pub name: String,
+    /// What the requirement is checked against
+    #[serde(default, skip_serializing_if = "SourceKind::is_config")]
+    pub kind: SourceKind,
+    /// Values that satisfy a flag, as written in a config (`y`, `m`, `1000`, `"-custom"`), or
+    /// numeric bounds like `>=64`; empty when built in or module will do
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accepted: Vec<String>,
+    #[serde(default)]
+    pub severity: Severity,
+    /// Why the requirement exists, shown with failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Where to read more about the requirement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Compliance control IDs the requirement is tagged with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub controls: Vec<String>,
+    /// Where the requirement was listed: a flags file, `profile:<name>` or `--set-flags`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Oldest kernel version the requirement applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_kernel: Option<String>,
+    /// Newest kernel version the requirement applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_kernel: Option<String>,
+    /// Architectures the requirement applies to; empty for all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arch: Vec<String>,
+    /// A flag or rule expression the requirement depends on; it is only checked when the condition holds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<String>,
+    /// The `[Section]` of its flags file the requirement is grouped under in the check output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+}
+
+impl FlagRequirement {
+    /// A flag (with or without its `CONFIG_` prefix) or rule expression that must be satisfied;
+    /// a flag given as `FLAG=value` only accepts that value, or either of `FLAG=m|y`, and
+    /// `!FLAG` or `FLAG=n` requires it disabled; `ENTRY depends on CONDITION` is only checked when
+    /// the condition holds, and `ENTRY @ x86_64,arm64` only on those architectures; a trailing
+    /// `!recommended` or `!optional` lowers the severity
+    pub fn new(entry: &str) -> Self {
+        let severity = check::severity_marker(entry).map(|(_, severity)| severity).unwrap_or_default();
+        let depends_on = check::dependency(entry).map(|(_, condition)| condition.to_string());
+        let arch = check::arch_qualifier(entry).map(|(_, arches)| arches.into_iter().map(str::to_string).collect());
+        let entry = check::unqualified(entry);
+        let mut accepted = Vec::new();
+        let name = match check::setting(entry) {
+            Some((name, value)) => {
+                accepted.extend(check::accepted_values(value).into_iter().map(str::to_string));
+                format!("CONFIG_{}", name.strip_prefix("CONFIG_").unwrap_or(name))
+            }
+            None if check::is_rule(entry) => entry.trim().to_string(),
+            None => format!("CONFIG_{}", entry.trim().strip_prefix("CONFIG_").unwrap_or(entry.trim())),
+        };
+        FlagRequirement {
+            name,
+            kind: SourceKind::Config,
+            accepted,
+            severity,
+            reason: None,
+            url: None,
+            controls: Vec::new(),
+            source: None,
+            min_kernel: None,
+            max_kernel: None,
+            arch: arch.unwrap_or_default(),
+            depends_on,
+            section: None,
+        }
+    }
+
+    /// A setting of the running system that must be on, checked by the source of that kind
+    pub fn for_source(kind: SourceKind, name: &str) -> Self {
+        if kind.is_config() {
+            return FlagRequirement::new(name);
+        }
+        FlagRequirement { name: name.trim().to_string(), kind, ..FlagRequirement::new("") }
+    }
+
+    pub fn builder(name: &str) -> FlagRequirementBuilder {
+        FlagRequirementBuilder { requirement: FlagRequirement::new(name) }
+    }
+
+    pub fn builder_for(kind: SourceKind, name: &str) -> FlagRequirementBuilder {
+        FlagRequirementBuilder { requirement: FlagRequirement::for_source(kind, name) }
+    }
+
+    /// A value that satisfies the requirement, for setting a flag that fails it; `None`
+    /// when built in or module will do
+    pub fn satisfying_value(&self) -> Option<String> {
+        check::satisfying_value(&self.accepted)
+    }
+
+    /// Whether the condition the requirement depends on holds in the config, or it has none
+    pub fn dependency_met(&self, config: &KernelConfig) -> bool {
+        // Conditions are validated when built, so one that doesn't parse is never met
+        self.depends_on.as_deref().is_none_or(|condition| {
+            expr::parse(condition).is_ok_and(|rule| expr::evaluate(&rule, &expr::Context::new(config)))
+        })
+    }
+
+    /// Whether this is a rule expression rather than a single flag
+    pub fn is_rule(&self) -> bool {
+        self.kind.is_config() && check::is_rule(&self.name)
+    }
+
+    /// Whether the kernel version and architecture of the config are within the requirement's constraints
+    pub fn applies_to(&self, config: &KernelConfig) -> bool {
+        self.applies_to_arch(config, None) && self.applies_to_kernel(config)
+    }
+
+    /// Whether the requirement is for the architecture of the config, taken to be built for
+    /// `target` rather than the architecture detected from its symbols when given
+    pub fn applies_to_arch(&self, config: &KernelConfig, target: Option<&str>) -> bool {
+        if self.arch.is_empty() {
+            return true;
+        }
+        match target.map(arch::normalize).or_else(|| arch::detect_config_arch(config)) {
+            Some(config_arch) => self.arch.iter().any(|a| arch::normalize(a) == config_arch),
+            None => true,
+        }
+    }
+
+    /// Whether the kernel version in the config's header is within `min_kernel` and `max_kernel`;
+    /// without a version there's nothing to rule the requirement out
+    pub fn applies_to_kernel(&self, config: &KernelConfig) -> bool {
+        if self.min_kernel.is_none() && self.max_kernel.is_none() {
+            return true;
+        }
+        let Some(version) = expr::kernel_version(config.text()) else {
+            return true;
+        };
+        let too_old = self.min_kernel.as_deref().is_some_and(|min| expr::compare_versions(&version, min) == Ordering::Less);
+        let too_new = self.max_kernel.as_deref().is_some_and(|max| expr::compare_versions(&version, max) == Ordering::Greater);
+        !too_old && !too_new
+    }
+}
+
+/// Builds a [`FlagRequirement`], checking rule expressions when done
+pub struct FlagRequirementBuilder {
+    requirement: FlagRequirement,
+}
+
+impl FlagRequirementBuilder {
+    /// Accept this value; can be called several times
+    pub fn accepts(mut self, value: &str) -> Self {
+        self.requirement.accepted.push(value.to_string());
+        self
+    }
+
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.requirement.severity = severity;
+        self
+    }
+
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.requirement.reason = Some(reason.to_string());
+        self
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.requirement.url = Some(url.to_string());
+        self
+    }
+
+    /// Tag the requirement with a compliance control ID; can be called several times
+    pub fn control(mut self, id: &str) -> Self {
+        self.requirement.controls.push(id.to_string());
+        self
+    }
+
+    pub fn source(mut self, source: &str) -> Self {
+        self.requirement.source = Some(source.to_string());
+        self
+    }
+
+    pub fn section(mut self, section: &str) -> Self {
+        self.requirement.section = Some(section.to_string());
+        self
+    }
+
+    pub fn min_kernel(mut self, version: &str) -> Self {
+        self.requirement.min_kernel = Some(version.to_string());
+        self
+    }
+
+    pub fn max_kernel(mut self, version: &str) -> Self {
+        self.requirement.max_kernel = Some(version.to_string());
+        self
+    }
+
+    /// Only check the requirement when this flag or rule expression holds
+    pub fn depends_on(mut self, condition: &str) -> Self {
+        self.requirement.depends_on = Some(condition.trim().to_string());
+        self
+    }
+
+    /// Only apply the requirement on this architecture; can be called several times
+    pub fn arch(mut self, arch: &str) -> Self {
+        self.requirement.arch.push(arch.to_string());
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<FlagRequirement> {
+        for arch in &self.requirement.arch {
+            arch::parse(arch)?;
+        }
+        if let Some(condition) = &self.requirement.depends_on {
+            expr::parse(condition)?;
+        }
+        if self.requirement.is_rule() {
+            expr::parse(&self.requirement.name)?;
+            if !self.requirement.accepted.is_empty() {
+                return Err(anyhow::anyhow!("A rule can't have accepted values: {}", self.requirement.name));
+            }
+        }
+        Ok(self.requirement)
+    }
+}
+
+/// Bring the requirements of each section together, in the order the sections first appear; those
+/// outside any section come first, and the order within a section is kept
+pub fn group_by_section(requirements: &mut [FlagRequirement]) {
+    let mut sections: Vec<Option<String>> = vec![None];
+    for requirement in requirements.iter() {
+        if !sections.contains(&requirement.section) {
+            sections.push(requirement.section.clone());
+        }
+    }
+    requirements.sort_by_key(|requirement| sections.iter().position(|section| *section == requirement.section));
+}
+
+/// Checks configs against requirements, handing each one to the source of its kind
+#[derive(Default)]
+pub struct Checker {
+    validation: Option<Box<dyn KnownSymbols>>,
+    arch: Option<String>,
+    sources: Vec<Box<dyn CheckSource>>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        Checker::default()
+    }
+
+    /// Report flags that don't exist in these symbols, a Kconfig database or a config (usually
+    /// the running kernel's), as invalid instead of missing
+    pub fn validate_against(mut self, symbols: impl KnownSymbols + 'static) -> Self {
+        self.validation = Some(Box::new(symbols));
+        self
+    }
+
+    /// The symbols flags are validated against, if any
+    pub fn validation(&self) -> Option<&dyn KnownSymbols> {
+        self.validation.as_deref()
+    }
+
+    /// Take configs to be built for this architecture when deciding which requirements apply,
+    /// instead of detecting it from their symbols
+    pub fn arch(mut self, arch: &str) -> Self {
+        self.arch = Some(arch::normalize(arch));
+        self
+    }
+
+    /// Check requirements of the source's kind against it, replacing any source of the same kind
+    pub fn source(mut self, source: impl CheckSource + 'static) -> Self {
+        self.sources.retain(|registered| registered.kind() != source.kind());
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Check every requirement that applies to the config, in order
+    pub fn check<'r>(
+        &self,
+        config: &KernelConfig,
+        requirements: &'r [FlagRequirement],
+    ) -> Vec<(&'r FlagRequirement, FlagCheckResult)> {
+        requirements
+            .iter()
+            .filter_map(|requirement| Some((requirement, self.check_one(config, requirement)?)))
+            .collect()
+    }
+
+    /// Check a single requirement, `None` when it doesn't apply to the config, its
+    /// dependency isn't met or there's no source of its kind
+    pub fn check_one(&self, config: &KernelConfig, requirement: &FlagRequirement) -> Option<FlagCheckResult> {
+        if !requirement.applies_to_arch(config, self.arch.as_deref())
+            || !requirement.applies_to_kernel(config)
+            || !requirement.dependency_met(config)
+        {
+            return None;
+        }
+        if requirement.kind.is_config() {
+            return Some(ConfigCheck { config, validation: self.validation.as_deref() }.check(requirement));
+        }
+        let source = self.sources.iter().find(|source| source.kind() == requirement.kind)?;
+        Some(source.check(requirement))
+    }
+}
@@ -0,0 +1,114 @@
+//! Finding config options with a fuzzy query, the way fzf does, for `kcc search`.
+//!
+//! Each word of the query must match an option's name without its `CONFIG_`
+//! prefix, or its Kconfig prompt, with its characters appearing in order but not
+//! necessarily next to each other, ignoring case: `nfct` finds `NF_CONNTRACK`.
+//! Characters next to each other and at the start of a word of the name score
+//! higher, so the best matches come first, and shorter names win ties.
+//!
+//! ```no_run
+//! let config = kcc::KernelConfig::load("/proc/config.gz")?;
+//! let candidates = kcc::search::candidates(&config, None);
+//! for candidate in kcc::search::rank(&candidates, "bpf jit").iter().take(5) {
+//!     println!("{} = {}", candidate.name, candidate.value.as_deref().unwrap_or("(not in the config)"));
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::kconfig::KconfigDb;
+use crate::{KernelConfig, Tristate};
+
+/// An option that can be searched for
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The option, with its `CONFIG_` prefix
+    pub name: String,
+    /// The value in the config as written, `n` when it is not set, none when the config doesn't mention it
+    pub value: Option<String>,
+    pub prompt: Option<String>,
+    pub help: Option<String>,
+    /// The Kconfig files declaring the option
+    pub files: Vec<String>,
+}
+
+/// The options of a config and of its Kconfig symbols, sorted by name
+pub fn candidates(config: &KernelConfig, kconfig: Option<&KconfigDb>) -> Vec<Candidate> {
+    let mut candidates: BTreeMap<String, Candidate> = BTreeMap::new();
+    let candidate = |name: String| Candidate { name, value: None, prompt: None, help: None, files: Vec::new() };
+    for (name, value) in config.iter() {
+        let value = match value.tristate() {
+            Some(Tristate::No) => "n".to_string(),
+            _ => value.to_string(),
+        };
+        candidates.entry(name.to_string()).or_insert_with(|| candidate(name.to_string())).value = Some(value);
+    }
+    for (name, symbol) in kconfig.iter().flat_map(|db| &db.symbols) {
+        let name = format!("CONFIG_{}", name);
+        let entry = candidates.entry(name.clone()).or_insert_with(|| candidate(name));
+        entry.prompt = symbol.prompt.clone();
+        entry.help = symbol.help.clone();
+        entry.files = symbol.files.clone();
+    }
+    candidates.into_values().collect()
+}
+
+/// How well a word of a query matches a text, none when its characters don't all appear in order
+pub fn score(word: &str, text: &str) -> Option<i64> {
+    let word: Vec<char> = word.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let first = *word.first()?;
+    // Matching greedily from each place the first character appears finds the tightest match
+    (0..text.len())
+        .filter(|&start| text[start] == first)
+        .filter_map(|start| {
+            let mut score = 0;
+            let mut next = 0;
+            let mut previous: Option<usize> = None;
+            for (index, &c) in text.iter().enumerate().skip(start) {
+                if next == word.len() {
+                    break;
+                }
+                if c != word[next] {
+                    continue;
+                }
+                score += 16;
+                match previous {
+                    Some(previous) if previous + 1 == index => score += 8,
+                    Some(previous) => score -= (index - previous - 1) as i64,
+                    None => {}
+                }
+                if index == 0 || !text[index - 1].is_alphanumeric() {
+                    score += 8;
+                }
+                previous = Some(index);
+                next += 1;
+            }
+            (next == word.len()).then_some(score)
+        })
+        .max()
+}
+
+/// The candidates matching every word of a query, best first; all of them, by name, for an empty query
+pub fn rank<'a>(candidates: &'a [Candidate], query: &str) -> Vec<&'a Candidate> {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.is_empty() {
+        return candidates.iter().collect();
+    }
+    let mut ranked: Vec<(i64, &Candidate)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let name = candidate.name.strip_prefix("CONFIG_").unwrap_or(&candidate.name);
+            let prompt = candidate.prompt.as_deref().unwrap_or_default();
+            // A match in the prompt counts, but less than one in the name
+            let scores: Option<Vec<i64>> =
+                words.iter().map(|word| score(word, name).max(score(word, prompt).map(|score| score / 2))).collect();
+            Some((scores?.iter().sum(), candidate))
+        })
+        .collect();
+    ranked.sort_by(|(a, a_candidate), (b, b_candidate)| {
+        b.cmp(a).then(a_candidate.name.len().cmp(&b_candidate.name.len())).then(a_candidate.name.cmp(&b_candidate.name))
+    });
+    ranked.into_iter().map(|(_, candidate)| candidate).collect()
+}
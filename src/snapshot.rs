@@ -0,0 +1,117 @@
+//! Baseline snapshots of a config, to detect when a kernel drifts from a known
+//! state.
+//!
+//! A snapshot records the options of a config, or those matching some patterns,
+//! with a SHA-256 digest over them. Verifying a config selects the same options and
+//! compares them with the recorded ones; a snapshot edited by hand no longer matches
+//! its digest and is refused.
+//!
+//! ```no_run
+//! use kcc::snapshot::Snapshot;
+//!
+//! let baseline = Snapshot::load("kernel.lock")?;
+//! let config = kcc::KernelConfig::load("/proc/config.gz")?;
+//! let drift = baseline.verify(&config);
+//! println!("{} options drifted", drift.len());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{self, Change};
+use crate::pattern::{self, Pattern};
+use crate::{expr, remote, ConfigValue, KernelConfig, Tristate};
+
+/// The recorded options of a config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Where the config came from
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kernel_version: Option<String>,
+    /// The prefixes and globs the options were selected with, none for the full config
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    /// SHA-256 over the options as `CONFIG_X=value` lines, sorted by name
+    pub sha256: String,
+    /// The options and their values as a config writes them, `n` when they are not set
+    pub options: BTreeMap<String, String>,
+}
+
+impl Snapshot {
+    /// Record the options of a config selected by the patterns, or all of them without patterns
+    pub fn capture(config: &KernelConfig, patterns: &[String]) -> Self {
+        let options = selected(config, patterns);
+        Snapshot {
+            source: config.source().to_string(),
+            kernel_version: expr::kernel_version(config.text()),
+            patterns: patterns.to_vec(),
+            sha256: digest(&options),
+            options,
+        }
+    }
+
+    /// Read a snapshot, refusing one whose options don't match its digest
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        let snapshot: Snapshot =
+            serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("{} is not a kcc snapshot: {}", path, e))?;
+        if digest(&snapshot.options) != snapshot.sha256 {
+            return Err(anyhow::anyhow!("The options in {} don't match its sha256, it was modified after the snapshot", path));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self).expect("snapshot serializes");
+        fs::write(path, json + "\n").map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))
+    }
+
+    /// The options of a config that differ from the snapshot, selected with the snapshot's patterns
+    pub fn verify(&self, config: &KernelConfig) -> Vec<Change> {
+        let recorded = as_config(&self.source, &self.options);
+        let current = as_config(config.source(), &selected(config, &self.patterns));
+        diff::diff(&recorded, &current)
+    }
+
+    /// The digest the same options of another config would get, to tell at a glance whether it drifted
+    pub fn digest_of(&self, config: &KernelConfig) -> String {
+        digest(&selected(config, &self.patterns))
+    }
+}
+
+/// The options of a config selected by the patterns, with their values as written
+fn selected(config: &KernelConfig, patterns: &[String]) -> BTreeMap<String, String> {
+    let patterns: Vec<Pattern> = patterns.iter().map(|pattern| Pattern::new(pattern)).collect();
+    config
+        .iter()
+        .filter(|(name, _)| pattern::matches_any(&patterns, name))
+        .map(|(name, value)| {
+            let value = match value.tristate() {
+                Some(Tristate::No) => "n".to_string(),
+                _ => value.to_string(),
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+fn digest(options: &BTreeMap<String, String>) -> String {
+    let lines: String = options.iter().map(|(name, value)| format!("{}={}\n", name, value)).collect();
+    remote::sha256_hex(lines.as_bytes())
+}
+
+/// Recorded options as a config, to compare them with the diff of two configs
+fn as_config(source: &str, options: &BTreeMap<String, String>) -> KernelConfig {
+    let lines: String = options
+        .iter()
+        .map(|(name, value)| match ConfigValue::parse(value) {
+            ConfigValue::Bool(false) => format!("# {} is not set\n", name),
+            _ => format!("{}={}\n", name, value),
+        })
+        .collect();
+    KernelConfig::parse(source, lines)
+}
@@ -0,0 +1,623 @@
+//! Requirement specs in formats other than plain flags files.
+//!
+//! The structured spec used for YAML and TOML holds a list of requirements,
+//! each either a flag (optionally with the values it accepts, as `m|y`) or a
+//! rule expression, plus an optional reason, a reference URL, the kernel
+//! versions and architectures it applies to, the condition it depends on, its
+//! severity and the section it is reported in:
+//!
+//! ```yaml
+//! version: 1
+//! min_kcc: 0.1.1
+//! description: Container host
+//! requirements:
+//!   - flag: NAMESPACES
+//!   - flag: CGROUPS
+//!     value: m
+//!   - rule: KERNEL>=5.10
+//!     reason: cgroup v2 freezer
+//!   - flag: X86_INTEL_TSX
+//!     arch: [x86_64]
+//!   - flag: BPF_JIT
+//!     depends_on: BPF_SYSCALL
+//!   - flag: BPF_LSM
+//!     severity: recommended
+//!   - flag: NF_TABLES
+//!     section: Networking
+//!   - flag: IO_URING
+//!     min_kernel: "5.1"
+//!     url: https://kernel.dk/io_uring.pdf
+//! ```
+//!
+//! YAML and TOML specs can be passed to `--flags` directly, told from flags
+//! files by their extension. A YAML file holding just a list of flags file
+//! entries is read as that flags file:
+//!
+//! ```yaml
+//! - NAMESPACES
+//! - CGROUPS=m|y
+//! - BPF_LSM !recommended
+//! ```
+//!
+//! `version` is the schema version the spec is written against and `min_kcc`
+//! the oldest kcc able to check it. Specs for the current schema are validated
+//! strictly; specs for a newer schema are read on a best-effort basis, with
+//! fields this kcc doesn't know about dropped and reported.
+//!
+//! kconfig-hardened-check (kernel-hardening-checker) JSON is the list printed by
+//! its `-m json` mode, either as objects or as
+//! `[option_name, type, desired_val, decision, reason, ...]` arrays.
+
+use std::path::Path;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::expr::{self, Expr};
+use crate::requirement::{FlagRequirement, FlagRequirementBuilder, Severity};
+use crate::{arch, check, include};
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum SpecFormat {
+    /// Flags file with one flag or rule per line
+    Plain,
+    Yaml,
+    Toml,
+    /// kconfig-hardened-check JSON
+    HardenedCheck,
+}
+
+impl SpecFormat {
+    /// Guess the format from a file extension
+    pub fn from_path(path: &str) -> Option<SpecFormat> {
+        match Path::new(path).extension()?.to_str()? {
+            "yaml" | "yml" => Some(SpecFormat::Yaml),
+            "toml" => Some(SpecFormat::Toml),
+            "json" => Some(SpecFormat::HardenedCheck),
+            "flags" | "txt" => Some(SpecFormat::Plain),
+            _ => None,
+        }
+    }
+
+    /// The format of a file passed with `--flags`: a YAML or TOML spec by its extension, else a flags file
+    pub fn of_flags_file(path: &str) -> SpecFormat {
+        match SpecFormat::from_path(path) {
+            Some(format @ (SpecFormat::Yaml | SpecFormat::Toml)) => format,
+            _ => SpecFormat::Plain,
+        }
+    }
+}
+
+/// Newest spec schema version this kcc understands
+pub const SCHEMA_VERSION: u32 = 1;
+
+const SPEC_FIELDS: &[&str] = &["version", "min_kcc", "description", "requirements"];
+const REQUIREMENT_FIELDS: &[&str] = &["flag", "value", "rule", "reason", "arch", "depends_on", "severity", "section", "url", "min_kernel", "max_kernel"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_kcc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub requirements: Vec<Requirement>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Requirement {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Architectures the requirement applies to; empty for all
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arch: Vec<String>,
+    /// Flag or rule expression that must hold for the requirement to be checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<String>,
+    #[serde(default, skip_serializing_if = "Severity::is_required")]
+    pub severity: Severity,
+    /// The section of the check output the requirement is grouped under, `[Section]` in flags files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Where to read more about the requirement, shown with failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Oldest kernel version the requirement applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_kernel: Option<String>,
+    /// Newest kernel version the requirement applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_kernel: Option<String>,
+}
+
+impl Requirement {
+    /// The flag or rule, as checked
+    pub fn entry(&self) -> Option<&str> {
+        self.rule.as_deref().or(self.flag.as_deref())
+    }
+
+    /// Whether the requirement has metadata a flags file can't hold
+    pub fn has_metadata(&self) -> bool {
+        self.url.is_some() || self.min_kernel.is_some() || self.max_kernel.is_some()
+    }
+
+    /// The requirement to check, built from the fields rather than read back from a flags file
+    /// line, so a `value` is accepted as written whatever it holds
+    pub fn builder(&self) -> anyhow::Result<FlagRequirementBuilder> {
+        let entry = self.entry().ok_or_else(|| anyhow::anyhow!("A requirement needs a `flag` or a `rule`"))?;
+        let mut builder = FlagRequirement::builder(entry).severity(self.severity);
+        if let (Some(value), None) = (&self.value, &self.rule) {
+            for value in check::accepted_values(value) {
+                builder = builder.accepts(value);
+            }
+        }
+        for arch in &self.arch {
+            builder = builder.arch(arch);
+        }
+        if let Some(condition) = &self.depends_on {
+            builder = builder.depends_on(condition);
+        }
+        if let Some(reason) = &self.reason {
+            builder = builder.reason(reason);
+        }
+        if let Some(section) = &self.section {
+            builder = builder.section(section);
+        }
+        if let Some(url) = &self.url {
+            builder = builder.url(url);
+        }
+        if let Some(version) = &self.min_kernel {
+            builder = builder.min_kernel(version);
+        }
+        if let Some(version) = &self.max_kernel {
+            builder = builder.max_kernel(version);
+        }
+        Ok(builder)
+    }
+}
+
+/// The fields that decide how the rest of a structured spec is read
+#[derive(Deserialize)]
+struct Header {
+    version: Option<u32>,
+    min_kcc: Option<String>,
+}
+
+/// Parse a spec in the given format
+pub fn parse(content: &str, format: SpecFormat) -> anyhow::Result<Spec> {
+    match format {
+        SpecFormat::Plain => parse_plain(content),
+        SpecFormat::Yaml => match serde_yaml::from_str::<Vec<String>>(content) {
+            // A bare list of entries, as kept next to playbooks and manifests
+            Ok(entries) => parse_plain(&entries.join("\n")),
+            Err(_) => parse_structured(content, format),
+        },
+        SpecFormat::Toml => parse_structured(content, format),
+        SpecFormat::HardenedCheck => parse_hardened_check(content),
+    }
+}
+
+/// Render a spec in the given format
+pub fn render(spec: &Spec, format: SpecFormat) -> anyhow::Result<String> {
+    let structured = Spec { version: Some(SCHEMA_VERSION), ..spec.clone() };
+    match format {
+        SpecFormat::Plain => Ok(render_plain(spec)),
+        SpecFormat::Yaml => serde_yaml::to_string(&structured).map_err(|e| anyhow::anyhow!("Failed to write YAML: {}", e)),
+        SpecFormat::Toml => toml::to_string(&structured).map_err(|e| anyhow::anyhow!("Failed to write TOML: {}", e)),
+        SpecFormat::HardenedCheck => render_hardened_check(spec),
+    }
+}
+
+/// A file passed with `--flags` as the text of the flags file it stands for, converting a YAML or
+/// TOML spec and following includes, along with the spec's requirements, which are checked as
+/// [`Requirement::builder`] builds them rather than as their lines in the text
+pub fn as_flags_file(path: &str, content: &str) -> anyhow::Result<(String, Vec<Requirement>)> {
+    match SpecFormat::of_flags_file(path) {
+        SpecFormat::Plain => include::expand(path, content),
+        format => {
+            let spec = parse(content, format).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+            Ok((render_plain(&spec), spec.requirements))
+        }
+    }
+}
+
+fn parse_structured(content: &str, format: SpecFormat) -> anyhow::Result<Spec> {
+    fn from_str<T: serde::de::DeserializeOwned>(content: &str, format: SpecFormat) -> anyhow::Result<T> {
+        match format {
+            SpecFormat::Toml => toml::from_str(content).map_err(|e| anyhow::anyhow!("Invalid TOML spec: {}", e)),
+            _ => serde_yaml::from_str(content).map_err(|e| anyhow::anyhow!("Invalid YAML spec: {}", e)),
+        }
+    }
+
+    let header: Header = from_str(content, format)?;
+    let kcc_version = env!("CARGO_PKG_VERSION");
+    if let Some(min_kcc) = &header.min_kcc {
+        if expr::compare_versions(min_kcc, kcc_version).is_gt() {
+            return Err(anyhow::anyhow!("Spec requires kcc {} or newer, this is kcc {}", min_kcc, kcc_version));
+        }
+    }
+
+    let spec = match header.version {
+        Some(0) => return Err(anyhow::anyhow!("Invalid spec: version must be 1 or higher")),
+        Some(version) if version > SCHEMA_VERSION => {
+            eprintln!(
+                "⚠️  Spec uses schema version {}, this kcc understands up to {}; reading what it can",
+                version, SCHEMA_VERSION
+            );
+            let mut value: Value = from_str(content, format)?;
+            let dropped = drop_unknown_fields(&mut value);
+            if !dropped.is_empty() {
+                eprintln!("⚠️  Ignoring fields unknown to this kcc: {}", dropped.join(", "));
+            }
+            serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Invalid spec: {}", e))?
+        }
+        _ => from_str(content, format)?,
+    };
+
+    validate(&spec)?;
+    Ok(spec)
+}
+
+/// Remove fields a newer schema may have added, returning their paths
+fn drop_unknown_fields(value: &mut Value) -> Vec<String> {
+    let mut dropped = Vec::new();
+    let Value::Object(spec) = value else {
+        return dropped;
+    };
+
+    spec.retain(|key, _| {
+        let known = SPEC_FIELDS.contains(&key.as_str());
+        if !known {
+            dropped.push(key.clone());
+        }
+        known
+    });
+    if let Some(Value::Array(requirements)) = spec.get_mut("requirements") {
+        for (index, requirement) in requirements.iter_mut().enumerate() {
+            if let Value::Object(fields) = requirement {
+                fields.retain(|key, _| {
+                    let known = REQUIREMENT_FIELDS.contains(&key.as_str());
+                    if !known {
+                        dropped.push(format!("requirements[{}].{}", index, key));
+                    }
+                    known
+                });
+            }
+        }
+    }
+
+    dropped
+}
+
+/// Check what the schema's types can't express, reporting every problem at once
+fn validate(spec: &Spec) -> anyhow::Result<()> {
+    let mut errors = Vec::new();
+
+    for (index, requirement) in spec.requirements.iter().enumerate() {
+        let path = format!("requirements[{}]", index);
+        match (&requirement.flag, &requirement.rule) {
+            (Some(_), Some(_)) => errors.push(format!("{}: has both `flag` and `rule`, use one", path)),
+            (None, None) => errors.push(format!("{}: needs a `flag` or a `rule`", path)),
+            (Some(flag), None) => {
+                if flag.is_empty() || !flag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    errors.push(format!("{}.flag: {:?} is not a config symbol name", path, flag));
+                }
+            }
+            (None, Some(rule)) => {
+                if requirement.value.is_some() {
+                    errors.push(format!("{}.value: only allowed with `flag`", path));
+                }
+                if let Err(e) = expr::parse(rule) {
+                    errors.push(format!("{}.rule: {}", path, e));
+                }
+            }
+        }
+        if let Some(Err(e)) = requirement.depends_on.as_deref().map(expr::parse) {
+            errors.push(format!("{}.depends_on: {}", path, e));
+        }
+        for (arch_index, name) in requirement.arch.iter().enumerate() {
+            if let Err(e) = arch::parse(name) {
+                errors.push(format!("{}.arch[{}]: {}", path, arch_index, e));
+            }
+        }
+        for (field, version) in [("min_kernel", &requirement.min_kernel), ("max_kernel", &requirement.max_kernel)] {
+            let Some(version) = version else { continue };
+            if version.split('.').any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit())) {
+                errors.push(format!("{}.{}: {:?} is not a kernel version like 5.10", path, field, version));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Invalid spec:\n  {}", errors.join("\n  ")))
+    }
+}
+
+/// Comments directly above an entry become its reason; a leading comment block
+/// separated from the entries by a blank line becomes the description; entries
+/// below a `[Section]` header are in that section, up to the next header or `[]`
+fn parse_plain(content: &str) -> anyhow::Result<Spec> {
+    let mut spec = Spec::default();
+    let mut comments: Vec<&str> = Vec::new();
+    let mut section: Option<String> = None;
+
+    let lines = check::flag_lines(content).map_err(|(line_number, e)| anyhow::anyhow!("line {}: {}", line_number, e))?;
+    for (line_number, trimmed) in &lines {
+        let trimmed = trimmed.as_str();
+
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            comments.push(comment.trim());
+            continue;
+        }
+        if trimmed.is_empty() {
+            if spec.description.is_none() && spec.requirements.is_empty() && !comments.is_empty() {
+                spec.description = Some(comments.join(" "));
+            }
+            comments.clear();
+            continue;
+        }
+        if check::include_directive(trimmed).is_some() {
+            return Err(anyhow::anyhow!("line {}: include directives have to be expanded first", line_number));
+        }
+        if let Some(name) = check::section_header(trimmed) {
+            section = (!name.is_empty()).then(|| name.to_string());
+            comments.clear();
+            continue;
+        }
+
+        let reason = (!comments.is_empty()).then(|| comments.join(" "));
+        comments.clear();
+
+        let (trimmed, severity) = check::severity_marker(trimmed).unwrap_or((trimmed, Severity::Required));
+        let (trimmed, arch) = match check::arch_qualifier(trimmed) {
+            Some((entry, arches)) => {
+                (entry, check::parse_arches(&arches).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?)
+            }
+            None => (trimmed, Vec::new()),
+        };
+        let (trimmed, depends_on) = match check::dependency(trimmed) {
+            Some((entry, condition)) => {
+                expr::parse(condition).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+                (entry, Some(condition.to_string()))
+            }
+            None => (trimmed, None),
+        };
+
+        let requirement = if check::is_rule(trimmed) {
+            expr::parse(trimmed).map_err(|e| anyhow::anyhow!("line {}: {}", line_number, e))?;
+            Requirement { rule: Some(trimmed.to_string()), reason, arch, depends_on, severity, ..Default::default() }
+        } else if let Some((flag, value)) = trimmed.split_once('=') {
+            Requirement {
+                flag: Some(flag.trim().to_string()),
+                value: Some(value.trim().to_string()),
+                reason,
+                arch,
+                depends_on,
+                severity,
+                ..Default::default()
+            }
+        } else {
+            Requirement { flag: Some(trimmed.to_string()), reason, arch, depends_on, severity, ..Default::default() }
+        };
+        spec.requirements.push(Requirement { section: section.clone(), ..requirement });
+    }
+
+    Ok(spec)
+}
+
+fn render_plain(spec: &Spec) -> String {
+    let mut out = String::new();
+    if let Some(description) = &spec.description {
+        out.push_str(&format!("# {}\n\n", description));
+    }
+
+    // Entries outside any section come first, as they would be read into the first section otherwise
+    let mut sections: Vec<Option<&str>> = vec![None];
+    for section in spec.requirements.iter().filter_map(|requirement| requirement.section.as_deref()) {
+        if !sections.contains(&Some(section)) {
+            sections.push(Some(section));
+        }
+    }
+    let grouped = sections.iter().flat_map(|section| {
+        spec.requirements.iter().filter(move |requirement| requirement.section.as_deref() == *section)
+    });
+
+    let mut current = None;
+    for requirement in grouped {
+        if let Some(section) = requirement.section.as_deref().filter(|section| current != Some(*section)) {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{}]\n", section));
+            current = Some(section);
+        }
+        if let Some(reason) = &requirement.reason {
+            out.push_str(&format!("# {}\n", reason));
+        }
+        let entry = match (&requirement.flag, &requirement.value, &requirement.rule) {
+            (Some(flag), Some(value), _) => format!("{}={}", flag, value),
+            (Some(flag), None, _) => flag.clone(),
+            (None, _, Some(rule)) => rule.clone(),
+            (None, _, None) => continue,
+        };
+        let entry = match &requirement.depends_on {
+            Some(condition) => format!("{} depends on {}", entry, condition),
+            None => entry,
+        };
+        let entry = match requirement.arch.as_slice() {
+            [] => entry,
+            arches => format!("{} @ {}", entry, arches.join(",")),
+        };
+        match requirement.severity {
+            Severity::Required => out.push_str(&format!("{}\n", entry)),
+            severity => out.push_str(&format!("{} !{}\n", entry, severity.as_str())),
+        }
+    }
+
+    out
+}
+
+fn parse_hardened_check(content: &str) -> anyhow::Result<Spec> {
+    let checks: Vec<Value> = serde_json::from_str(content)
+        .map_err(|e| anyhow::anyhow!("Invalid kconfig-hardened-check JSON: {}", e))?;
+
+    let mut spec = Spec {
+        description: Some("Converted from kconfig-hardened-check".to_string()),
+        ..Default::default()
+    };
+    let mut skipped = 0;
+
+    for check in &checks {
+        let field = |key: &str, index: usize| -> Option<&str> {
+            match check {
+                Value::Object(fields) => fields.get(key)?.as_str(),
+                Value::Array(items) => items.get(index)?.as_str(),
+                _ => None,
+            }
+        };
+        let (Some(name), Some(desired)) = (field("option_name", 0), field("desired_val", 2)) else {
+            return Err(anyhow::anyhow!("Unexpected kconfig-hardened-check entry: {}", check));
+        };
+
+        // Only kconfig checks can be expressed; cmdline and sysctl checks are dropped
+        if field("type", 1) != Some("kconfig") {
+            skipped += 1;
+            continue;
+        }
+
+        let name = name.strip_prefix("CONFIG_").unwrap_or(name);
+        let reason = match (field("decision", 3), field("reason", 4)) {
+            (Some(decision), Some(reason)) => Some(format!("{}: {}", decision, reason)),
+            (Some(text), None) | (None, Some(text)) => Some(text.to_string()),
+            (None, None) => None,
+        };
+
+        let requirement = match desired {
+            "is not set" => Requirement { rule: Some(format!("!{}", name)), reason, ..Default::default() },
+            "is present" => Requirement { flag: Some(name.to_string()), reason, ..Default::default() },
+            "y" | "m" => Requirement {
+                flag: Some(name.to_string()),
+                value: Some(desired.to_string()),
+                reason,
+                ..Default::default()
+            },
+            value => Requirement { rule: Some(format!("{}={}", name, quote(value))), reason, ..Default::default() },
+        };
+        spec.requirements.push(requirement);
+    }
+
+    if skipped > 0 {
+        eprintln!("⚠️  Skipped {} cmdline/sysctl checks that kcc can't express", skipped);
+    }
+
+    Ok(spec)
+}
+
+fn render_hardened_check(spec: &Spec) -> anyhow::Result<String> {
+    let mut checks = Vec::new();
+    let mut skipped = Vec::new();
+
+    for requirement in &spec.requirements {
+        let check = match (&requirement.flag, &requirement.rule) {
+            (Some(flag), _) => Some((
+                flag.clone(),
+                match requirement.value.as_deref() {
+                    None => "y".to_string(),
+                    Some("n") => "is not set".to_string(),
+                    Some(value) => value.to_string(),
+                },
+            )),
+            (None, Some(rule)) => hardened_check_rule(rule),
+            (None, None) => None,
+        };
+        let Some((name, desired)) = check else {
+            skipped.extend(requirement.rule.clone());
+            continue;
+        };
+
+        let name = name.strip_prefix("CONFIG_").unwrap_or(&name).to_string();
+        checks.push(json!({
+            "option_name": format!("CONFIG_{}", name),
+            "type": "kconfig",
+            "desired_val": desired,
+            "decision": "kcc",
+            "reason": requirement.reason.clone().unwrap_or_else(|| "kcc".to_string()),
+        }));
+    }
+
+    for rule in skipped {
+        eprintln!("⚠️  Skipped rule that kconfig-hardened-check can't express: {}", rule);
+    }
+    let qualified = spec.requirements.iter().filter(|requirement| !requirement.arch.is_empty()).count();
+    if qualified > 0 {
+        eprintln!("⚠️  Dropped the architecture qualifiers of {} requirement(s), they apply everywhere now", qualified);
+    }
+    let dependent = spec.requirements.iter().filter(|requirement| requirement.depends_on.is_some()).count();
+    if dependent > 0 {
+        eprintln!("⚠️  Dropped the dependencies of {} requirement(s), they are always checked now", dependent);
+    }
+
+    Ok(serde_json::to_string_pretty(&checks)? + "\n")
+}
+
+/// The (option, desired value) pair for rules that test a single symbol
+fn hardened_check_rule(rule: &str) -> Option<(String, String)> {
+    match expr::parse(rule).ok()? {
+        Expr::Not(inner) => match *inner {
+            Expr::Test { symbol, op: None } => Some((symbol, "is not set".to_string())),
+            _ => None,
+        },
+        Expr::Test { symbol, op: Some((expr::Op::Eq, value)) } if symbol != "KERNEL" && symbol != "ARCH" => {
+            let value = value.trim_matches('"').to_string();
+            Some((symbol, if value == "n" { "is not set".to_string() } else { value }))
+        }
+        _ => None,
+    }
+}
+
+fn quote(value: &str) -> String {
+    if value.chars().all(|c| c.is_alphanumeric() || "_.-+".contains(c)) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::KernelConfig;
+    use crate::report::Report;
+
+    /// The status of each result of checking the spec against the config
+    fn check(source: &str, spec: &str, config: &str) -> Vec<String> {
+        let report = Report::from_flags(&KernelConfig::parse("", config), source, spec).unwrap();
+        report.results.iter().map(|result| result.status.as_str().to_string()).collect()
+    }
+
+    #[test]
+    fn toml_values_are_checked_as_written() {
+        let spec = "[[requirements]]\nflag = \"CONFIG_DEFAULT_HOSTNAME\"\nvalue = '\"(none)\"'\n";
+        assert_eq!(check("host.toml", spec, "CONFIG_DEFAULT_HOSTNAME=\"(none)\"\n"), ["enabled"]);
+        assert_eq!(check("host.toml", spec, "CONFIG_DEFAULT_HOSTNAME=\"box\"\n"), ["wrong-value"]);
+    }
+
+    #[test]
+    fn yaml_values_are_checked_as_written() {
+        let spec = "requirements:\n  - flag: DEFAULT_HOSTNAME\n    value: '\"(none)\"'\n  - flag: HZ\n    value: 250|1000\n";
+        let config = "CONFIG_DEFAULT_HOSTNAME=\"(none)\"\nCONFIG_HZ=300\n";
+        assert_eq!(check("host.yaml", spec, config), ["enabled", "wrong-value"]);
+    }
+}
@@ -0,0 +1,124 @@
+//! Checking configs that arrive as a stream, without holding the whole config in memory.
+//!
+//! A [`ConfigStream`] is fed the config line by line, or written to as an
+//! [`io::Write`], and keeps only what the requirements can look at: the comment
+//! header (kernel version) and the lines of the symbols they name, rules,
+//! dependencies and architecture symbols included. Values are recorded as the
+//! lines arrive; results are computed at the end of the stream, since a later
+//! line may still assign a symbol again.
+//!
+//! ```no_run
+//! use std::process::{Command, Stdio};
+//!
+//! use kcc::{stream, Checker, FlagRequirement};
+//!
+//! let requirements = [FlagRequirement::builder("CONFIG_BPF_SYSCALL").build()?];
+//! let mut ssh = Command::new("ssh").args(["web-1", "cat /proc/config.gz"]).stdout(Stdio::piped()).spawn()?;
+//! let stdout = ssh.stdout.take().unwrap();
+//! for (_, result) in stream::check_reader(&Checker::new(), "web-1:/proc/config.gz", stdout, &requirements)? {
+//!     println!("{}: {}", result.name, result.status.as_str());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::HashSet;
+use std::io::{self, Read, Write};
+
+use crate::check::FlagCheckResult;
+use crate::config::KernelConfig;
+use crate::requirement::{Checker, FlagRequirement};
+use crate::{arch, compress, expr};
+
+/// Lines of the comment header that are always kept, as read by `expr::kernel_version`
+const HEADER_LINES: usize = 10;
+
+/// A config being read line by line, keeping only the lines the requirements need
+pub struct ConfigStream {
+    wanted: HashSet<String>,
+    kept: String,
+    /// Bytes of an incomplete line, when written to as an `io::Write`
+    partial: Vec<u8>,
+    lines: usize,
+}
+
+impl ConfigStream {
+    /// A stream keeping what `requirements` look at
+    pub fn new(requirements: &[FlagRequirement]) -> Self {
+        let mut wanted: HashSet<String> = arch::ARCH_SYMBOLS.iter().map(|symbol| symbol.to_string()).collect();
+        for requirement in requirements {
+            if let Some(condition) = &requirement.depends_on {
+                wanted.extend(expr::parse(condition).map(|rule| expr::symbols(&rule)).unwrap_or_default());
+            }
+            if requirement.is_rule() {
+                // Requirements are validated when built, so a rule that doesn't parse tests nothing
+                wanted.extend(expr::parse(&requirement.name).map(|rule| expr::symbols(&rule)).unwrap_or_default());
+            } else {
+                wanted.insert(requirement.name.clone());
+            }
+        }
+        ConfigStream { wanted, kept: String::new(), partial: Vec::new(), lines: 0 }
+    }
+
+    /// Take the next line of the config, without its line ending
+    pub fn feed_line(&mut self, line: &str) {
+        self.lines += 1;
+        if self.lines <= HEADER_LINES || symbol(line).is_some_and(|name| self.wanted.contains(name)) {
+            self.kept.push_str(line);
+            self.kept.push('\n');
+        }
+    }
+
+    /// Number of lines read so far
+    pub fn lines(&self) -> usize {
+        self.lines
+    }
+
+    /// End the stream, returning a config holding the header and the symbols the requirements name
+    pub fn finish(mut self, source: &str) -> KernelConfig {
+        if !self.partial.is_empty() {
+            let line = String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned();
+            self.feed_line(&line);
+        }
+        KernelConfig::parse(source, self.kept)
+    }
+}
+
+impl Write for ConfigStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.partial.extend_from_slice(buf);
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return Ok(buf.len());
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+        for line in String::from_utf8_lossy(&complete).lines() {
+            self.feed_line(line);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The symbol a config line assigns or unsets
+fn symbol(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if let Some(name) = line.strip_prefix("# ").and_then(|rest| rest.strip_suffix(" is not set")) {
+        return Some(name);
+    }
+    line.split_once('=').map(|(name, _)| name).filter(|name| name.starts_with("CONFIG_"))
+}
+
+/// Read a plain, gzip, xz or zstd config from `reader` as it arrives and check the requirements
+/// against it; `source` names the config in errors and is used to recognize compression by extension
+pub fn check_reader<'r>(
+    checker: &Checker,
+    source: &str,
+    reader: impl Read,
+    requirements: &'r [FlagRequirement],
+) -> anyhow::Result<Vec<(&'r FlagRequirement, FlagCheckResult)>> {
+    let mut stream = ConfigStream::new(requirements);
+    compress::decode_stream(reader, &mut stream, source)?;
+    Ok(checker.check(&stream.finish(source), requirements))
+}
@@ -0,0 +1,57 @@
+//! Alternate root directory for auditing offline images and rescue-mounted
+//! systems with `--root`.
+//!
+//! Lookups of installed files (/boot, /lib/modules, /usr/src, package
+//! databases) go through [`path`], which places them under the root when one
+//! is set. The running system is never consulted then.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::expr;
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `root` for every later lookup of installed files
+pub fn set(root: &str) -> anyhow::Result<()> {
+    if !Path::new(root).is_dir() {
+        return Err(anyhow::anyhow!("Root directory not found: {}", root));
+    }
+    ROOT.set(PathBuf::from(root))
+        .map_err(|_| anyhow::anyhow!("Root directory already set"))
+}
+
+/// The alternate root, if one was given
+pub fn get() -> Option<&'static Path> {
+    ROOT.get().map(PathBuf::as_path)
+}
+
+/// `path` inside the alternate root, or unchanged without one
+pub fn path(path: &str) -> PathBuf {
+    match get() {
+        Some(root) => root.join(path.trim_start_matches('/')),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The newest kernel release installed in the root, from /lib/modules or /boot/config-*
+pub fn installed_release() -> Option<String> {
+    let modules = fs::read_dir(path("/lib/modules"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string());
+    let configs = fs::read_dir(path("/boot"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let release = name.strip_prefix("config-")?;
+            Some(release.trim_end_matches(".xz").trim_end_matches(".zst").to_string())
+        });
+
+    modules.chain(configs).max_by(|a, b| expr::compare_versions(a, b))
+}
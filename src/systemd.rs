@@ -0,0 +1,193 @@
+//! Derive kernel requirements from the sandboxing and resource control
+//! directives of a systemd unit.
+//!
+//! systemd implements most sandboxing with namespaces and seccomp, IP and
+//! socket filtering with cgroup BPF programs, and resource limits with cgroup
+//! controllers. A directive the kernel can't back is either ignored or makes the
+//! service fail to start, depending on the systemd version.
+
+use std::fs;
+
+use crate::derive::Requirements;
+
+/// Parse a unit file and derive the kernel config its directives depend on
+pub fn derive(path: &str) -> anyhow::Result<Requirements> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read unit file {}: {}", path, e))?;
+
+    let mut requirements = Requirements::default();
+    for (key, value) in directives(&content) {
+        if is_disabled(&value) {
+            continue;
+        }
+        directive_requirements(&key, &value, &mut requirements);
+    }
+
+    if requirements.flags.is_empty() {
+        return Err(anyhow::anyhow!("No sandboxing or resource control directives found in {}", path));
+    }
+
+    Ok(requirements)
+}
+
+/// `Key=Value` assignments, with comments dropped and continuation lines joined
+fn directives(content: &str) -> Vec<(String, String)> {
+    let mut directives = Vec::new();
+    let mut pending = String::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if pending.is_empty() && (line.starts_with('#') || line.starts_with(';') || line.starts_with('[')) {
+            continue;
+        }
+
+        if let Some(continued) = line.strip_suffix('\\') {
+            pending.push_str(continued);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(line);
+
+        if let Some((key, value)) = pending.split_once('=') {
+            directives.push((key.trim().to_string(), value.trim().to_string()));
+        }
+        pending.clear();
+    }
+
+    directives
+}
+
+/// Values that turn a directive off or reset it
+fn is_disabled(value: &str) -> bool {
+    matches!(value.to_ascii_lowercase().as_str(), "" | "no" | "false" | "off" | "0")
+}
+
+fn directive_requirements(key: &str, value: &str, req: &mut Requirements) {
+    let reason = format!("{}=", key);
+    let reason = reason.as_str();
+
+    match key {
+        // Namespaces
+        "PrivateNetwork" | "NetworkNamespacePath" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_NET_NS", reason);
+        }
+        "PrivateUsers" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_USER_NS", reason);
+        }
+        "PrivateIPC" | "IPCNamespacePath" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_IPC_NS", reason);
+        }
+        "ProtectHostname" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_UTS_NS", reason);
+        }
+        "PrivatePIDs" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_PID_NS", reason);
+        }
+        "PrivateTmp" | "TemporaryFileSystem" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_TMPFS", reason);
+        }
+        "PrivateDevices" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_TMPFS", reason);
+            seccomp(req, reason);
+        }
+        "ProtectSystem" | "ProtectHome" | "PrivateMounts" | "ReadOnlyPaths" | "ReadWritePaths"
+        | "InaccessiblePaths" | "ExecPaths" | "NoExecPaths" | "BindPaths" | "BindReadOnlyPaths"
+        | "ProtectKernelTunables" | "ProtectControlGroups" | "RootDirectory" | "MountAPIVFS" => {
+            req.require("CONFIG_NAMESPACES", reason);
+        }
+        "ProtectProc" | "ProcSubset" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require_version("5.8", reason);
+        }
+        "RootImage" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            req.require("CONFIG_BLK_DEV_LOOP", reason);
+        }
+        "RootHash" | "RootVerity" => req.require("CONFIG_DM_VERITY", reason),
+
+        // seccomp filters
+        "RestrictNamespaces" | "SystemCallFilter" | "SystemCallArchitectures" | "SystemCallLog"
+        | "MemoryDenyWriteExecute" | "RestrictRealtime" | "RestrictSUIDSGID" | "LockPersonality"
+        | "RestrictAddressFamilies" | "ProtectClock" => seccomp(req, reason),
+        "ProtectKernelModules" | "ProtectKernelLogs" => {
+            req.require("CONFIG_NAMESPACES", reason);
+            seccomp(req, reason);
+        }
+
+        // cgroup BPF programs
+        "IPAddressAllow" | "IPAddressDeny" | "IPIngressFilterPath" | "IPEgressFilterPath"
+        | "IPAccounting" => {
+            cgroup_bpf(req, reason);
+            req.require_version("4.10", reason);
+        }
+        "SocketBindAllow" | "SocketBindDeny" => {
+            cgroup_bpf(req, reason);
+            req.require_version("4.17", reason);
+        }
+        "RestrictNetworkInterfaces" => cgroup_bpf(req, reason),
+        "DeviceAllow" | "DevicePolicy" => {
+            cgroup_bpf(req, reason);
+            req.require_version("4.15", reason);
+        }
+        "RestrictFileSystems" => {
+            req.require("CONFIG_BPF_SYSCALL", reason);
+            req.require("CONFIG_BPF_LSM", reason);
+            req.require("CONFIG_DEBUG_INFO_BTF", reason);
+            req.require_version("5.7", reason);
+        }
+
+        // cgroup controllers
+        "CPUQuota" => {
+            req.require("CONFIG_CGROUP_SCHED", reason);
+            req.require("CONFIG_FAIR_GROUP_SCHED", reason);
+            req.require("CONFIG_CFS_BANDWIDTH", reason);
+        }
+        "CPUWeight" | "StartupCPUWeight" | "CPUShares" => {
+            req.require("CONFIG_CGROUP_SCHED", reason);
+            req.require("CONFIG_FAIR_GROUP_SCHED", reason);
+        }
+        "AllowedCPUs" | "AllowedMemoryNodes" | "StartupAllowedCPUs" | "StartupAllowedMemoryNodes" => {
+            req.require("CONFIG_CPUSETS", reason);
+        }
+        "MemoryMax" | "MemoryHigh" | "MemoryLow" | "MemoryMin" | "MemoryLimit" => {
+            req.require("CONFIG_MEMCG", reason);
+        }
+        "MemorySwapMax" => {
+            req.require("CONFIG_MEMCG", reason);
+            req.require("CONFIG_SWAP", reason);
+        }
+        "IOWeight" | "StartupIOWeight" | "IODeviceWeight" | "BlockIOWeight" => {
+            req.require("CONFIG_BLK_CGROUP", reason);
+        }
+        "IOReadBandwidthMax" | "IOWriteBandwidthMax" | "IOReadIOPSMax" | "IOWriteIOPSMax" => {
+            req.require("CONFIG_BLK_CGROUP", reason);
+            req.require("CONFIG_BLK_DEV_THROTTLING", reason);
+        }
+        "IODeviceLatencyTargetSec" => {
+            req.require("CONFIG_BLK_CGROUP", reason);
+            req.require("CONFIG_BLK_CGROUP_IOLATENCY", reason);
+        }
+        "TasksMax" => req.require("CONFIG_CGROUP_PIDS", reason),
+
+        "KeyringMode" if value != "inherit" => req.require("CONFIG_KEYS", reason),
+        "CapabilityBoundingSet" | "AmbientCapabilities" => req.require("CONFIG_MULTIUSER", reason),
+        _ => {}
+    }
+}
+
+fn seccomp(req: &mut Requirements, reason: &str) {
+    req.require("CONFIG_SECCOMP", reason);
+    req.require("CONFIG_SECCOMP_FILTER", reason);
+}
+
+fn cgroup_bpf(req: &mut Requirements, reason: &str) {
+    req.require("CONFIG_BPF_SYSCALL", reason);
+    req.require("CONFIG_CGROUP_BPF", reason);
+}
@@ -0,0 +1,400 @@
+//! Browsing the results of a check in a terminal UI, for `--tui`.
+//!
+//! The results are listed on the left and the selected one is explained on the
+//! right: why the spec asks for it, its Kconfig help text and the chain of
+//! dependencies that keeps it from being enabled. `/` filters the list by name,
+//! status or reason, and Tab shows only the failures.
+//!
+//! `kcc search` uses it as a fuzzy finder over the options of a config.
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::kconfig::KconfigDb;
+use crate::report::{CheckResult, Report};
+use crate::search::{self, Candidate};
+use crate::KernelConfig;
+
+struct Browser<'a> {
+    report: &'a Report,
+    config: &'a KernelConfig,
+    kconfig: Option<&'a KconfigDb>,
+    filter: String,
+    /// Whether keys go to the filter instead of moving through the list
+    editing: bool,
+    failures_only: bool,
+    /// Indices of the results that pass the filter
+    visible: Vec<usize>,
+    list: ListState,
+    scroll: u16,
+}
+
+/// Show the results of a check until the user quits with q or Esc
+pub fn browse(report: &Report, config: &KernelConfig, kconfig: Option<&KconfigDb>) -> anyhow::Result<()> {
+    let mut browser = Browser {
+        report,
+        config,
+        kconfig,
+        filter: String::new(),
+        editing: false,
+        failures_only: false,
+        visible: Vec::new(),
+        list: ListState::default(),
+        scroll: 0,
+    };
+    browser.refilter();
+
+    let mut terminal = ratatui::try_init().map_err(|e| anyhow::anyhow!("Failed to start the terminal UI: {}", e))?;
+    let outcome = browser.run(&mut terminal);
+    ratatui::restore();
+    outcome
+}
+
+impl Browser<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                return Ok(());
+            }
+            if self.editing {
+                match key.code {
+                    KeyCode::Enter => self.editing = false,
+                    KeyCode::Esc => {
+                        self.editing = false;
+                        self.filter.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => continue,
+                }
+                self.refilter();
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('/') => self.editing = true,
+                KeyCode::Tab => {
+                    self.failures_only = !self.failures_only;
+                    self.refilter();
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.select(1),
+                KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+                KeyCode::Home | KeyCode::Char('g') => self.select(isize::MIN),
+                KeyCode::End | KeyCode::Char('G') => self.select(isize::MAX),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(5),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(5),
+                _ => {}
+            }
+        }
+    }
+
+    /// Recompute the visible results, keeping the selection on the same result when it still is
+    fn refilter(&mut self) {
+        let selected = self.selected().map(|result| result.name.clone());
+        let needle = self.filter.to_lowercase();
+        self.visible = (0..self.report.results.len())
+            .filter(|&index| {
+                let result = &self.report.results[index];
+                let text = [Some(result.name.as_str()), Some(result.status.as_str()), result.reason.as_deref()];
+                (!self.failures_only || result.failed())
+                    && text.into_iter().flatten().any(|text| text.to_lowercase().contains(&needle))
+            })
+            .collect();
+        let position = selected.and_then(|name| self.visible.iter().position(|&index| self.report.results[index].name == name));
+        self.list.select(position.or((!self.visible.is_empty()).then_some(0)));
+        self.scroll = 0;
+    }
+
+    fn selected(&self) -> Option<&CheckResult> {
+        self.list.selected().and_then(|position| self.visible.get(position)).map(|&index| &self.report.results[index])
+    }
+
+    /// Move the selection by an offset, stopping at the ends of the list
+    fn select(&mut self, offset: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        self.list.select(Some(current.saturating_add(offset).clamp(0, last) as usize));
+        self.scroll = 0;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(frame.area());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(rows[0]);
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&index| {
+                let result = &self.report.results[index];
+                Line::from(vec![Span::styled(format!("{:<14}", result.status), status_style(result)), Span::raw(&result.name)])
+                    .into()
+            })
+            .collect();
+        let summary = &self.report.summary;
+        let title = format!(
+            " {} · {}/{} passed{} ",
+            self.report.config,
+            summary.passed,
+            summary.checked,
+            if self.failures_only { " · failures only" } else { "" }
+        );
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, columns[0], &mut self.list);
+
+        let details = Paragraph::new(self.details())
+            .block(Block::default().borders(Borders::ALL).title(" Details "))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(details, columns[1]);
+
+        let footer = if self.editing {
+            Line::from(vec![Span::raw("/"), Span::raw(&self.filter), Span::raw("▏")])
+        } else if !self.filter.is_empty() {
+            Line::from(format!("filter: {}  ·  / edit  Esc quit  Tab failures only  PgUp/PgDn scroll details", self.filter))
+        } else {
+            Line::from("↑/↓ move  / filter  Tab failures only  PgUp/PgDn scroll details  q quit")
+        };
+        frame.render_widget(Paragraph::new(footer.dim()), rows[1]);
+    }
+
+    /// The lines explaining the selected result
+    fn details(&self) -> Vec<Line<'static>> {
+        let Some(result) = self.selected() else {
+            return vec![Line::from("No results match the filter")];
+        };
+        let mut lines = vec![
+            heading(&result.name),
+            Line::from(Span::styled(result.status.as_str(), status_style(result))),
+            Line::default(),
+        ];
+        for (label, value) in [
+            ("value", &result.value),
+            ("required", &result.required),
+            ("source", &result.source),
+            ("detail", &result.detail),
+            ("see", &result.url),
+        ] {
+            if let Some(value) = value {
+                lines.push(field(label, value));
+            }
+        }
+        if !result.controls.is_empty() {
+            lines.push(field("controls", &result.controls.join(", ")));
+        }
+        if let Some(reason) = &result.reason {
+            lines.extend([Line::default(), heading("Reason")]);
+            lines.extend(reason.lines().map(|line| Line::from(line.to_string())));
+        }
+
+        let Some((db, symbol)) = self.kconfig.and_then(|db| Some((db, db.get(&result.name)?))) else {
+            return lines;
+        };
+        lines.extend(symbol_lines(symbol.prompt.as_deref(), &symbol.files, symbol.help.as_deref()));
+        let chain = db.dependency_chain(self.config, &result.name);
+        if !chain.is_empty() {
+            lines.extend([Line::default(), heading("Unmet dependencies")]);
+            for unmet in chain {
+                lines.push(Line::from(format!("{} requires {}", unmet.name, unmet.needs.join(" and "))));
+            }
+        }
+        let selected_by = db.selected_by(&result.name);
+        if !selected_by.is_empty() {
+            lines.push(field("selected by", &selected_by.join(", ")));
+        }
+        lines
+    }
+}
+
+/// A search for config options, with the query typed so far
+struct Finder<'a> {
+    candidates: &'a [Candidate],
+    query: String,
+    matches: Vec<&'a Candidate>,
+    list: ListState,
+    scroll: u16,
+}
+
+/// Search the candidates interactively, starting with a query, until one is picked with Enter;
+/// none when the search is left with Esc
+pub fn search(candidates: &[Candidate], query: &str) -> anyhow::Result<Option<String>> {
+    let mut finder = Finder { candidates, query: query.to_string(), matches: Vec::new(), list: ListState::default(), scroll: 0 };
+    finder.rerank();
+
+    let mut terminal = ratatui::try_init().map_err(|e| anyhow::anyhow!("Failed to start the terminal UI: {}", e))?;
+    let outcome = finder.run(&mut terminal);
+    ratatui::restore();
+    outcome
+}
+
+impl Finder<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<Option<String>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let control = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if control => return Ok(None),
+                KeyCode::Enter => return Ok(self.selected().map(|candidate| candidate.name.clone())),
+                KeyCode::Down => self.select(1),
+                KeyCode::Char('n') if control => self.select(1),
+                KeyCode::Up => self.select(-1),
+                KeyCode::Char('p') if control => self.select(-1),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(5),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(5),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.rerank();
+                }
+                KeyCode::Char('u') if control => {
+                    self.query.clear();
+                    self.rerank();
+                }
+                KeyCode::Char(c) if !control => {
+                    self.query.push(c);
+                    self.rerank();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn rerank(&mut self) {
+        self.matches = search::rank(self.candidates, &self.query);
+        self.list.select((!self.matches.is_empty()).then_some(0));
+        self.scroll = 0;
+    }
+
+    fn selected(&self) -> Option<&Candidate> {
+        self.list.selected().and_then(|position| self.matches.get(position).copied())
+    }
+
+    /// Move the selection by an offset, stopping at the ends of the list
+    fn select(&mut self, offset: isize) {
+        if self.matches.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let last = self.matches.len() as isize - 1;
+        self.list.select(Some(current.saturating_add(offset).clamp(0, last) as usize));
+        self.scroll = 0;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+            .split(frame.area());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+            .split(rows[1]);
+
+        let prompt = Line::from(vec![Span::raw("> ").bold(), Span::raw(&self.query), Span::raw("▏")]);
+        frame.render_widget(Paragraph::new(prompt), rows[0]);
+
+        // Only the rows that fit are built, the list may have every symbol of the kernel
+        let height = columns[0].height.saturating_sub(2) as usize;
+        let first = self.list.selected().unwrap_or(0).saturating_sub(height.saturating_sub(1));
+        let items: Vec<ListItem> = self.matches[first..]
+            .iter()
+            .take(height.max(1))
+            .map(|candidate| {
+                let value = match candidate.value.as_deref() {
+                    Some("n") => Span::raw(" n").red(),
+                    Some(value) => Span::raw(format!(" {}", value)).green(),
+                    None => Span::raw(" -").dim(),
+                };
+                Line::from(vec![Span::raw(&candidate.name), value]).into()
+            })
+            .collect();
+        let title = format!(" {}/{} options ", self.matches.len(), self.candidates.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(self.list.selected().map(|selected| selected - first));
+        frame.render_stateful_widget(list, columns[0], &mut state);
+
+        let details = Paragraph::new(self.details())
+            .block(Block::default().borders(Borders::ALL).title(" Details "))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(details, columns[1]);
+
+        let footer = Line::from("type to search  ↑/↓ move  PgUp/PgDn scroll details  Enter pick  Esc quit");
+        frame.render_widget(Paragraph::new(footer.dim()), rows[2]);
+    }
+
+    /// The lines describing the selected option
+    fn details(&self) -> Vec<Line<'static>> {
+        let Some(candidate) = self.selected() else {
+            return vec![Line::from("No options match the query")];
+        };
+        let mut lines = vec![
+            heading(&candidate.name),
+            field("value", candidate.value.as_deref().unwrap_or("not in the config")),
+        ];
+        lines.extend(symbol_lines(candidate.prompt.as_deref(), &candidate.files, candidate.help.as_deref()));
+        lines
+    }
+}
+
+fn heading(text: &str) -> Line<'static> {
+    Line::from(Span::styled(text.to_string(), Style::default().add_modifier(Modifier::BOLD)))
+}
+
+fn field(label: &str, value: &str) -> Line<'static> {
+    Line::from(vec![Span::raw(format!("{:<12}", label)).dim(), Span::raw(value.to_string())])
+}
+
+/// The prompt, declaring files and help text of a Kconfig symbol
+fn symbol_lines(prompt: Option<&str>, files: &[String], help: Option<&str>) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if let Some(prompt) = prompt {
+        lines.extend([Line::default(), field("prompt", prompt)]);
+    }
+    if !files.is_empty() {
+        lines.push(field("defined", &files.join(", ")));
+    }
+    if let Some(help) = help {
+        lines.extend([Line::default(), heading("Help")]);
+        lines.extend(help.lines().map(|line| Line::from(line.to_string())));
+    }
+    lines
+}
+
+fn status_style(result: &CheckResult) -> Style {
+    let color = if result.failed() {
+        Color::Red
+    } else if result.status.is_pass() {
+        Color::Green
+    } else {
+        Color::Yellow
+    };
+    Style::default().fg(color)
+}
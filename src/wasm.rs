@@ -0,0 +1,30 @@
+//! JavaScript bindings for checking a config in the browser.
+//!
+//! Everything works on text the page already has: a pasted `.config` and flags
+//! list go in, a rendered report comes out. Nothing touches the filesystem or
+//! spawns processes, so the checks run entirely client-side. Build with:
+//!
+//! ```text
+//! cargo build --lib --release --target wasm32-unknown-unknown --no-default-features --features wasm
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/kcc.wasm
+//! ```
+
+use clap::ValueEnum;
+use wasm_bindgen::prelude::*;
+
+use crate::config::KernelConfig;
+use crate::html;
+use crate::report::{OutputFormat, Report};
+
+/// Check a config against a flags list and render the report: `json`, `yaml`, `csv`,
+/// `tsv`, `junit`, `sarif`, `prometheus`, `porcelain`, `text` or `html`
+#[wasm_bindgen]
+pub fn check(config: &str, flags: &str, format: &str) -> Result<String, JsError> {
+    let kernel_config = KernelConfig::parse("pasted config", config);
+    let report = Report::from_flags(&kernel_config, "pasted flags", flags).map_err(|e| JsError::new(&e.to_string()))?;
+    if format.eq_ignore_ascii_case("html") {
+        return Ok(html::render(&report, &html::config_metadata(config)));
+    }
+    let format = OutputFormat::from_str(format, true).map_err(|_| JsError::new(&format!("Unknown format: {}", format)))?;
+    report.render(format).map_err(|e| JsError::new(&e.to_string()))
+}
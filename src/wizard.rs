@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+use crate::profiles;
+
+/// Ask what the kernel will be used for and compose a flags file from the matching profiles.
+/// Questions go to stderr so the generated spec can be redirected from stdout.
+pub fn run() -> anyhow::Result<String> {
+    eprintln!("🧙 kcc wizard - answer a few questions to generate a requirements file\n");
+
+    let mut selected = Vec::new();
+    if ask_yes_no("Will this kernel run containers (Docker, Podman, Kubernetes)?")? {
+        if crate::wsl::is_wsl2() && ask_yes_no("Is this a WSL2 kernel?")? {
+            selected.push("wsl2-docker");
+        }
+        selected.push("containers");
+    }
+    if ask_yes_no("Will it run eBPF tooling (bcc, bpftrace, Cilium, CO-RE programs)?")? {
+        selected.push("ebpf");
+    }
+    if ask_yes_no("Will it host virtual machines with KVM?")? {
+        selected.push("kvm");
+    }
+    match ask_choice("Hardening level", &["none", "basic", "strict"], "basic")? {
+        "basic" => selected.push("hardening"),
+        "strict" => selected.extend(["hardening", "hardening-strict"]),
+        _ => {}
+    }
+
+    if selected.is_empty() {
+        return Err(anyhow::anyhow!("Nothing selected, no requirements to generate"));
+    }
+
+    compose(&selected)
+}
+
+/// Concatenate profiles into one flags file, dropping entries already required by an earlier one
+fn compose(names: &[&str]) -> anyhow::Result<String> {
+    let mut seen = HashSet::new();
+    let mut out = format!("# Requirements generated by kcc wizard from profiles: {}\n", names.join(", "));
+
+    for name in names {
+        let profile = profiles::find(name)?;
+        out.push_str(&format!("\n# --- {}: {} ---\n", profile.name, profile.description));
+        for line in profile.flags.lines() {
+            let entry = line.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            if seen.insert(entry.strip_prefix("CONFIG_").unwrap_or(entry).to_string()) {
+                out.push_str(entry);
+                out.push('\n');
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn prompt(question: &str) -> anyhow::Result<String> {
+    eprint!("❓ {} ", question);
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Err(anyhow::anyhow!("Unexpected end of input while answering the wizard"));
+    }
+    Ok(answer.trim().to_lowercase())
+}
+
+fn ask_yes_no(question: &str) -> anyhow::Result<bool> {
+    loop {
+        match prompt(&format!("{} [y/N]", question))?.as_str() {
+            "y" | "yes" => return Ok(true),
+            "" | "n" | "no" => return Ok(false),
+            _ => eprintln!("   Please answer y or n"),
+        }
+    }
+}
+
+fn ask_choice<'a>(question: &str, choices: &[&'a str], default: &'a str) -> anyhow::Result<&'a str> {
+    loop {
+        let answer = prompt(&format!("{} ({}) [{}]", question, choices.join("/"), default))?;
+        if answer.is_empty() {
+            return Ok(default);
+        }
+        match choices.iter().find(|choice| **choice == answer) {
+            Some(choice) => return Ok(choice),
+            None => eprintln!("   Please answer one of: {}", choices.join(", ")),
+        }
+    }
+}
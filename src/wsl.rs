@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Returns true when running under WSL2
+pub fn is_wsl2() -> bool {
+    fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|release| {
+            let release = release.to_lowercase();
+            release.contains("microsoft-standard") || release.contains("wsl2")
+        })
+        .unwrap_or(false)
+}
+
+/// Find the custom kernel configured with `kernel=` in the `[wsl2]` section
+/// of a Windows user's .wslconfig, translated to a path under /mnt
+pub fn custom_kernel_path() -> Option<PathBuf> {
+    let users = fs::read_dir("/mnt/c/Users").ok()?;
+
+    for user in users.flatten() {
+        let wslconfig = user.path().join(".wslconfig");
+        if let Ok(content) = fs::read_to_string(&wslconfig) {
+            if let Some(kernel) = parse_kernel_setting(&content) {
+                return Some(windows_to_wsl_path(&kernel));
+            }
+        }
+    }
+
+    None
+}
+
+/// Find a kernel config file shipped next to a custom WSL2 kernel image
+pub fn custom_kernel_config(kernel: &Path) -> Option<PathBuf> {
+    let dir = kernel.parent()?;
+    let mut candidates = vec![dir.join(".config"), dir.join("config")];
+    if let Some(name) = kernel.file_name().and_then(|n| n.to_str()) {
+        candidates.insert(0, dir.join(format!("{}.config", name)));
+    }
+
+    candidates.into_iter().find(|p| p.exists())
+}
+
+fn parse_kernel_setting(content: &str) -> Option<String> {
+    let mut in_wsl2_section = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_wsl2_section = trimmed.eq_ignore_ascii_case("[wsl2]");
+        } else if in_wsl2_section {
+            if let Some((key, value)) = trimmed.split_once('=') {
+                if key.trim().eq_ignore_ascii_case("kernel") {
+                    return Some(value.trim().trim_matches('"').to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn windows_to_wsl_path(path: &str) -> PathBuf {
+    // .wslconfig paths are escaped Windows paths like C:\\Users\\me\\bzImage
+    let path = path.replace("\\\\", "/").replace('\\', "/");
+
+    match path.split_once(":/") {
+        Some((drive, rest)) if drive.len() == 1 => {
+            PathBuf::from(format!("/mnt/{}/{}", drive.to_lowercase(), rest))
+        }
+        _ => PathBuf::from(path),
+    }
+}